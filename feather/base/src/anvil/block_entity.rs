@@ -140,6 +140,17 @@ pub enum BlockEntityKind {
         #[serde(default)]
         record_item: InventorySlot,
     },
+    #[serde(rename = "minecraft:sign")]
+    #[serde(rename_all = "PascalCase")]
+    Sign {
+        /// Lines of text, as JSON text components.
+        text1: String,
+        text2: String,
+        text3: String,
+        text4: String,
+        #[serde(default = "BlockEntityKind::default_sign_color")]
+        color: String,
+    },
     // TODO: a few more
     /// Fallback type for unknown block entities
     #[serde(other, serialize_with = "BlockEntityKind::serialize_unknown")]
@@ -151,6 +162,10 @@ impl BlockEntityKind {
         Err(S::Error::custom("cannot serialize unknown block entities"))
     }
 
+    fn default_sign_color() -> String {
+        "black".to_owned()
+    }
+
     pub fn variant(&self) -> BlockEntityVariant {
         match self {
             BlockEntityKind::Beacon { .. } => BlockEntityVariant::Beacon,
@@ -171,6 +186,7 @@ impl BlockEntityKind {
             BlockEntityKind::Hopper { .. } => BlockEntityVariant::Hopper,
             BlockEntityKind::Jigsaw { .. } => BlockEntityVariant::Jigsaw,
             BlockEntityKind::Jukebox { .. } => BlockEntityVariant::Jukebox,
+            BlockEntityKind::Sign { .. } => BlockEntityVariant::Sign,
             BlockEntityKind::Unknown { .. } => BlockEntityVariant::Unknown,
         }
     }
@@ -197,5 +213,6 @@ pub enum BlockEntityVariant {
     Hopper,
     Jigsaw,
     Jukebox,
+    Sign,
     Unknown,
 }
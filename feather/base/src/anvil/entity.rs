@@ -1,5 +1,5 @@
 use arrayvec::ArrayVec;
-use generated::{Item, ItemStack};
+use generated::{Enchantment, EnchantmentKind, Item, ItemStack};
 use serde::ser::Error;
 use serde::{Deserialize, Serialize, Serializer};
 use thiserror::Error;
@@ -64,7 +64,7 @@ pub enum EntityData {
     Llama(AnimalData),
     #[serde(rename = "minecraft:mooshroom")]
     Mooshroom(AnimalData),
-    #[serde(rename = "minecraft:rabbit}")]
+    #[serde(rename = "minecraft:rabbit")]
     Rabbit(AnimalData),
     #[serde(rename = "minecraft:squid")]
     Squid(AnimalData),
@@ -231,7 +231,20 @@ where
 pub struct ItemNbt {
     #[serde(rename = "Damage")]
     pub damage: Option<i32>,
-    // TODO enchantments, display name, ...
+    #[serde(
+        rename = "Enchantments",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub enchantments: Vec<EnchantmentNbt>,
+    // TODO display name, ...
+}
+
+/// An enchantment in an item's NBT.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EnchantmentNbt {
+    pub id: String,
+    pub lvl: i16,
 }
 
 impl ItemNbt {
@@ -241,6 +254,15 @@ impl ItemNbt {
             count: count as u32,
             item,
             damage: nbt.as_ref().map(|n| n.damage).flatten().map(|x| x as u32),
+            enchantments: nbt
+                .iter()
+                .flat_map(|n| n.enchantments.iter())
+                .filter_map(|enchantment| {
+                    // Unknown enchantments are dropped
+                    let kind = EnchantmentKind::from_name(&enchantment.id)?;
+                    Some(Enchantment::new(kind, enchantment.lvl.max(0) as u16))
+                })
+                .collect(),
         }
     }
 }
@@ -253,6 +275,14 @@ where
         let stack = s.borrow();
         Self {
             damage: stack.damage.map(|d| d as i32),
+            enchantments: stack
+                .enchantments
+                .iter()
+                .map(|enchantment| EnchantmentNbt {
+                    id: format!("minecraft:{}", enchantment.kind.name()),
+                    lvl: enchantment.level.min(i16::MAX as u16) as i16,
+                })
+                .collect(),
         }
     }
 }
@@ -1,9 +1,36 @@
 //! Implements level.dat file loading.
 
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use generated::{Biome, Item};
+use libcraft_core::GameRules;
 use serde::{Deserialize, Serialize};
-use std::io::{Cursor, Read, Write};
+use std::io::{self, Cursor, Read, Write};
+use std::path::Path;
 use std::{collections::HashMap, fs::File};
+use thiserror::Error;
+
+use super::region::DATA_VERSION;
+use crate::CHUNK_HEIGHT;
+
+/// An error encountered while loading a level file.
+#[derive(Debug, Error)]
+pub enum LevelLoadError {
+    /// The file couldn't be read.
+    #[error("failed to read level file: {0}")]
+    Io(#[from] io::Error),
+    /// The file isn't valid NBT, or is missing fields.
+    #[error("failed to decode level file: {0}")]
+    Nbt(#[from] nbt::Error),
+    /// The level was saved by a newer version of the game.
+    #[error(
+        "level file has data version {0}, but the newest supported version is {}",
+        DATA_VERSION
+    )]
+    UnsupportedVersion(i32),
+    /// A field of the level has an impossible value.
+    #[error("level file has an invalid `{0}` field")]
+    Corrupt(&'static str),
+}
 
 /// Root level tag
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +57,19 @@ pub struct LevelData {
     pub border_safe_zone: f64,
     #[serde(rename = "BorderSize")]
     pub border_size: f64,
+    #[serde(default)]
+    #[serde(rename = "BorderSizeLerpTarget")]
+    pub border_size_lerp_target: f64,
+    /// Milliseconds left until the border reaches its target size.
+    #[serde(default)]
+    #[serde(rename = "BorderSizeLerpTime")]
+    pub border_size_lerp_time: i64,
+    #[serde(default)]
+    #[serde(rename = "BorderWarningBlocks")]
+    pub border_warning_blocks: f64,
+    #[serde(default)]
+    #[serde(rename = "BorderWarningTime")]
+    pub border_warning_time: f64,
 
     #[serde(rename = "clearWeatherTime")]
     pub clear_weather_time: i32,
@@ -74,25 +114,170 @@ pub struct LevelData {
     pub generator_name: String,
     #[serde(rename = "generatorOptions")]
     pub generator_options: Option<SuperflatGeneratorOptions>,
+
+    /// Game rules in their string form, e.g. `"keepInventory": "false"`.
+    #[serde(rename = "GameRules", default)]
+    pub game_rules: HashMap<String, String>,
+
+    #[serde(rename = "LevelName", default, skip_serializing_if = "Option::is_none")]
+    pub level_name: Option<String>,
+    #[serde(rename = "DataPacks", default, skip_serializing_if = "Option::is_none")]
+    pub data_packs: Option<DataPacks>,
+    /// Only present in levels saved by 1.16 and newer.
+    #[serde(
+        rename = "WorldGenSettings",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub world_gen_settings: Option<WorldGenSettings>,
+    /// Only present in levels saved by 1.16 and newer.
+    #[serde(
+        rename = "DragonFight",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub dragon_fight: Option<DragonFight>,
+
+    /// Fields not covered above, including unknown fields
+    /// of nested compounds. They are written back unchanged
+    /// when saving, so that no data is lost.
+    #[serde(skip)]
+    pub unknown_fields: HashMap<String, nbt::Value>,
 }
 
 impl LevelData {
-    pub fn load_from_file(file: &mut File) -> anyhow::Result<Self> {
+    /// Loads the level file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LevelLoadError> {
+        Self::load_from_file(&mut File::open(path)?)
+    }
+
+    pub fn load_from_file(file: &mut File) -> Result<Self, LevelLoadError> {
         let mut buf = vec![];
         file.read_to_end(&mut buf)?;
-
-        nbt::from_gzip_reader::<_, Root>(Cursor::new(&buf))
-            .map_err(Into::into)
-            .map(|root| root.data)
+        deserialize_level_file(&buf)
     }
 
     pub fn save_to_file(&self, file: &mut File) -> anyhow::Result<()> {
-        let mut buf = vec![];
-        nbt::to_gzip_writer(&mut buf, &Root { data: self.clone() }, None)?;
-
-        file.write_all(&buf)?;
+        file.write_all(&self.serialize_level_file()?)?;
         Ok(())
     }
+
+    /// Encodes this level as the contents of a gzip-compressed level file.
+    fn serialize_level_file(&self) -> anyhow::Result<Vec<u8>> {
+        let mut data = self.typed_fields()?;
+        merge_unknown_fields(&mut data, &self.unknown_fields);
+
+        let mut root = HashMap::new();
+        root.insert("Data".to_owned(), nbt::Value::Compound(data));
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        write_root(&mut encoder, &nbt::Value::Compound(root))?;
+        Ok(encoder.finish()?)
+    }
+
+    /// Encodes the fields of this struct as an NBT compound.
+    fn typed_fields(&self) -> nbt::Result<HashMap<String, nbt::Value>> {
+        let mut buf = Vec::new();
+        nbt::to_writer(&mut buf, &Root { data: self.clone() }, None)?;
+        let data = match read_root(&mut Cursor::new(buf))? {
+            nbt::Value::Compound(mut root) => root.remove("Data"),
+            _ => None,
+        };
+        match data {
+            Some(nbt::Value::Compound(data)) => Ok(data),
+            _ => unreachable!("`Root` always serializes to a compound"),
+        }
+    }
+}
+
+/// Decodes the contents of a gzip-compressed level file.
+fn deserialize_level_file(bytes: &[u8]) -> Result<LevelData, LevelLoadError> {
+    let mut level = nbt::from_gzip_reader::<_, Root>(Cursor::new(bytes))?.data;
+
+    if level.data_version > DATA_VERSION {
+        return Err(LevelLoadError::UnsupportedVersion(level.data_version));
+    }
+    if !level.border_size.is_finite() || level.border_size < 0. {
+        return Err(LevelLoadError::Corrupt("BorderSize"));
+    }
+    if !(0..CHUNK_HEIGHT as i32).contains(&level.spawn_y) {
+        return Err(LevelLoadError::Corrupt("SpawnY"));
+    }
+    if !(0..=3).contains(&level.game_type) {
+        return Err(LevelLoadError::Corrupt("GameType"));
+    }
+
+    // Serde can't keep the tag types of unknown values,
+    // so decode the file again without it.
+    let data = match read_root(&mut GzDecoder::new(bytes))? {
+        nbt::Value::Compound(mut root) => root.remove("Data"),
+        _ => None,
+    };
+    match data {
+        Some(nbt::Value::Compound(data)) => {
+            level.unknown_fields = find_unknown_fields(data, &level.typed_fields()?)
+        }
+        _ => return Err(LevelLoadError::Corrupt("Data")),
+    }
+    Ok(level)
+}
+
+/// Reads a named root tag, discarding its name.
+fn read_root(src: &mut impl Read) -> nbt::Result<nbt::Value> {
+    let mut id = [0; 1];
+    src.read_exact(&mut id)?;
+    // The name is encoded like the payload of a string tag
+    nbt::Value::from_reader(0x08, src)?;
+    nbt::Value::from_reader(id[0], src)
+}
+
+/// Writes `root` as a root tag with an empty name.
+fn write_root(dst: &mut impl Write, root: &nbt::Value) -> nbt::Result<()> {
+    dst.write_all(&[root.id()])?;
+    nbt::Value::String(String::new()).to_writer(dst)?;
+    root.to_writer(dst)
+}
+
+/// Returns the entries of `fields` missing from `known`,
+/// recursing into compounds present in both.
+fn find_unknown_fields(
+    fields: HashMap<String, nbt::Value>,
+    known: &HashMap<String, nbt::Value>,
+) -> HashMap<String, nbt::Value> {
+    let mut unknown = HashMap::new();
+    for (name, value) in fields {
+        match (value, known.get(&name)) {
+            (value, None) => {
+                unknown.insert(name, value);
+            }
+            (nbt::Value::Compound(fields), Some(nbt::Value::Compound(known))) => {
+                let fields = find_unknown_fields(fields, known);
+                if !fields.is_empty() {
+                    unknown.insert(name, nbt::Value::Compound(fields));
+                }
+            }
+            _ => {}
+        }
+    }
+    unknown
+}
+
+/// Adds the entries of `unknown` to `fields`. Existing
+/// values take precedence over unknown ones.
+fn merge_unknown_fields(
+    fields: &mut HashMap<String, nbt::Value>,
+    unknown: &HashMap<String, nbt::Value>,
+) {
+    for (name, value) in unknown {
+        match (fields.get_mut(name), value) {
+            (None, value) => {
+                fields.insert(name.clone(), value.clone());
+            }
+            (Some(nbt::Value::Compound(fields)), nbt::Value::Compound(unknown)) => {
+                merge_unknown_fields(fields, unknown)
+            }
+            _ => {}
+        }
+    }
 }
 
 /// Represents level version data.
@@ -104,6 +289,40 @@ pub struct LevelVersion {
     name: String,
 }
 
+/// The data packs of a level.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct DataPacks {
+    #[serde(rename = "Enabled", default)]
+    pub enabled: Vec<String>,
+    #[serde(rename = "Disabled", default)]
+    pub disabled: Vec<String>,
+}
+
+/// World generation settings of levels saved by 1.16 and newer.
+///
+/// The generators of each dimension are kept
+/// in [`LevelData::unknown_fields`].
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct WorldGenSettings {
+    pub seed: i64,
+    #[serde(default)]
+    pub generate_features: bool,
+    #[serde(default)]
+    pub bonus_chest: bool,
+}
+
+/// State of the fight against the ender dragon.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct DragonFight {
+    #[serde(rename = "DragonKilled", default)]
+    pub dragon_killed: bool,
+    #[serde(rename = "PreviouslyKilled", default)]
+    pub previously_killed: bool,
+    /// Angles of the end gateways yet to be spawned.
+    #[serde(rename = "Gateways", default)]
+    pub gateways: Vec<i32>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SuperflatGeneratorOptions {
     pub structures: HashMap<String, nbt::Value>,
@@ -159,6 +378,17 @@ pub enum LevelGeneratorType {
 }
 
 impl LevelData {
+    /// Parses the game rules stored in this level.
+    ///
+    /// Unknown rules and invalid values are ignored.
+    pub fn game_rules(&self) -> GameRules {
+        let mut rules = GameRules::default();
+        for (name, value) in &self.game_rules {
+            let _ = rules.set(name, value);
+        }
+        rules
+    }
+
     pub fn generator_type(&self) -> LevelGeneratorType {
         match self.generator_name.to_lowercase().as_str() {
             "default" => LevelGeneratorType::Default,
@@ -175,13 +405,10 @@ impl LevelData {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::Cursor;
 
     #[test]
     fn test_deserialize_level_file() {
-        let cursor = Cursor::new(include_bytes!("level.dat").to_vec());
-
-        let level = nbt::from_gzip_reader::<_, Root>(cursor).unwrap().data;
+        let level = deserialize_level_file(include_bytes!("level.dat")).unwrap();
 
         assert!(!level.allow_commands);
         assert_eq!(level.clear_weather_time, 0);
@@ -202,5 +429,102 @@ mod tests {
         assert_eq!(level.thunder_time, 5252);
         assert_eq!(level.generator_name, "default");
         assert!(level.generator_options.is_none());
+
+        assert_eq!(level.level_name.as_deref(), Some("world"));
+        let data_packs = level.data_packs.as_ref().unwrap();
+        assert_eq!(data_packs.enabled, vec!["vanilla", "file/bukkit"]);
+        assert!(data_packs.disabled.is_empty());
+        assert!(level.world_gen_settings.is_none());
+        assert!(level.dragon_fight.is_none());
+
+        let rules = level.game_rules();
+        assert!(!rules.do_daylight_cycle);
+        assert!(!rules.keep_inventory);
+        assert_eq!(rules.random_tick_speed, 3);
+    }
+
+    #[test]
+    fn unknown_fields_are_preserved() {
+        let bytes = include_bytes!("level.dat");
+        let level = deserialize_level_file(bytes).unwrap();
+        for name in &["Bukkit.Version", "SizeOnDisk", "DimensionData", "Version"] {
+            assert!(level.unknown_fields.contains_key(*name), "{}", name);
+        }
+        assert!(!level.unknown_fields.contains_key("LevelName"));
+
+        let original = read_data(bytes);
+        let saved = read_data(&level.serialize_level_file().unwrap());
+        assert_eq!(saved, original);
+        // Compounds are unordered, so only compare the encoding of other values
+        for name in level.unknown_fields.keys() {
+            if !matches!(original[name], nbt::Value::Compound(_)) {
+                assert_eq!(
+                    encode_value(&saved[name]),
+                    encode_value(&original[name]),
+                    "{}",
+                    name
+                );
+            }
+        }
+    }
+
+    fn read_data(bytes: &[u8]) -> HashMap<String, nbt::Value> {
+        match read_root(&mut GzDecoder::new(bytes)).unwrap() {
+            nbt::Value::Compound(mut root) => match root.remove("Data") {
+                Some(nbt::Value::Compound(data)) => data,
+                data => panic!("unexpected data {:?}", data),
+            },
+            root => panic!("unexpected root {:?}", root),
+        }
+    }
+
+    fn encode_value(value: &nbt::Value) -> Vec<u8> {
+        let mut buf = Vec::new();
+        value.to_writer(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn truncated_level_file() {
+        let bytes = include_bytes!("level.dat");
+        let error = deserialize_level_file(&bytes[..bytes.len() / 2]).unwrap_err();
+        assert!(matches!(error, LevelLoadError::Nbt(_)), "{:?}", error);
+    }
+
+    #[test]
+    fn missing_level_file() {
+        let error = LevelData::load("this/level.dat/does/not/exist").unwrap_err();
+        assert!(matches!(error, LevelLoadError::Io(_)), "{:?}", error);
+    }
+
+    #[test]
+    fn invalid_level_data() {
+        let mut level = deserialize_level_file(include_bytes!("level.dat")).unwrap();
+
+        level.data_version = DATA_VERSION + 1;
+        let error = deserialize_level_file(&encode(&level)).unwrap_err();
+        assert!(matches!(error, LevelLoadError::UnsupportedVersion(v) if v == DATA_VERSION + 1));
+
+        level.data_version = DATA_VERSION;
+        level.spawn_y = -5;
+        let error = deserialize_level_file(&encode(&level)).unwrap_err();
+        assert!(
+            matches!(error, LevelLoadError::Corrupt("SpawnY")),
+            "{:?}",
+            error
+        );
+    }
+
+    fn encode(level: &LevelData) -> Vec<u8> {
+        let mut buf = Vec::new();
+        nbt::to_gzip_writer(
+            &mut buf,
+            &Root {
+                data: level.clone(),
+            },
+            None,
+        )
+        .unwrap();
+        buf
     }
 }
@@ -142,6 +142,11 @@ pub fn load_player_data(world_dir: &Path, uuid: Uuid) -> Result<PlayerData, nbt:
     Ok(data)
 }
 
+/// Returns whether data was saved for the player with `uuid`.
+pub fn player_data_exists(world_dir: &Path, uuid: Uuid) -> bool {
+    file_path(world_dir, uuid).exists()
+}
+
 pub fn save_player_data(
     world_dir: &Path,
     uuid: Uuid,
@@ -153,6 +158,18 @@ pub fn save_player_data(
     nbt::to_gzip_writer(&mut file, data, None).map_err(anyhow::Error::from)
 }
 
+/// Serializes player data as stored in player data files.
+pub fn serialize_player_data(data: &PlayerData) -> Result<Vec<u8>, anyhow::Error> {
+    let mut buf = Vec::new();
+    nbt::to_gzip_writer(&mut buf, data, None)?;
+    Ok(buf)
+}
+
+/// Deserializes player data stored by [`serialize_player_data`].
+pub fn deserialize_player_data(buf: &[u8]) -> Result<PlayerData, nbt::Error> {
+    nbt::from_gzip_reader(buf)
+}
+
 fn file_path(world_dir: &Path, uuid: Uuid) -> PathBuf {
     world_dir.join("playerdata").join(format!("{}.dat", uuid))
 }
@@ -10,6 +10,7 @@ use super::{block_entity::BlockEntityData, entity::EntityData};
 use bitvec::{bitvec, vec::BitVec};
 use blocks::BlockId;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use flate2::{write::ZlibEncoder, Compression};
 use generated::Biome;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
@@ -27,11 +28,15 @@ const REGION_SIZE: usize = 32;
 
 /// The data version supported by this code, currently corresponding
 /// to 1.16.5.
-const DATA_VERSION: i32 = 2586;
+pub(crate) const DATA_VERSION: i32 = 2586;
 
 /// Length, in bytes, of a sector.
 const SECTOR_BYTES: usize = 4096;
 
+/// The zlib compression level of saved chunks,
+/// unless changed with [`RegionHandle::set_compression_level`].
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 6;
+
 /// Represents the data for a chunk after the "Chunk [x, y]" tag.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
@@ -147,6 +152,8 @@ pub struct RegionHandle {
     header: RegionHeader,
     /// Sector allocator to allocate sectors where we can store chunks.
     allocator: SectorAllocator,
+    /// The zlib compression level of saved chunks.
+    compression: Compression,
 }
 
 impl RegionHandle {
@@ -194,53 +201,14 @@ impl RegionHandle {
         }
 
         if len == 0 {
-            return Err(Error::ChunkTooLarge(0));
+            return Err(Error::EmptyChunk);
         }
 
         // Read `len` bytes into memory.
         let mut buf = vec![0u8; len as usize];
         self.file.read_exact(&mut buf).map_err(Error::Io)?;
 
-        // The compression type is indicated by a byte.
-        // 1 corresponds to gzip compression, while 2
-        // corresponds to zlib.
-        let compression_type = buf[0];
-
-        // Parse NBT data
-        let cursor = Cursor::new(&buf[1..]);
-        let mut root: ChunkRoot = match compression_type {
-            1 => nbt::from_gzip_reader(cursor).map_err(Error::Nbt)?,
-            2 => nbt::from_zlib_reader(cursor).map_err(Error::Nbt)?,
-            _ => return Err(Error::InvalidCompression(compression_type)),
-        };
-
-        // Check data version
-        if root.data_version != DATA_VERSION {
-            return Err(Error::UnsupportedDataVersion(root.data_version));
-        }
-
-        let level = &mut root.level;
-
-        let mut chunk = Chunk::new(original_pos);
-
-        // Read sections
-        for section in &mut level.sections {
-            read_section_into_chunk(section, &mut chunk)?;
-        }
-
-        // Read biomes
-        if level.biomes.len() != 1024 {
-            return Err(Error::IndexOutOfBounds);
-        }
-        for index in 0..1024 {
-            let id = level.biomes[index];
-            chunk.biomes_mut().as_slice_mut()[index] =
-                Biome::from_id(id as u32).ok_or(Error::InvalidBiomeId(id))?;
-        }
-
-        // chunk.recalculate_heightmap();
-
-        Ok((chunk, level.entities.clone(), level.block_entities.clone()))
+        deserialize_chunk(original_pos, &buf)
     }
 
     /// Checks if the specified chunk position is generated in this region.
@@ -278,10 +246,7 @@ impl RegionHandle {
         let root = chunk_to_chunk_root(chunk, entities, block_entities);
 
         // Write to intermediate buffer, because we need to know the length.
-        let mut buf = Vec::with_capacity(4096);
-        buf.write_u8(2).map_err(Error::Io)?; // Compression type: zlib
-
-        nbt::to_zlib_writer(&mut buf, &root, None).map_err(Error::Nbt)?;
+        let buf = encode_chunk(&root, self.compression)?;
 
         let total_len = buf.len() + 4; // 4 bytes for length header
 
@@ -314,6 +279,12 @@ impl RegionHandle {
         Ok(())
     }
 
+    /// Sets the zlib compression level, from 0 to 9,
+    /// of chunks saved from now on.
+    pub fn set_compression_level(&mut self, level: u32) {
+        self.compression = Compression::new(level);
+    }
+
     fn save_header(&mut self) -> Result<(), io::Error> {
         self.file.seek(SeekFrom::Start(0))?;
 
@@ -321,6 +292,78 @@ impl RegionHandle {
     }
 }
 
+/// Serializes a chunk along with its entities and block entities
+/// as stored in region files: a compression type byte followed
+/// by NBT compressed at the zlib `compression_level`.
+pub fn serialize_chunk(
+    chunk: &Chunk,
+    entities: &[EntityData],
+    block_entities: &[BlockEntityData],
+    compression_level: u32,
+) -> Result<Vec<u8>, Error> {
+    let root = chunk_to_chunk_root(chunk, entities, block_entities);
+    encode_chunk(&root, Compression::new(compression_level))
+}
+
+/// Deserializes a chunk at `pos` stored by [`serialize_chunk`]
+/// or read from a region file.
+pub fn deserialize_chunk(
+    pos: ChunkPosition,
+    buf: &[u8],
+) -> Result<(Chunk, Vec<EntityData>, Vec<BlockEntityData>), Error> {
+    // The compression type is indicated by a byte.
+    // 1 corresponds to gzip compression, while 2
+    // corresponds to zlib.
+    let compression_type = *buf.get(0).ok_or(Error::EmptyChunk)?;
+
+    // Parse NBT data
+    let cursor = Cursor::new(&buf[1..]);
+    let mut root: ChunkRoot = match compression_type {
+        1 => nbt::from_gzip_reader(cursor).map_err(Error::Nbt)?,
+        2 => nbt::from_zlib_reader(cursor).map_err(Error::Nbt)?,
+        _ => return Err(Error::InvalidCompression(compression_type)),
+    };
+
+    // Check data version
+    if root.data_version != DATA_VERSION {
+        return Err(Error::UnsupportedDataVersion(root.data_version));
+    }
+
+    let level = &mut root.level;
+
+    let mut chunk = Chunk::new(pos);
+
+    // Read sections
+    for section in &mut level.sections {
+        read_section_into_chunk(section, &mut chunk)?;
+    }
+
+    // Read biomes
+    if level.biomes.len() != 1024 {
+        return Err(Error::IndexOutOfBounds);
+    }
+    for index in 0..1024 {
+        let id = level.biomes[index];
+        chunk.biomes_mut().as_slice_mut()[index] =
+            Biome::from_id(id as u32).ok_or(Error::InvalidBiomeId(id))?;
+    }
+
+    // chunk.recalculate_heightmap();
+
+    Ok((chunk, level.entities.clone(), level.block_entities.clone()))
+}
+
+/// Serializes a chunk as stored in region files:
+/// a compression type byte followed by zlib-compressed NBT.
+fn encode_chunk(root: &ChunkRoot, compression: Compression) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::with_capacity(4096);
+    buf.write_u8(2).map_err(Error::Io)?; // Compression type: zlib
+
+    let mut encoder = ZlibEncoder::new(buf, compression);
+    nbt::to_writer(&mut encoder, root, None).map_err(Error::Nbt)?;
+    encoder.finish().map_err(Error::Io)
+}
+
 fn read_section_into_chunk(section: &mut LevelSection, chunk: &mut Chunk) -> Result<(), Error> {
     let data = &section.states;
 
@@ -593,6 +636,8 @@ pub enum Error {
     Nbt(nbt::Error),
     /// The chunk was too large
     ChunkTooLarge(usize),
+    /// The chunk contained no data
+    EmptyChunk,
     /// The chunk contained an invalid compression type
     InvalidCompression(u8),
     /// An IO error occurred
@@ -622,6 +667,7 @@ impl Display for Error {
             Error::ChunkTooLarge(size) => {
                 f.write_str(&format!("Chunk is too large: {} bytes", size))?
             }
+            Error::EmptyChunk => f.write_str("Chunk is empty")?,
             Error::InvalidCompression(id) => {
                 f.write_str(&format!("Chunk uses invalid compression type {}", id))?
             }
@@ -671,6 +717,7 @@ pub fn load_region(dir: &Path, pos: RegionPosition) -> Result<RegionHandle, Erro
         file,
         header,
         allocator,
+        compression: Compression::new(DEFAULT_COMPRESSION_LEVEL),
     })
 }
 
@@ -702,6 +749,7 @@ pub fn create_region(dir: &Path, pos: RegionPosition) -> Result<RegionHandle, Er
         file,
         header,
         allocator,
+        compression: Compression::new(DEFAULT_COMPRESSION_LEVEL),
     })
 }
 
@@ -868,8 +916,69 @@ impl RegionPosition {
 
 #[cfg(test)]
 mod tests {
+    use tempfile::TempDir;
+
     use super::*;
 
+    /// Creates a chunk whose rows of blocks are picked from
+    /// a few patterns, like the words of a text.
+    fn sample_chunk(pos: ChunkPosition) -> Chunk {
+        let blocks = [
+            BlockId::air(),
+            BlockId::stone(),
+            BlockId::dirt(),
+            BlockId::iron_ore(),
+            BlockId::grass_block(),
+        ];
+        let mut chunk = Chunk::new(pos);
+        let mut seed: u32 = 1;
+        for y in 0..64 {
+            for z in 0..16 {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                let pattern = (seed >> 16) as usize % 8;
+                for x in 0..16 {
+                    let block = blocks[(pattern * 7 + x * 3) % blocks.len()];
+                    chunk.set_block_at(x, y, z, block);
+                }
+            }
+        }
+        chunk
+    }
+
+    #[test]
+    fn compression_levels() {
+        let pos = ChunkPosition::new(0, 0);
+        let chunk = sample_chunk(pos);
+        let root = chunk_to_chunk_root(&chunk, &[], &[]);
+        let fast = encode_chunk(&root, Compression::new(1)).unwrap();
+        let best = encode_chunk(&root, Compression::new(9)).unwrap();
+        assert!(best.len() < fast.len());
+
+        let dir = TempDir::new().unwrap();
+        for level in [1, 9].iter().copied() {
+            let region = RegionPosition::from_chunk(pos);
+            let mut handle = create_region(dir.path(), region).unwrap();
+            handle.set_compression_level(level);
+            handle.save_chunk(&chunk, &[], &[]).unwrap();
+
+            let mut handle = load_region(dir.path(), region).unwrap();
+            let (loaded, _, _) = handle.load_chunk(pos).unwrap();
+            for x in 0..16 {
+                for y in 0..80 {
+                    for z in 0..16 {
+                        assert_eq!(loaded.block_at(x, y, z), chunk.block_at(x, y, z));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty_chunks_are_rejected() {
+        let result = deserialize_chunk(ChunkPosition::new(0, 0), &[]);
+        assert!(matches!(result, Err(Error::EmptyChunk)));
+    }
+
     #[test]
     fn test_sector_allocator() {
         let header = RegionHeader {
@@ -207,7 +207,7 @@ impl Chunk {
 
     pub fn set_block_light_at(&mut self, x: usize, y: usize, z: usize, light: u8) -> Option<()> {
         if let Some(section) = self.section_for_y_mut(y)? {
-            section.set_block_light_at(x, y, z, light)
+            section.set_block_light_at(x, y % SECTION_HEIGHT, z, light)
         } else {
             Some(())
         }
@@ -215,7 +215,7 @@ impl Chunk {
 
     pub fn set_sky_light_at(&mut self, x: usize, y: usize, z: usize, light: u8) -> Option<()> {
         if let Some(section) = self.section_for_y_mut(y)? {
-            section.set_sky_light_at(x, y, z, light)
+            section.set_sky_light_at(x, y % SECTION_HEIGHT, z, light)
         } else {
             Some(())
         }
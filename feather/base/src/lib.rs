@@ -18,11 +18,16 @@ pub mod metadata;
 pub use blocks::*;
 pub use chunk::{Chunk, ChunkSection, CHUNK_HEIGHT, CHUNK_WIDTH};
 pub use chunk_lock::*;
-pub use generated::{Area, Biome, EntityKind, Inventory, Item, ItemStack};
+pub use generated::{
+    Area, Biome, Enchantment, EnchantmentKind, EntityKind, Inventory, Item, ItemStack,
+};
 pub use libcraft_blocks::{BlockKind, BlockState};
-pub use libcraft_core::{position, vec3, BlockPosition, ChunkPosition, Gamemode, Position, Vec3d};
+pub use libcraft_core::{
+    position, vec3, BlockPosition, ChunkPosition, Difficulty, GameRuleError, GameRuleValue,
+    GameRules, Gamemode, Position, Vec3d,
+};
 pub use libcraft_particles::{Particle, ParticleKind};
-pub use libcraft_text::{deserialize_text, Text, Title};
+pub use libcraft_text::{deserialize_text, Color, Text, Title};
 #[doc(inline)]
 pub use metadata::EntityMetadata;
 
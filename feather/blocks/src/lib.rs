@@ -9,8 +9,11 @@ mod directions;
 #[allow(warnings)]
 #[allow(clippy::all)]
 mod generated;
+mod parse;
 mod wall_blocks;
 
+pub use parse::{parse_block_state, BlockParseError};
+
 static BLOCK_TABLE: Lazy<BlockTable> = Lazy::new(|| {
     let bytes = include_bytes!("generated/table.dat");
     bincode::deserialize(bytes).expect("failed to deserialize generated block table (bincode)")
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use thiserror::Error;
+
+use crate::BlockId;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BlockParseError {
+    #[error("unknown block '{0}'")]
+    UnknownBlock(String),
+    #[error("block '{block}' has no property '{property}'")]
+    UnknownProperty { block: String, property: String },
+    #[error("invalid value '{value}' for property '{property}' of block '{block}'")]
+    InvalidValue {
+        block: String,
+        property: String,
+        value: String,
+    },
+    #[error("malformed block state '{0}'")]
+    Malformed(String),
+}
+
+/// Parses a block state in the format used by commands,
+/// e.g. `minecraft:oak_stairs[facing=east,half=top]`.
+///
+/// The namespace defaults to `minecraft`. Properties which
+/// are not specified take the value of the block's default state.
+pub fn parse_block_state(s: &str) -> Result<BlockId, BlockParseError> {
+    let malformed = || BlockParseError::Malformed(s.to_owned());
+    let (name, properties) = match s.find('[') {
+        Some(start) => {
+            let properties = s[start + 1..].strip_suffix(']').ok_or_else(malformed)?;
+            (&s[..start], Some(properties))
+        }
+        None => (s, None),
+    };
+    let identifier = if name.contains(':') {
+        name.to_owned()
+    } else {
+        format!("minecraft:{}", name)
+    };
+
+    let default = BlockId::from_identifier(&identifier)
+        .ok_or_else(|| BlockParseError::UnknownBlock(name.to_owned()))?;
+    let mut map = default_map(default);
+
+    for property in properties
+        .into_iter()
+        .flat_map(|properties| properties.split(','))
+        .map(str::trim)
+        .filter(|property| !property.is_empty())
+    {
+        let mut split = property.splitn(2, '=');
+        let (property, value) = match (split.next(), split.next()) {
+            (Some(property), Some(value)) => (property.trim(), value.trim()),
+            _ => return Err(malformed()),
+        };
+        match map.get_mut(property) {
+            Some(old_value) => *old_value = value.to_owned(),
+            None => {
+                return Err(BlockParseError::UnknownProperty {
+                    block: name.to_owned(),
+                    property: property.to_owned(),
+                })
+            }
+        }
+        // Check each value on its own so the
+        // error can point at the offending property.
+        let mut single = default_map(default);
+        single.insert(property.to_owned(), value.to_owned());
+        if BlockId::from_identifier_and_properties(&identifier, &single).is_none() {
+            return Err(BlockParseError::InvalidValue {
+                block: name.to_owned(),
+                property: property.to_owned(),
+                value: value.to_owned(),
+            });
+        }
+    }
+
+    BlockId::from_identifier_and_properties(&identifier, &map).ok_or_else(malformed)
+}
+
+fn default_map(block: BlockId) -> BTreeMap<String, String> {
+    block
+        .to_properties_map()
+        .into_iter()
+        .map(|(property, value)| (property.to_owned(), value.to_owned()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FacingCardinal, HalfTopBottom};
+
+    #[test]
+    fn parse_stateful_block() {
+        let block = parse_block_state("minecraft:oak_stairs[facing=east, half=top]").unwrap();
+        assert_eq!(
+            block,
+            BlockId::oak_stairs()
+                .with_facing_cardinal(FacingCardinal::East)
+                .with_half_top_bottom(HalfTopBottom::Top)
+        );
+        assert_eq!(parse_block_state("oak_stairs"), Ok(BlockId::oak_stairs()));
+        assert_eq!(parse_block_state("stone[]"), Ok(BlockId::stone()));
+    }
+
+    #[test]
+    fn parse_unknown_block() {
+        assert_eq!(
+            parse_block_state("minecraft:not_a_block"),
+            Err(BlockParseError::UnknownBlock(
+                "minecraft:not_a_block".to_owned()
+            ))
+        );
+        assert!(matches!(
+            parse_block_state("stone[facing=east]"),
+            Err(BlockParseError::UnknownProperty { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_illegal_property_value() {
+        assert_eq!(
+            parse_block_state("oak_stairs[facing=up]"),
+            Err(BlockParseError::InvalidValue {
+                block: "oak_stairs".to_owned(),
+                property: "facing".to_owned(),
+                value: "up".to_owned(),
+            })
+        );
+        assert!(matches!(
+            parse_block_state("redstone_wire[power=16]"),
+            Err(BlockParseError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            parse_block_state("oak_stairs[facing=east"),
+            Err(BlockParseError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,470 @@
+//! Block entities: data attached to a block that doesn't
+//! fit in its block state, like the items in a chest
+//! or the text of a sign.
+//!
+//! Block entities live in the [`BlockEntities`] store of the
+//! [`World`](crate::World) and are saved to the `TileEntities`
+//! list of their chunk.
+
+use ahash::AHashMap;
+use base::{
+    anvil::{
+        block_entity::{BlockEntityBase, BlockEntityData, BlockEntityKind},
+        player::InventorySlot,
+    },
+    Area, BlockId, BlockPosition, ChunkPosition, Inventory, ItemStack, Text,
+};
+use blocks::{BlockKind, SimplifiedBlockKind};
+
+/// A block entity.
+#[derive(Debug, Clone)]
+pub enum BlockEntity {
+    Chest(Inventory),
+    Furnace {
+        inventory: Inventory,
+        burn_time: i16,
+        cook_time: i16,
+        cook_time_total: i16,
+    },
+    Sign(Sign),
+    CommandBlock(CommandBlock),
+}
+
+/// The text written on a sign.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sign {
+    pub lines: [Text; 4],
+    /// The name of the dye color of the text,
+    /// e.g. `"black"` or `"light_blue"`.
+    pub color: String,
+}
+
+impl Default for Sign {
+    fn default() -> Self {
+        Self {
+            lines: [Text::empty(), Text::empty(), Text::empty(), Text::empty()],
+            color: "black".to_owned(),
+        }
+    }
+}
+
+/// The state of a command block. Whether it is an impulse,
+/// chain or repeating command block is stored in its block.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandBlock {
+    pub command: String,
+    pub custom_name: Option<String>,
+    /// Whether the command block runs without redstone power.
+    pub auto: bool,
+    /// Whether the command block receives redstone power.
+    /// Updated when blocks next to it change.
+    pub powered: bool,
+    /// Whether `last_output` is updated when the command runs.
+    pub track_output: bool,
+    /// JSON text of the last command feedback.
+    pub last_output: String,
+    /// 1 if the command last succeeded, 0 otherwise.
+    pub success_count: i32,
+    /// Whether the previous command block in a chain succeeded,
+    /// for conditional command blocks.
+    pub condition_met: bool,
+    /// The tick the command last ran on.
+    pub last_execution: i64,
+    /// Whether an impulse command block has run since it
+    /// was activated. Not persisted.
+    pub triggered: bool,
+}
+
+impl CommandBlock {
+    /// Returns whether the command block is powered
+    /// or always active.
+    pub fn is_active(&self) -> bool {
+        self.auto || self.powered
+    }
+}
+
+/// Slots of a furnace inventory, in the order used by the `Slot` tag.
+const FURNACE_AREAS: [Area; 3] = [
+    Area::FurnaceIngredient,
+    Area::FurnaceFuel,
+    Area::FurnaceOutput,
+];
+
+impl BlockEntity {
+    /// Creates the empty block entity of a freshly placed `block`.
+    ///
+    /// Returns `None` if the block has no block entity.
+    pub fn new_for_block(block: BlockId) -> Option<Self> {
+        match block.simplified_kind() {
+            SimplifiedBlockKind::Chest => Some(BlockEntity::Chest(Inventory::chest())),
+            SimplifiedBlockKind::Furnace => Some(BlockEntity::Furnace {
+                inventory: Inventory::furnace(),
+                burn_time: 0,
+                cook_time: 0,
+                cook_time_total: 0,
+            }),
+            SimplifiedBlockKind::Sign | SimplifiedBlockKind::WallSign => {
+                Some(BlockEntity::Sign(Sign::default()))
+            }
+            _ => match block.kind() {
+                BlockKind::CommandBlock
+                | BlockKind::ChainCommandBlock
+                | BlockKind::RepeatingCommandBlock => {
+                    Some(BlockEntity::CommandBlock(CommandBlock::default()))
+                }
+                _ => None,
+            },
+        }
+    }
+
+    /// Gets the inventory of this block entity, if it has one.
+    pub fn inventory(&self) -> Option<&Inventory> {
+        match self {
+            BlockEntity::Chest(inventory) | BlockEntity::Furnace { inventory, .. } => {
+                Some(inventory)
+            }
+            BlockEntity::Sign(_) | BlockEntity::CommandBlock(_) => None,
+        }
+    }
+
+    /// Serializes this block entity to be stored in its chunk.
+    pub fn to_data(&self, pos: BlockPosition) -> BlockEntityData {
+        let kind = match self {
+            BlockEntity::Chest(inventory) => BlockEntityKind::Chest {
+                items: save_items(inventory, &[Area::Storage]),
+                loot_table: None,
+                loot_table_seed: None,
+            },
+            BlockEntity::Furnace {
+                inventory,
+                burn_time,
+                cook_time,
+                cook_time_total,
+            } => BlockEntityKind::Furnace {
+                items: save_items(inventory, &FURNACE_AREAS),
+                burn_time: *burn_time,
+                cook_time: *cook_time,
+                cook_time_total: *cook_time_total,
+            },
+            BlockEntity::Sign(sign) => {
+                let [text1, text2, text3, text4] = sign.lines.clone();
+                BlockEntityKind::Sign {
+                    text1: text1.to_string(),
+                    text2: text2.to_string(),
+                    text3: text3.to_string(),
+                    text4: text4.to_string(),
+                    color: sign.color.clone(),
+                }
+            }
+            BlockEntity::CommandBlock(command_block) => BlockEntityKind::CommandBlock {
+                custom_name: command_block.custom_name.clone(),
+                command: command_block.command.clone(),
+                success_count: command_block.success_count,
+                last_output: command_block.last_output.clone(),
+                track_output: command_block.track_output,
+                powered: command_block.powered,
+                auto: command_block.auto,
+                condition_met: command_block.condition_met,
+                update_last_execution: true,
+                last_execution: command_block.last_execution,
+            },
+        };
+        BlockEntityData {
+            base: BlockEntityBase {
+                x: pos.x,
+                y: pos.y,
+                z: pos.z,
+            },
+            kind,
+        }
+    }
+
+    /// Deserializes a block entity stored in a chunk.
+    ///
+    /// Returns `None` for block entities that aren't supported yet.
+    pub fn from_data(data: &BlockEntityData) -> Option<(BlockPosition, Self)> {
+        let pos = BlockPosition::new(data.base.x, data.base.y, data.base.z);
+        let block_entity = match &data.kind {
+            BlockEntityKind::Chest { items, .. } => {
+                let inventory = Inventory::chest();
+                load_items(&inventory, &[Area::Storage], items);
+                BlockEntity::Chest(inventory)
+            }
+            BlockEntityKind::Furnace {
+                items,
+                burn_time,
+                cook_time,
+                cook_time_total,
+            } => {
+                let inventory = Inventory::furnace();
+                load_items(&inventory, &FURNACE_AREAS, items);
+                BlockEntity::Furnace {
+                    inventory,
+                    burn_time: *burn_time,
+                    cook_time: *cook_time,
+                    cook_time_total: *cook_time_total,
+                }
+            }
+            BlockEntityKind::Sign {
+                text1,
+                text2,
+                text3,
+                text4,
+                color,
+            } => BlockEntity::Sign(Sign {
+                lines: [
+                    parse_line(text1),
+                    parse_line(text2),
+                    parse_line(text3),
+                    parse_line(text4),
+                ],
+                color: color.clone(),
+            }),
+            BlockEntityKind::CommandBlock {
+                custom_name,
+                command,
+                success_count,
+                last_output,
+                track_output,
+                powered,
+                auto,
+                condition_met,
+                last_execution,
+                ..
+            } => BlockEntity::CommandBlock(CommandBlock {
+                command: command.clone(),
+                custom_name: custom_name.clone(),
+                auto: *auto,
+                powered: *powered,
+                track_output: *track_output,
+                last_output: last_output.clone(),
+                success_count: *success_count,
+                condition_met: *condition_met,
+                last_execution: *last_execution,
+                // Don't run impulse command blocks again
+                // just because their chunk was loaded.
+                triggered: *auto || *powered,
+            }),
+            _ => return None,
+        };
+        Some((pos, block_entity))
+    }
+}
+
+/// Sign lines are stored as JSON text. Plain strings
+/// written by older versions are kept as is.
+fn parse_line(line: &str) -> Text {
+    serde_json::from_str(line).unwrap_or_else(|_| Text::from(line.to_owned()))
+}
+
+/// Collects the items in `areas` of an inventory, numbering
+/// slots consecutively across the areas.
+fn save_items(inventory: &Inventory, areas: &[Area]) -> Vec<InventorySlot> {
+    let mut items = Vec::new();
+    let mut index = 0;
+    for &area in areas {
+        let mut slot = 0;
+        while let Some(item) = inventory.item(area, slot) {
+            if let Some(stack) = item.clone() {
+                items.push(InventorySlot::from_inventory_index(index, stack));
+            }
+            slot += 1;
+            index += 1;
+        }
+    }
+    items
+}
+
+/// Inverse of [`save_items`]. Items in slots that don't
+/// exist are dropped.
+fn load_items(inventory: &Inventory, areas: &[Area], items: &[InventorySlot]) {
+    for item in items {
+        let mut index = item.slot as usize;
+        for &area in areas {
+            let len = area_len(inventory, area);
+            if index < len {
+                if let Some(mut slot) = inventory.item(area, index) {
+                    *slot = Some(ItemStack::from(item));
+                }
+                break;
+            }
+            index -= len;
+        }
+    }
+}
+
+fn area_len(inventory: &Inventory, area: Area) -> usize {
+    (0..)
+        .take_while(|&slot| inventory.item(area, slot).is_some())
+        .count()
+}
+
+/// Stores the block entities of all loaded chunks.
+#[derive(Debug, Default)]
+pub struct BlockEntities {
+    chunks: AHashMap<ChunkPosition, AHashMap<BlockPosition, BlockEntity>>,
+}
+
+impl BlockEntities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the block entity at `pos`.
+    pub fn get(&self, pos: BlockPosition) -> Option<&BlockEntity> {
+        self.chunks.get(&pos.chunk())?.get(&pos)
+    }
+
+    /// Mutably gets the block entity at `pos`.
+    pub fn get_mut(&mut self, pos: BlockPosition) -> Option<&mut BlockEntity> {
+        self.chunks.get_mut(&pos.chunk())?.get_mut(&pos)
+    }
+
+    /// Sets the block entity at `pos`, returning the one it replaced.
+    pub fn insert(&mut self, pos: BlockPosition, block_entity: BlockEntity) -> Option<BlockEntity> {
+        self.chunks
+            .entry(pos.chunk())
+            .or_default()
+            .insert(pos, block_entity)
+    }
+
+    /// Removes the block entity at `pos`.
+    pub fn remove(&mut self, pos: BlockPosition) -> Option<BlockEntity> {
+        let chunk = self.chunks.get_mut(&pos.chunk())?;
+        let removed = chunk.remove(&pos);
+        if chunk.is_empty() {
+            self.chunks.remove(&pos.chunk());
+        }
+        removed
+    }
+
+    /// Iterates over all block entities.
+    pub fn iter(&self) -> impl Iterator<Item = (BlockPosition, &BlockEntity)> {
+        self.chunks
+            .values()
+            .flatten()
+            .map(|(&pos, block_entity)| (pos, block_entity))
+    }
+
+    /// Iterates over the block entities in a chunk.
+    pub fn iter_chunk(
+        &self,
+        chunk: ChunkPosition,
+    ) -> impl Iterator<Item = (BlockPosition, &BlockEntity)> {
+        self.chunks
+            .get(&chunk)
+            .into_iter()
+            .flatten()
+            .map(|(&pos, block_entity)| (pos, block_entity))
+    }
+
+    /// Adds the block entities stored in a newly loaded chunk.
+    pub fn load_chunk(&mut self, data: &[BlockEntityData]) {
+        for data in data {
+            match BlockEntity::from_data(data) {
+                Some((pos, block_entity)) => {
+                    self.insert(pos, block_entity);
+                }
+                None => log::debug!(
+                    "Skipping unsupported block entity {:?}",
+                    data.kind.variant()
+                ),
+            }
+        }
+    }
+
+    /// Serializes and removes the block entities of a chunk.
+    /// Used when the chunk is unloaded.
+    pub fn take_chunk(&mut self, chunk: ChunkPosition) -> Vec<BlockEntityData> {
+        self.chunks
+            .remove(&chunk)
+            .into_iter()
+            .flatten()
+            .map(|(pos, block_entity)| block_entity.to_data(pos))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{
+        anvil::region::{self, RegionPosition},
+        Chunk, Item,
+    };
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Saves block entities to a region file and loads them back.
+    fn round_trip(block_entities: &mut BlockEntities, chunk: ChunkPosition) -> BlockEntities {
+        let data = block_entities.take_chunk(chunk);
+
+        let dir = TempDir::new().unwrap();
+        let region_pos = RegionPosition::from_chunk(chunk);
+        let mut handle = region::create_region(dir.path(), region_pos).unwrap();
+        handle.save_chunk(&Chunk::new(chunk), &[], &data).unwrap();
+        let mut handle = region::load_region(dir.path(), region_pos).unwrap();
+        let (_, _, loaded) = handle.load_chunk(chunk).unwrap();
+
+        let mut block_entities = BlockEntities::new();
+        block_entities.load_chunk(&loaded);
+        block_entities
+    }
+
+    #[test]
+    fn chest_items_survive_save_and_load() {
+        let pos = BlockPosition::new(4, 64, 9);
+        let inventory = Inventory::chest();
+        *inventory.item(Area::Storage, 0).unwrap() = Some(ItemStack::new(Item::Diamond, 5));
+        let pickaxe = ItemStack {
+            damage: Some(40),
+            ..ItemStack::new(Item::IronPickaxe, 1)
+        };
+        *inventory.item(Area::Storage, 26).unwrap() = Some(pickaxe.clone());
+
+        let mut block_entities = BlockEntities::new();
+        block_entities.insert(pos, BlockEntity::Chest(inventory));
+        let loaded = round_trip(&mut block_entities, pos.chunk());
+        assert!(block_entities.get(pos).is_none());
+
+        let inventory = loaded.get(pos).unwrap().inventory().unwrap();
+        assert_eq!(
+            *inventory.item(Area::Storage, 0).unwrap(),
+            Some(ItemStack::new(Item::Diamond, 5))
+        );
+        assert_eq!(*inventory.item(Area::Storage, 26).unwrap(), Some(pickaxe));
+        assert_eq!(inventory.to_vec().iter().flatten().count(), 2);
+    }
+
+    #[test]
+    fn sign_text_survives_save_and_load() {
+        let pos = BlockPosition::new(-3, 70, 12);
+        let sign = Sign {
+            lines: [
+                Text::from("Welcome"),
+                Text::empty(),
+                Text::from("to the"),
+                Text::from("spawn"),
+            ],
+            color: "light_blue".to_owned(),
+        };
+
+        let mut block_entities = BlockEntities::new();
+        block_entities.insert(pos, BlockEntity::Sign(sign.clone()));
+        let loaded = round_trip(&mut block_entities, pos.chunk());
+
+        match loaded.get(pos) {
+            Some(BlockEntity::Sign(loaded)) => assert_eq!(*loaded, sign),
+            other => panic!("expected a sign, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn new_for_block() {
+        assert!(matches!(
+            BlockEntity::new_for_block(BlockId::chest()),
+            Some(BlockEntity::Chest(_))
+        ));
+        assert!(BlockEntity::new_for_block(BlockId::stone()).is_none());
+    }
+}
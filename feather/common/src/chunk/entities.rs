@@ -51,15 +51,13 @@ impl ChunkEntities {
 
 fn update_chunk_entities(game: &mut Game) -> SysResult {
     // Entities that have crossed chunks
-    let mut events = Vec::new();
+    let mut crossings = Vec::new();
     for (entity, (old_chunk, &position)) in
         game.ecs.query::<(&mut ChunkPosition, &Position)>().iter()
     {
         let new_chunk = position.chunk();
         if position.chunk() != *old_chunk {
-            game.chunk_entities
-                .update(entity, Some(*old_chunk), new_chunk);
-            events.push((
+            crossings.push((
                 entity,
                 ChunkCrossEvent {
                     old_chunk: *old_chunk,
@@ -70,7 +68,10 @@ fn update_chunk_entities(game: &mut Game) -> SysResult {
             *old_chunk = new_chunk;
         }
     }
-    for (entity, event) in events {
+    game.sort_for_tick(&mut crossings);
+    for (entity, event) in crossings {
+        game.chunk_entities
+            .update(entity, Some(event.old_chunk), event.new_chunk);
         game.ecs.insert_entity_event(entity, event)?;
     }
 
@@ -78,23 +79,72 @@ fn update_chunk_entities(game: &mut Game) -> SysResult {
     let mut insertions = Vec::new();
     for (entity, (_event, &position)) in game.ecs.query::<(&EntityCreateEvent, &Position)>().iter()
     {
-        let chunk = position.chunk();
-        game.chunk_entities.update(entity, None, chunk);
-        insertions.push((entity, chunk));
+        insertions.push((entity, position.chunk()));
     }
-    // Add ChunkPosition component to new entities
+    game.sort_for_tick(&mut insertions);
     for (entity, chunk) in insertions {
+        game.chunk_entities.update(entity, None, chunk);
+        // Add ChunkPosition component to new entities
         game.ecs.insert(entity, chunk)?;
     }
 
     // Entities that have been destroyed
-    for (entity, (_event, &chunk)) in game
+    let mut removals: Vec<(Entity, ChunkPosition)> = game
         .ecs
         .query::<(&EntityRemoveEvent, &ChunkPosition)>()
         .iter()
-    {
+        .map(|(entity, (_event, &chunk))| (entity, chunk))
+        .collect();
+    game.sort_for_tick(&mut removals);
+    for (entity, chunk) in removals {
         game.chunk_entities.remove_entity(entity, chunk);
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+    use quill_common::entity_init::EntityInit;
+
+    use super::*;
+
+    fn spawn_cows(game: &mut Game, count: usize) -> Vec<Entity> {
+        (0..count)
+            .map(|_| {
+                let builder =
+                    game.create_entity_builder(position!(1.0, 64.0, 1.0), EntityInit::Cow);
+                game.spawn_entity(builder)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn deterministic_ticking_sorts_by_entity() {
+        let mut game = Game::new();
+        game.deterministic_ticking = true;
+        let mut systems = SystemExecutor::new();
+        register(&mut systems);
+
+        let cows = spawn_cows(&mut game, 32);
+        systems.run(&mut game);
+
+        // Free entity IDs out of order so that they are reused out of order
+        for i in (0..32).map(|i| i * 7 % 32) {
+            game.remove_entity(cows[i]).unwrap();
+        }
+        systems.run(&mut game);
+
+        let cows = spawn_cows(&mut game, 32);
+        let mut sorted = cows.clone();
+        sorted.sort();
+        assert_ne!(cows, sorted, "entities were spawned in order");
+        systems.run(&mut game);
+
+        let chunk = game
+            .chunk_entities
+            .entities_in_chunk(ChunkPosition::new(0, 0));
+        assert_eq!(chunk, &sorted[..]);
+    }
+}
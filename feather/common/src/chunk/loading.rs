@@ -12,7 +12,7 @@ use ecs::{Entity, SysResult, SystemExecutor};
 use utils::vec_remove_item;
 
 use crate::{
-    chunk::worker::LoadRequest,
+    chunk::{persistence, worker::LoadRequest},
     events::{EntityRemoveEvent, ViewUpdateEvent},
     Game,
 };
@@ -24,7 +24,8 @@ pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
         .add_system(remove_dead_entities)
         .add_system(update_tickets_for_players)
         .add_system(unload_chunks)
-        .add_system(load_chunks);
+        .add_system(load_chunks)
+        .add_system(spawn_loaded_entities);
 }
 
 /// Amount of time to wait after a chunk has
@@ -156,9 +157,14 @@ fn unload_chunks(game: &mut Game, state: &mut ChunkLoadState) -> SysResult {
             continue;
         }
 
-        game.world.unload_chunk(unload.pos)?;
+        let entities = if game.world.is_chunk_loaded(unload.pos) {
+            persistence::take_chunk_entities(game, unload.pos)?
+        } else {
+            Vec::new()
+        };
+        game.world.unload_chunk(unload.pos, entities)?;
     }
-    game.world.cache.purge_unused();
+    game.world.purge_cache();
     Ok(())
 }
 
@@ -176,3 +182,13 @@ fn remove_dead_entities(game: &mut Game, state: &mut ChunkLoadState) -> SysResul
 fn load_chunks(game: &mut Game, _state: &mut ChunkLoadState) -> SysResult {
     game.world.load_chunks(&mut game.ecs)
 }
+
+/// System to spawn the entities stored in newly loaded chunks.
+fn spawn_loaded_entities(game: &mut Game, _state: &mut ChunkLoadState) -> SysResult {
+    for data in game.world.take_loaded_entities() {
+        if let Err(e) = persistence::load_entity(game, &data) {
+            log::warn!("Failed to load a saved entity: {:?}", e);
+        }
+    }
+    Ok(())
+}
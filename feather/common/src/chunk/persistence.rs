@@ -0,0 +1,217 @@
+//! Conversion between entities and the `Entities`
+//! list stored in chunk NBT.
+
+use anyhow::Context;
+use base::{
+    anvil::entity::{
+        AnimalData, ArrowEntityData, BaseEntityData, EntityData, ItemData, ItemEntityData,
+    },
+    ChunkPosition, EntityKind, ItemStack, Position,
+};
+use ecs::{Entity, EntityRef};
+use quill_common::entity_init::EntityInit;
+
+use crate::{
+    damage::Health,
+    events::EntityRemoveEvent,
+    physics::Velocity,
+    projectile::{self, Projectile},
+    Game,
+};
+
+/// Health of a freshly dropped item entity.
+const ITEM_HEALTH: i16 = 5;
+
+/// Damage dealt by an arrow restored from disk. The shooter's
+/// bow enchantments aren't known anymore.
+const ARROW_DAMAGE: f32 = 2.;
+
+/// Serializes an entity to be stored in its chunk.
+///
+/// Returns `None` for entities that aren't persisted, like players,
+/// or that are being removed.
+pub fn save_entity(entity: &EntityRef) -> Option<EntityData> {
+    if entity.get::<EntityRemoveEvent>().is_ok() {
+        return None;
+    }
+    let kind = *entity.get::<EntityKind>().ok()?;
+    let position = *entity.get::<Position>().ok()?;
+    let velocity = entity
+        .get::<Velocity>()
+        .map(|velocity| velocity.0)
+        .unwrap_or_default();
+    let base = BaseEntityData::new(position, velocity);
+
+    let data = match kind {
+        EntityKind::Item => EntityData::Item(ItemEntityData {
+            entity: base,
+            item: ItemData::from(&*entity.get::<ItemStack>().ok()?),
+            health: ITEM_HEALTH,
+            ..Default::default()
+        }),
+        EntityKind::Arrow => EntityData::Arrow(ArrowEntityData {
+            entity: base,
+            critical: 0,
+        }),
+        EntityKind::Cow => EntityData::Cow(animal_data(entity, base)),
+        EntityKind::Pig => EntityData::Pig(animal_data(entity, base)),
+        EntityKind::Chicken => EntityData::Chicken(animal_data(entity, base)),
+        EntityKind::Sheep => EntityData::Sheep(animal_data(entity, base)),
+        EntityKind::Horse => EntityData::Horse(animal_data(entity, base)),
+        EntityKind::Llama => EntityData::Llama(animal_data(entity, base)),
+        EntityKind::Mooshroom => EntityData::Mooshroom(animal_data(entity, base)),
+        EntityKind::Rabbit => EntityData::Rabbit(animal_data(entity, base)),
+        EntityKind::Squid => EntityData::Squid(animal_data(entity, base)),
+        EntityKind::Donkey => EntityData::Donkey(animal_data(entity, base)),
+        _ => return None,
+    };
+    Some(data)
+}
+
+fn animal_data(entity: &EntityRef, base: BaseEntityData) -> AnimalData {
+    let health = entity
+        .get::<Health>()
+        .map(|health| health.0)
+        .unwrap_or_else(|_| AnimalData::default().health);
+    AnimalData::new(base, health)
+}
+
+/// Serializes and removes all persisted entities in a chunk.
+/// Used when the chunk is unloaded.
+pub fn take_chunk_entities(
+    game: &mut Game,
+    chunk: ChunkPosition,
+) -> anyhow::Result<Vec<EntityData>> {
+    let mut saved = Vec::new();
+    for entity in game.chunk_entities.entities_in_chunk(chunk).to_vec() {
+        let data = save_entity(&game.ecs.entity(entity)?);
+        if let Some(data) = data {
+            saved.push(data);
+            game.remove_entity(entity)?;
+        }
+    }
+    Ok(saved)
+}
+
+/// Spawns an entity stored in a chunk.
+///
+/// Returns `Ok(None)` if the entity type is unknown.
+pub fn load_entity(game: &mut Game, data: &EntityData) -> anyhow::Result<Option<Entity>> {
+    let (init, base) = match data {
+        EntityData::Item(item) => (EntityInit::Item, &item.entity),
+        EntityData::Arrow(arrow) => (EntityInit::Arrow, &arrow.entity),
+        EntityData::Cow(animal) => (EntityInit::Cow, &animal.base),
+        EntityData::Pig(animal) => (EntityInit::Pig, &animal.base),
+        EntityData::Chicken(animal) => (EntityInit::Chicken, &animal.base),
+        EntityData::Sheep(animal) => (EntityInit::Sheep, &animal.base),
+        EntityData::Horse(animal) => (EntityInit::Horse, &animal.base),
+        EntityData::Llama(animal) => (EntityInit::Llama, &animal.base),
+        EntityData::Mooshroom(animal) => (EntityInit::Mooshroom, &animal.base),
+        EntityData::Rabbit(animal) => (EntityInit::Rabbit, &animal.base),
+        EntityData::Squid(animal) => (EntityInit::Squid, &animal.base),
+        EntityData::Donkey(animal) => (EntityInit::Donkey, &animal.base),
+        EntityData::Unknown => return Ok(None),
+    };
+    let position = base
+        .read_position()
+        .context("invalid saved entity position")?;
+    let velocity = base
+        .read_velocity()
+        .context("invalid saved entity velocity")?;
+
+    let mut builder = game.create_entity_builder(position, init);
+    match data {
+        EntityData::Item(item) => {
+            builder.add(ItemStack::from(&item.item));
+        }
+        EntityData::Arrow(_) => {
+            builder.add(Velocity(velocity)).add(Projectile {
+                shooter: None,
+                damage: ARROW_DAMAGE,
+                gravity: projectile::gravity(&EntityInit::Arrow),
+            });
+        }
+        EntityData::Cow(animal)
+        | EntityData::Pig(animal)
+        | EntityData::Chicken(animal)
+        | EntityData::Sheep(animal)
+        | EntityData::Horse(animal)
+        | EntityData::Llama(animal)
+        | EntityData::Mooshroom(animal)
+        | EntityData::Rabbit(animal)
+        | EntityData::Squid(animal)
+        | EntityData::Donkey(animal) => {
+            builder.add(Health(animal.health));
+        }
+        EntityData::Unknown => unreachable!("unknown entities are skipped above"),
+    }
+    Ok(Some(game.spawn_entity(builder)))
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{
+        anvil::region::{self, RegionPosition},
+        position, Chunk, Item,
+    };
+    use ecs::SystemExecutor;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn game() -> Game {
+        let mut game = Game::new();
+        game.add_entity_spawn_callback(crate::entities::add_entity_components);
+        game
+    }
+
+    #[test]
+    fn item_entity_survives_save_and_load() {
+        let mut game = game();
+        let mut systems = SystemExecutor::new();
+        crate::chunk::entities::register(&mut systems);
+
+        let stack = ItemStack {
+            damage: Some(12),
+            ..ItemStack::new(Item::IronPickaxe, 1)
+        };
+        let mut builder =
+            game.create_entity_builder(position!(3.5, 64.0, 7.25, 90.0, 0.0), EntityInit::Item);
+        builder.add(stack.clone());
+        let item = game.spawn_entity(builder);
+        systems.run(&mut game);
+
+        let chunk_pos = ChunkPosition::new(0, 0);
+        let entities = take_chunk_entities(&mut game, chunk_pos).unwrap();
+        assert_eq!(entities.len(), 1);
+        assert!(game.ecs.get::<EntityRemoveEvent>(item).is_ok());
+
+        // Round-trip through a region file
+        let dir = TempDir::new().unwrap();
+        let region_pos = RegionPosition::from_chunk(chunk_pos);
+        let mut handle = region::create_region(dir.path(), region_pos).unwrap();
+        handle
+            .save_chunk(&Chunk::new(chunk_pos), &entities, &[])
+            .unwrap();
+        let mut handle = region::load_region(dir.path(), region_pos).unwrap();
+        let (_, loaded, _) = handle.load_chunk(chunk_pos).unwrap();
+
+        let mut game = self::game();
+        assert_eq!(loaded.len(), 1);
+        let item = load_entity(&mut game, &loaded[0]).unwrap().unwrap();
+        assert_eq!(*game.ecs.get::<EntityKind>(item).unwrap(), EntityKind::Item);
+        assert_eq!(*game.ecs.get::<ItemStack>(item).unwrap(), stack);
+        assert_eq!(
+            *game.ecs.get::<Position>(item).unwrap(),
+            position!(3.5, 64.0, 7.25, 90.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn players_are_not_saved() {
+        let mut game = game();
+        let builder = game.create_entity_builder(position!(0.0, 64.0, 0.0), EntityInit::Player);
+        let player = game.spawn_entity(builder);
+        assert!(save_entity(&game.ecs.entity(player).unwrap()).is_none());
+    }
+}
@@ -1,4 +1,4 @@
-use std::{path::PathBuf, sync::Arc};
+use std::sync::Arc;
 
 use anyhow::bail;
 use base::{
@@ -6,9 +6,10 @@ use base::{
     Chunk, ChunkHandle, ChunkPosition,
 };
 use flume::{Receiver, Sender};
+use parking_lot::Mutex;
 use worldgen::WorldGenerator;
 
-use crate::region_worker::RegionWorker;
+use crate::{region_worker::RegionWorker, world_storage::WorldStorage};
 
 #[derive(Debug)]
 pub struct LoadRequest {
@@ -18,6 +19,10 @@ pub struct LoadRequest {
 pub struct LoadedChunk {
     pub pos: ChunkPosition,
     pub chunk: Chunk,
+    /// Entities stored in the chunk.
+    pub entities: Vec<EntityData>,
+    /// Block entities stored in the chunk.
+    pub block_entities: Vec<BlockEntityData>,
 }
 
 #[derive(Debug)]
@@ -54,10 +59,11 @@ pub struct ChunkWorker {
 }
 
 impl ChunkWorker {
-    pub fn new(world_dir: impl Into<PathBuf>, generator: Arc<dyn WorldGenerator>) -> Self {
+    /// Creates a worker loading and saving chunks in `storage`.
+    pub fn new(storage: Arc<Mutex<dyn WorldStorage>>, generator: Arc<dyn WorldGenerator>) -> Self {
         let (send_req, recv_req) = flume::unbounded();
         let (send_gen, recv_gen) = flume::unbounded();
-        let (region_worker, recv_load) = RegionWorker::new(world_dir.into(), recv_req);
+        let (region_worker, recv_load) = RegionWorker::new(storage, recv_req);
         region_worker.start();
         Self {
             generator,
@@ -94,7 +100,14 @@ impl ChunkWorker {
                         rayon::spawn(move || {
                             // spawn task to generate chunk
                             let chunk = gen.generate_chunk(pos);
-                            send_gen.send(LoadedChunk { pos, chunk }).unwrap()
+                            send_gen
+                                .send(LoadedChunk {
+                                    pos,
+                                    chunk,
+                                    entities: Vec::new(),
+                                    block_entities: Vec::new(),
+                                })
+                                .unwrap()
                         });
                         self.try_recv_gen() // check for generated chunks
                     }
@@ -0,0 +1,271 @@
+//! Melee combat.
+//!
+//! The damage of an attack depends on the attacker's held item.
+//! After attacking, a weapon needs time to recharge: attacking
+//! again before then deals less damage (the 1.9+ attack cooldown).
+
+use base::{Area, EnchantmentKind, Gamemode, Inventory, Item, ItemStack, Position, Vec3d};
+use ecs::{Entity, SysResult};
+use quill_common::components::{OnGround, Sprinting};
+
+use crate::{
+    damage::{self, DamageSource, Health},
+    entities::player::HotbarSlot,
+    physics::Velocity,
+    Game,
+};
+
+/// Knockback strength of every attack.
+const BASE_KNOCKBACK: f64 = 0.4;
+
+/// Extra knockback strength per knockback level.
+const KNOCKBACK_PER_LEVEL: f64 = 0.5;
+
+/// Component storing when an entity last attacked.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AttackCooldown {
+    /// The tick at which the cooldown was last reset.
+    pub last_reset: u64,
+}
+
+impl AttackCooldown {
+    /// Gets how far the weapon has recharged at `tick`, between 0 and 1.
+    pub fn progress(&self, tick: u64, weapon: Option<&ItemStack>) -> f32 {
+        let ticks = tick.saturating_sub(self.last_reset) as f32;
+        let period = 20. / attack_speed(weapon);
+        ((ticks + 0.5) / period).min(1.)
+    }
+}
+
+/// Gets the number of full-strength attacks
+/// per second possible with `weapon`.
+pub fn attack_speed(weapon: Option<&ItemStack>) -> f32 {
+    match weapon.map(|weapon| weapon.item) {
+        Some(Item::WoodenSword)
+        | Some(Item::StoneSword)
+        | Some(Item::IronSword)
+        | Some(Item::GoldenSword)
+        | Some(Item::DiamondSword)
+        | Some(Item::NetheriteSword) => 1.6,
+        Some(Item::WoodenAxe) | Some(Item::StoneAxe) => 0.8,
+        Some(Item::IronAxe) => 0.9,
+        Some(Item::GoldenAxe) | Some(Item::DiamondAxe) | Some(Item::NetheriteAxe) => 1.,
+        Some(Item::WoodenPickaxe)
+        | Some(Item::StonePickaxe)
+        | Some(Item::IronPickaxe)
+        | Some(Item::GoldenPickaxe)
+        | Some(Item::DiamondPickaxe)
+        | Some(Item::NetheritePickaxe) => 1.2,
+        Some(Item::WoodenShovel)
+        | Some(Item::StoneShovel)
+        | Some(Item::IronShovel)
+        | Some(Item::GoldenShovel)
+        | Some(Item::DiamondShovel)
+        | Some(Item::NetheriteShovel) => 1.,
+        Some(Item::Trident) => 1.1,
+        _ => 4.,
+    }
+}
+
+/// The outcome of an [`attack`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Attack {
+    /// The damage dealt to the target.
+    pub damage: f32,
+    /// The velocity of the target after being knocked back.
+    pub velocity: Vec3d,
+}
+
+/// Makes `attacker` hit `target` with its held item.
+///
+/// The target takes damage scaled by the attacker's cooldown,
+/// triggering an `EntityDamageEvent`, and is knocked back.
+/// Returns `None` if the target can't be attacked.
+pub fn attack(game: &mut Game, attacker: Entity, target: Entity) -> SysResult<Option<Attack>> {
+    if attacker == target
+        || game.ecs.get::<Health>(target).is_err()
+        || damage::is_pvp_blocked(game, attacker, target)
+    {
+        return Ok(None);
+    }
+    if let Ok(gamemode) = game.ecs.get::<Gamemode>(attacker) {
+        if *gamemode == Gamemode::Spectator {
+            return Ok(None);
+        }
+    }
+    if let Ok(gamemode) = game.ecs.get::<Gamemode>(target) {
+        if matches!(*gamemode, Gamemode::Creative | Gamemode::Spectator) {
+            return Ok(None);
+        }
+    }
+
+    let weapon = held_item(game, attacker);
+    let weapon = weapon.as_ref();
+    let progress = match game.ecs.get_mut::<AttackCooldown>(attacker) {
+        Ok(mut cooldown) => {
+            let progress = cooldown.progress(game.tick_count, weapon);
+            cooldown.last_reset = game.tick_count;
+            progress
+        }
+        Err(_) => 1.,
+    };
+
+    let amount = damage::base_attack_damage(weapon) * (0.2 + progress * progress * 0.8)
+        + damage::sharpness_bonus(weapon) * progress;
+    damage::damage(game, target, amount, DamageSource::Entity(attacker))?;
+
+    let mut knockback = weapon.map_or(0, |weapon| {
+        weapon.enchantment_level(EnchantmentKind::Knockback)
+    }) as f64;
+    let sprinting = game
+        .ecs
+        .get::<Sprinting>(attacker)
+        .map_or(false, |sprinting| sprinting.0);
+    if sprinting && progress > 0.9 {
+        knockback += 1.;
+    }
+    let velocity = knock_back(
+        game,
+        attacker,
+        target,
+        BASE_KNOCKBACK + knockback * KNOCKBACK_PER_LEVEL,
+    )?;
+
+    Ok(Some(Attack {
+        damage: amount,
+        velocity,
+    }))
+}
+
+/// Pushes `target` away from `attacker`, returning its new velocity.
+fn knock_back(
+    game: &mut Game,
+    attacker: Entity,
+    target: Entity,
+    strength: f64,
+) -> SysResult<Vec3d> {
+    let from = *game.ecs.get::<Position>(attacker)?;
+    let to = *game.ecs.get::<Position>(target)?;
+    let mut direction = Vec3d::new(to.x - from.x, 0., to.z - from.z);
+    if direction.magnitude_squared() < 1e-4 {
+        // Pick a direction if both are at the same position
+        direction = Vec3d::new(1., 0., 0.);
+    }
+    let push = direction.normalized() * strength;
+
+    let old = game
+        .ecs
+        .get::<Velocity>(target)
+        .map_or(Vec3d::zero(), |velocity| velocity.0);
+    let on_ground = game
+        .ecs
+        .get::<OnGround>(target)
+        .map_or(false, |on_ground| on_ground.0);
+    let velocity = Vec3d::new(
+        old.x / 2. + push.x,
+        if on_ground {
+            (old.y / 2. + strength).min(0.4)
+        } else {
+            old.y
+        },
+        old.z / 2. + push.z,
+    );
+
+    if let Ok(mut target_velocity) = game.ecs.get_mut::<Velocity>(target) {
+        target_velocity.0 = velocity;
+    }
+    Ok(velocity)
+}
+
+fn held_item(game: &Game, entity: Entity) -> Option<ItemStack> {
+    let inventory = game.ecs.get::<Inventory>(entity).ok()?;
+    let slot = game.ecs.get::<HotbarSlot>(entity).ok()?.get();
+    let item = inventory.item(Area::Hotbar, slot)?.clone();
+    item
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+    use quill_common::entities::Player;
+
+    use super::*;
+
+    fn setup(tick: u64) -> (Game, Entity, Entity) {
+        let mut game = Game::new();
+        let inventory = Inventory::player();
+        *inventory.item(Area::Hotbar, 0).unwrap() = Some(ItemStack::new(Item::IronSword, 1));
+        let attacker = game.ecs.spawn((
+            position!(0.0, 64.0, 0.0),
+            inventory,
+            HotbarSlot::default(),
+            AttackCooldown::default(),
+        ));
+        let target = game.ecs.spawn((
+            position!(2.0, 64.0, 0.0),
+            Health(20.),
+            OnGround(true),
+            Velocity(Vec3d::zero()),
+        ));
+        game.tick_count = tick;
+        (game, attacker, target)
+    }
+
+    #[test]
+    fn attack_at_full_cooldown() {
+        let (mut game, attacker, target) = setup(100);
+        let attack = attack(&mut game, attacker, target).unwrap().unwrap();
+
+        assert_eq!(attack.damage, 6.);
+        assert_eq!(*game.ecs.get::<Health>(target).unwrap(), Health(14.));
+        assert_eq!(attack.velocity, Vec3d::new(0.4, 0.4, 0.));
+        assert_eq!(game.ecs.get::<Velocity>(target).unwrap().0, attack.velocity);
+        assert_eq!(
+            *game.ecs.get::<AttackCooldown>(attacker).unwrap(),
+            AttackCooldown { last_reset: 100 }
+        );
+    }
+
+    #[test]
+    fn attack_mid_cooldown() {
+        let (mut game, attacker, target) = setup(100);
+        attack(&mut game, attacker, target).unwrap();
+
+        // An iron sword recharges in 12.5 ticks
+        game.tick_count += 6;
+        let attack = attack(&mut game, attacker, target).unwrap().unwrap();
+        let progress: f32 = 6.5 / 12.5;
+        let expected = 6. * (0.2 + progress * progress * 0.8);
+        assert!((attack.damage - expected).abs() < 1e-4);
+        assert!(attack.damage < 3.);
+
+        let health = game.ecs.get::<Health>(target).unwrap().0;
+        assert!((health - (14. - expected)).abs() < 1e-4);
+        // Knockback doesn't depend on the cooldown
+        assert_eq!(attack.velocity, Vec3d::new(0.6, 0.4, 0.));
+    }
+
+    #[test]
+    fn creative_players_cannot_be_attacked() {
+        let (mut game, attacker, target) = setup(100);
+        game.ecs.insert(target, Gamemode::Creative).unwrap();
+        assert_eq!(attack(&mut game, attacker, target).unwrap(), None);
+        assert_eq!(*game.ecs.get::<Health>(target).unwrap(), Health(20.));
+    }
+
+    #[test]
+    fn players_cannot_be_attacked_without_pvp() {
+        let (mut game, attacker, target) = setup(100);
+        game.ecs.insert(attacker, Player).unwrap();
+        game.ecs.insert(target, Player).unwrap();
+
+        game.pvp = false;
+        assert_eq!(attack(&mut game, attacker, target).unwrap(), None);
+        assert_eq!(*game.ecs.get::<Health>(target).unwrap(), Health(20.));
+        assert_eq!(game.ecs.get::<Velocity>(target).unwrap().0, Vec3d::zero());
+
+        game.pvp = true;
+        assert!(attack(&mut game, attacker, target).unwrap().is_some());
+        assert_eq!(*game.ecs.get::<Health>(target).unwrap(), Health(14.));
+    }
+}
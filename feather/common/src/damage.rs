@@ -0,0 +1,218 @@
+//! Entity health and damage.
+
+use base::{Area, EnchantmentKind, Inventory, Item, ItemStack};
+use ecs::{Entity, SysResult};
+use quill_common::entities::Player;
+
+use crate::{events::EntityDamageEvent, Game};
+
+/// The health of an entity, in half-hearts.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Health(pub f32);
+
+impl Health {
+    /// The health of a player with full hearts.
+    pub const PLAYER_MAX: Health = Health(20.);
+
+    /// Returns whether this entity has no health left.
+    pub fn is_dead(self) -> bool {
+        self.0 <= 0.
+    }
+}
+
+/// The maximum [`Health`] of an entity. Healing
+/// never raises an entity's health above this value.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MaxHealth(pub f32);
+
+/// The cause of an [`EntityDamageEvent`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DamageSource {
+    /// Damage dealt by another entity. For projectiles,
+    /// this is the shooter if it is known.
+    Entity(Entity),
+    /// Damage dealt by status effects like poison.
+    Magic,
+    /// Damage taken by entities crammed together.
+    Cramming,
+    /// Damage without a specific cause.
+    Generic,
+}
+
+/// The armor slots of an entity's inventory.
+const ARMOR_AREAS: [Area; 4] = [Area::Helmet, Area::Chestplate, Area::Leggings, Area::Boots];
+
+/// Deals `amount` damage to `entity`, lowering its [`Health`]
+/// and triggering an [`EntityDamageEvent`].
+///
+/// The damage is reduced by protection enchantments on the
+/// entity's armor. Entities without a `Health` component
+/// still receive the event. Nothing happens if the damage
+/// is [blocked by pvp being disabled](is_pvp_blocked).
+pub fn damage(game: &mut Game, entity: Entity, amount: f32, source: DamageSource) -> SysResult {
+    if let DamageSource::Entity(attacker) = source {
+        if is_pvp_blocked(game, attacker, entity) {
+            return Ok(());
+        }
+    }
+    let amount = match game.ecs.get::<Inventory>(entity) {
+        Ok(inventory) => amount * (1. - protection_reduction(&inventory)),
+        Err(_) => amount,
+    };
+    if let Ok(mut health) = game.ecs.get_mut::<Health>(entity) {
+        health.0 = (health.0 - amount).max(0.);
+    }
+    game.ecs
+        .insert_entity_event(entity, EntityDamageEvent { amount, source })?;
+    Ok(())
+}
+
+/// Returns whether `attacker` can't damage `target` because
+/// both are players and [`Game::pvp`] is disabled.
+pub fn is_pvp_blocked(game: &Game, attacker: Entity, target: Entity) -> bool {
+    !game.pvp && game.ecs.get::<Player>(attacker).is_ok() && game.ecs.get::<Player>(target).is_ok()
+}
+
+/// Raises the [`Health`] of `entity` by `amount`,
+/// up to its [`MaxHealth`] if it has one.
+pub fn heal(game: &mut Game, entity: Entity, amount: f32) {
+    let max = game
+        .ecs
+        .get::<MaxHealth>(entity)
+        .map_or(f32::INFINITY, |max| max.0);
+    if let Ok(mut health) = game.ecs.get_mut::<Health>(entity) {
+        health.0 = (health.0 + amount).min(max).max(health.0);
+    }
+}
+
+/// Gets the fraction of damage blocked by the
+/// protection enchantments on an entity's armor.
+fn protection_reduction(inventory: &Inventory) -> f32 {
+    let protection: u32 = ARMOR_AREAS
+        .iter()
+        .filter_map(|&area| inventory.item(area, 0))
+        .filter_map(|item| {
+            item.as_ref()
+                .map(|item| item.enchantment_level(EnchantmentKind::Protection) as u32)
+        })
+        .sum();
+    // Protection is capped at 80%
+    protection.min(20) as f32 / 25.
+}
+
+/// Gets the damage dealt by a melee attack with `weapon`,
+/// including the bonus from sharpness.
+pub fn attack_damage(weapon: Option<&ItemStack>) -> f32 {
+    base_attack_damage(weapon) + sharpness_bonus(weapon)
+}
+
+/// Gets the damage dealt by a melee attack with
+/// `weapon`, ignoring enchantments.
+pub fn base_attack_damage(weapon: Option<&ItemStack>) -> f32 {
+    match weapon.map(|weapon| weapon.item) {
+        Some(Item::WoodenSword) | Some(Item::GoldenSword) => 4.,
+        Some(Item::StoneSword) => 5.,
+        Some(Item::IronSword) => 6.,
+        Some(Item::DiamondSword) => 7.,
+        Some(Item::NetheriteSword) => 8.,
+        Some(Item::WoodenAxe) | Some(Item::GoldenAxe) => 7.,
+        Some(Item::StoneAxe) | Some(Item::IronAxe) | Some(Item::DiamondAxe) => 9.,
+        Some(Item::NetheriteAxe) => 10.,
+        Some(Item::WoodenPickaxe) | Some(Item::GoldenPickaxe) => 2.,
+        Some(Item::StonePickaxe) => 3.,
+        Some(Item::IronPickaxe) => 4.,
+        Some(Item::DiamondPickaxe) => 5.,
+        Some(Item::NetheritePickaxe) => 6.,
+        Some(Item::WoodenShovel) | Some(Item::GoldenShovel) => 2.5,
+        Some(Item::StoneShovel) => 3.5,
+        Some(Item::IronShovel) => 4.5,
+        Some(Item::DiamondShovel) => 5.5,
+        Some(Item::NetheriteShovel) => 6.5,
+        Some(Item::Trident) => 9.,
+        _ => 1.,
+    }
+}
+
+/// Gets the extra damage dealt by a sharpness enchantment on `weapon`.
+pub fn sharpness_bonus(weapon: Option<&ItemStack>) -> f32 {
+    let level = weapon.map_or(0, |weapon| {
+        weapon.enchantment_level(EnchantmentKind::Sharpness)
+    });
+    match level {
+        0 => 0.,
+        level => 0.5 * level as f32 + 0.5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::Enchantment;
+
+    use super::*;
+
+    fn enchanted(item: Item, kind: EnchantmentKind, level: u16) -> ItemStack {
+        ItemStack {
+            enchantments: vec![Enchantment::new(kind, level)],
+            ..ItemStack::new(item, 1)
+        }
+    }
+
+    #[test]
+    fn protection_reduces_damage() {
+        let mut game = Game::new();
+        let inventory = Inventory::player();
+        let entity = game.ecs.spawn((Health(20.), inventory.new_handle()));
+
+        damage(&mut game, entity, 5., DamageSource::Generic).unwrap();
+        assert_eq!(*game.ecs.get::<Health>(entity).unwrap(), Health(15.));
+
+        *inventory.item(Area::Chestplate, 0).unwrap() = Some(enchanted(
+            Item::IronChestplate,
+            EnchantmentKind::Protection,
+            4,
+        ));
+        damage(&mut game, entity, 5., DamageSource::Generic).unwrap();
+        // 16% of the damage is blocked
+        let health = game.ecs.get::<Health>(entity).unwrap().0;
+        assert!((health - 10.8).abs() < 1e-4);
+    }
+
+    #[test]
+    fn pvp_toggle() {
+        let mut game = Game::new();
+        let attacker = game.ecs.spawn((Player, Health(20.)));
+        let target = game.ecs.spawn((Player, Health(20.)));
+        let zombie = game.ecs.spawn((Health(20.),));
+
+        game.pvp = false;
+        damage(&mut game, target, 5., DamageSource::Entity(attacker)).unwrap();
+        assert_eq!(*game.ecs.get::<Health>(target).unwrap(), Health(20.));
+        assert!(game.ecs.get::<EntityDamageEvent>(target).is_err());
+
+        // Mobs and the environment can still hurt players, and players mobs
+        damage(&mut game, target, 5., DamageSource::Entity(zombie)).unwrap();
+        damage(&mut game, target, 1., DamageSource::Generic).unwrap();
+        damage(&mut game, zombie, 5., DamageSource::Entity(attacker)).unwrap();
+        assert_eq!(*game.ecs.get::<Health>(target).unwrap(), Health(14.));
+        assert_eq!(*game.ecs.get::<Health>(zombie).unwrap(), Health(15.));
+
+        game.pvp = true;
+        damage(&mut game, target, 5., DamageSource::Entity(attacker)).unwrap();
+        assert_eq!(*game.ecs.get::<Health>(target).unwrap(), Health(9.));
+    }
+
+    #[test]
+    fn sharpness_increases_attack_damage() {
+        let sword = ItemStack::new(Item::IronSword, 1);
+        assert_eq!(attack_damage(None), 1.);
+        assert_eq!(attack_damage(Some(&sword)), 6.);
+        assert_eq!(
+            attack_damage(Some(&enchanted(
+                Item::IronSword,
+                EnchantmentKind::Sharpness,
+                3
+            ))),
+            8.
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! Player death.
+//!
+//! When a player's [`Health`] drops to zero, they trigger a
+//! [`PlayerDeathEvent`] and drop their whole inventory,
+//! unless the `keepInventory` game rule is enabled.
+//! Dead players stay dead until they [`respawn`] or
+//! their health is raised above zero.
+
+use base::{GameRules, Inventory, Position};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::{entities::Player, entity_init::EntityInit};
+
+use crate::{
+    damage::{Health, MaxHealth},
+    events::PlayerDeathEvent,
+    Game, Level,
+};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(kill_players);
+}
+
+/// Marker component for players that died
+/// and have yet to respawn.
+#[derive(Copy, Clone, Debug)]
+pub struct Dead;
+
+/// Respawns a dead player at the world spawn with full health.
+///
+/// Returns whether the player was dead.
+pub fn respawn(game: &mut Game, player: Entity) -> SysResult<bool> {
+    if game.ecs.remove::<Dead>(player).is_err() {
+        return Ok(false);
+    }
+    let health = game
+        .ecs
+        .get::<MaxHealth>(player)
+        .map_or(Health::PLAYER_MAX, |max_health| Health(max_health.0));
+    let spawn = game.resources.get::<Level>()?.spawn_position();
+    game.ecs.insert(player, health)?;
+    game.ecs.insert(player, spawn)?;
+    Ok(true)
+}
+
+fn kill_players(game: &mut Game) -> SysResult {
+    // Players healed by other means are alive again
+    let revived: Vec<Entity> = game
+        .ecs
+        .query::<(&Health, &Dead)>()
+        .iter()
+        .filter(|(_, (health, _))| !health.is_dead())
+        .map(|(entity, _)| entity)
+        .collect();
+    for player in revived {
+        game.ecs.remove::<Dead>(player)?;
+    }
+
+    let mut dying: Vec<(Entity, Position)> = game
+        .ecs
+        .query::<(&Player, &Health, &Position, Option<&Dead>)>()
+        .iter()
+        .filter(|(_, (_, health, _, dead))| health.is_dead() && dead.is_none())
+        .map(|(entity, (_, _, &position, _))| (entity, position))
+        .collect();
+    game.sort_for_tick(&mut dying);
+
+    let keep_inventory = game.resources.get::<GameRules>()?.keep_inventory;
+    for (player, position) in dying {
+        if !keep_inventory {
+            drop_inventory(game, player, position);
+        }
+        game.ecs.insert(player, Dead)?;
+        game.ecs
+            .insert_entity_event(player, PlayerDeathEvent { keep_inventory })?;
+    }
+    Ok(())
+}
+
+/// Empties the inventory of `player`, spawning
+/// its contents as item entities at `position`.
+fn drop_inventory(game: &mut Game, player: Entity, position: Position) {
+    let items = match game.ecs.get::<Inventory>(player) {
+        Ok(inventory) => inventory.take_all(),
+        Err(_) => return,
+    };
+    for item in items {
+        let mut builder = game.create_entity_builder(position, EntityInit::Item);
+        builder.add(item);
+        game.spawn_entity(builder);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{position, Area, BlockPosition, Item, ItemStack};
+
+    use super::*;
+
+    fn kill_player(keep_inventory: bool) -> (Game, Entity, Inventory) {
+        let mut game = Game::new();
+        game.add_entity_spawn_callback(crate::entities::add_entity_components);
+        game.resources
+            .get_mut::<GameRules>()
+            .unwrap()
+            .keep_inventory = keep_inventory;
+
+        let inventory = Inventory::player();
+        *inventory.item(Area::Hotbar, 0).unwrap() = Some(ItemStack::new(Item::Diamond, 3));
+        *inventory.item(Area::Helmet, 0).unwrap() = Some(ItemStack::new(Item::IronHelmet, 1));
+        let player = game.ecs.spawn((
+            Player,
+            Health(0.),
+            position!(0.0, 64.0, 0.0),
+            inventory.new_handle(),
+        ));
+
+        kill_players(&mut game).unwrap();
+        (game, player, inventory)
+    }
+
+    fn dropped_items(game: &Game) -> Vec<ItemStack> {
+        let mut items: Vec<ItemStack> = game
+            .ecs
+            .query::<&ItemStack>()
+            .iter()
+            .map(|(_, item)| item.clone())
+            .collect();
+        items.sort_by_key(|item| item.item.id());
+        items
+    }
+
+    #[test]
+    fn death_drops_inventory() {
+        let (game, player, inventory) = kill_player(false);
+
+        assert!(inventory.to_vec().iter().all(Option::is_none));
+        assert_eq!(
+            dropped_items(&game),
+            vec![
+                ItemStack::new(Item::Diamond, 3),
+                ItemStack::new(Item::IronHelmet, 1)
+            ]
+        );
+        assert!(
+            !game
+                .ecs
+                .get::<PlayerDeathEvent>(player)
+                .unwrap()
+                .keep_inventory
+        );
+        assert!(game.ecs.get::<Dead>(player).is_ok());
+    }
+
+    #[test]
+    fn keep_inventory_retains_items() {
+        let (game, player, inventory) = kill_player(true);
+
+        assert_eq!(
+            inventory.item(Area::Hotbar, 0).unwrap().clone(),
+            Some(ItemStack::new(Item::Diamond, 3))
+        );
+        assert_eq!(
+            inventory.item(Area::Helmet, 0).unwrap().clone(),
+            Some(ItemStack::new(Item::IronHelmet, 1))
+        );
+        assert!(dropped_items(&game).is_empty());
+        assert!(
+            game.ecs
+                .get::<PlayerDeathEvent>(player)
+                .unwrap()
+                .keep_inventory
+        );
+    }
+
+    #[test]
+    fn dead_players_die_once() {
+        let (mut game, player, _) = kill_player(false);
+        game.ecs.remove::<PlayerDeathEvent>(player).unwrap();
+
+        kill_players(&mut game).unwrap();
+        assert!(game.ecs.get::<PlayerDeathEvent>(player).is_err());
+    }
+
+    #[test]
+    fn respawned_players_can_die_again() {
+        let (mut game, player, _) = kill_player(false);
+        game.ecs.remove::<PlayerDeathEvent>(player).unwrap();
+        game.resources
+            .get_mut::<Level>()
+            .unwrap()
+            .set_spawn(BlockPosition::new(10, 70, -5));
+
+        assert!(respawn(&mut game, player).unwrap());
+        assert!(!respawn(&mut game, player).unwrap());
+        assert!(game.ecs.get::<Dead>(player).is_err());
+        assert_eq!(*game.ecs.get::<Health>(player).unwrap(), Health::PLAYER_MAX);
+        assert_eq!(
+            *game.ecs.get::<Position>(player).unwrap(),
+            position!(10.5, 70.0, -4.5)
+        );
+
+        *game.ecs.get_mut::<Health>(player).unwrap() = Health(0.);
+        kill_players(&mut game).unwrap();
+        assert!(game.ecs.get::<PlayerDeathEvent>(player).is_ok());
+        assert!(game.ecs.get::<Dead>(player).is_ok());
+    }
+
+    #[test]
+    fn healed_players_are_revived() {
+        let (mut game, player, _) = kill_player(false);
+        game.ecs.remove::<PlayerDeathEvent>(player).unwrap();
+
+        *game.ecs.get_mut::<Health>(player).unwrap() = Health(5.);
+        kill_players(&mut game).unwrap();
+        assert!(game.ecs.get::<Dead>(player).is_err());
+
+        *game.ecs.get_mut::<Health>(player).unwrap() = Health(0.);
+        kill_players(&mut game).unwrap();
+        assert!(game.ecs.get::<PlayerDeathEvent>(player).is_ok());
+    }
+}
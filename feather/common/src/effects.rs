@@ -0,0 +1,270 @@
+//! Status effects like speed and regeneration.
+//!
+//! An entity's active effects are stored in its [`StatusEffects`]
+//! component. Each tick, the effects are applied and their
+//! remaining durations decrease until they expire.
+
+use ecs::{Entity, SysResult, SystemExecutor};
+
+use crate::{
+    damage::{self, DamageSource, Health},
+    Game,
+};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(update_status_effects);
+}
+
+/// A kind of status effect.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StatusEffect {
+    Speed,
+    Slowness,
+    Regeneration,
+    Poison,
+}
+
+impl StatusEffect {
+    /// Gets the vanilla ID of this effect.
+    pub fn id(self) -> u8 {
+        match self {
+            StatusEffect::Speed => 1,
+            StatusEffect::Slowness => 2,
+            StatusEffect::Regeneration => 10,
+            StatusEffect::Poison => 19,
+        }
+    }
+
+    /// Gets the name of this effect, e.g. `regeneration`.
+    pub fn name(self) -> &'static str {
+        match self {
+            StatusEffect::Speed => "speed",
+            StatusEffect::Slowness => "slowness",
+            StatusEffect::Regeneration => "regeneration",
+            StatusEffect::Poison => "poison",
+        }
+    }
+
+    /// Gets an effect by its name, with or
+    /// without the `minecraft:` namespace.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.strip_prefix("minecraft:").unwrap_or(name) {
+            "speed" => Some(StatusEffect::Speed),
+            "slowness" => Some(StatusEffect::Slowness),
+            "regeneration" => Some(StatusEffect::Regeneration),
+            "poison" => Some(StatusEffect::Poison),
+            _ => None,
+        }
+    }
+
+    /// Returns the interval in ticks between two applications
+    /// of this effect, or `None` if the effect isn't applied
+    /// periodically.
+    fn interval(self, amplifier: u8) -> Option<u32> {
+        let base: u32 = match self {
+            StatusEffect::Regeneration => 50,
+            StatusEffect::Poison => 25,
+            StatusEffect::Speed | StatusEffect::Slowness => return None,
+        };
+        Some(base.checked_shr(amplifier as u32).unwrap_or(0).max(1))
+    }
+}
+
+/// An active status effect.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StatusEffectInstance {
+    pub effect: StatusEffect,
+    /// The level of the effect minus one.
+    pub amplifier: u8,
+    /// Remaining duration in ticks.
+    pub duration: u32,
+}
+
+impl StatusEffectInstance {
+    pub fn new(effect: StatusEffect, amplifier: u8, duration: u32) -> Self {
+        Self {
+            effect,
+            amplifier,
+            duration,
+        }
+    }
+}
+
+/// Component storing the active status effects of an entity.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StatusEffects {
+    effects: Vec<StatusEffectInstance>,
+}
+
+impl StatusEffects {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an effect. An active effect of the same kind is
+    /// only replaced if the new effect is stronger, or equally
+    /// strong and longer. Returns whether the effect was added.
+    pub fn add(&mut self, instance: StatusEffectInstance) -> bool {
+        match self
+            .effects
+            .iter_mut()
+            .find(|active| active.effect == instance.effect)
+        {
+            Some(active) => {
+                if instance.amplifier > active.amplifier
+                    || (instance.amplifier == active.amplifier
+                        && instance.duration > active.duration)
+                {
+                    *active = instance;
+                    true
+                } else {
+                    false
+                }
+            }
+            None => {
+                self.effects.push(instance);
+                true
+            }
+        }
+    }
+
+    /// Removes an effect, returning it if it was active.
+    pub fn remove(&mut self, effect: StatusEffect) -> Option<StatusEffectInstance> {
+        let index = self
+            .effects
+            .iter()
+            .position(|active| active.effect == effect)?;
+        Some(self.effects.remove(index))
+    }
+
+    /// Removes all effects, returning them.
+    pub fn clear(&mut self) -> Vec<StatusEffectInstance> {
+        std::mem::take(&mut self.effects)
+    }
+
+    /// Gets the active effect of the given kind.
+    pub fn get(&self, effect: StatusEffect) -> Option<&StatusEffectInstance> {
+        self.effects.iter().find(|active| active.effect == effect)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StatusEffectInstance> + '_ {
+        self.effects.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+}
+
+/// Applies status effects and removes expired ones.
+fn update_status_effects(game: &mut Game) -> SysResult {
+    let mut entities: Vec<(Entity, Vec<StatusEffectInstance>)> = game
+        .ecs
+        .query::<&StatusEffects>()
+        .iter()
+        .filter(|(_, effects)| !effects.is_empty())
+        .map(|(entity, effects)| (entity, effects.effects.clone()))
+        .collect();
+    game.sort_for_tick(&mut entities);
+
+    for (entity, effects) in entities {
+        for instance in effects {
+            apply_effect(game, entity, instance)?;
+        }
+
+        let mut effects = game.ecs.get_mut::<StatusEffects>(entity)?;
+        for instance in &mut effects.effects {
+            instance.duration = instance.duration.saturating_sub(1);
+        }
+        effects.effects.retain(|instance| instance.duration > 0);
+    }
+    Ok(())
+}
+
+fn apply_effect(game: &mut Game, entity: Entity, instance: StatusEffectInstance) -> SysResult {
+    let interval = match instance.effect.interval(instance.amplifier) {
+        Some(interval) => interval,
+        None => return Ok(()),
+    };
+    if instance.duration % interval != 0 {
+        return Ok(());
+    }
+
+    match instance.effect {
+        StatusEffect::Regeneration => damage::heal(game, entity, 1.),
+        StatusEffect::Poison => {
+            // Poison never kills
+            let health = game.ecs.get::<Health>(entity).map_or(0., |health| health.0);
+            if health > 1. {
+                damage::damage(game, entity, 1., DamageSource::Magic)?;
+            }
+        }
+        StatusEffect::Speed | StatusEffect::Slowness => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::damage::MaxHealth;
+
+    use super::*;
+
+    fn run_ticks(game: &mut Game, ticks: u32) {
+        for _ in 0..ticks {
+            update_status_effects(game).unwrap();
+        }
+    }
+
+    #[test]
+    fn regeneration_heals_over_time() {
+        let mut game = Game::new();
+        let mut effects = StatusEffects::new();
+        effects.add(StatusEffectInstance::new(
+            StatusEffect::Regeneration,
+            1,
+            100,
+        ));
+        let entity = game.ecs.spawn((Health(10.), MaxHealth(20.), effects));
+
+        // Regeneration II heals every 25 ticks
+        run_ticks(&mut game, 1);
+        assert_eq!(*game.ecs.get::<Health>(entity).unwrap(), Health(11.));
+        run_ticks(&mut game, 24);
+        assert_eq!(*game.ecs.get::<Health>(entity).unwrap(), Health(11.));
+        run_ticks(&mut game, 75);
+        assert_eq!(*game.ecs.get::<Health>(entity).unwrap(), Health(14.));
+
+        // Expired
+        assert!(game.ecs.get::<StatusEffects>(entity).unwrap().is_empty());
+        run_ticks(&mut game, 100);
+        assert_eq!(*game.ecs.get::<Health>(entity).unwrap(), Health(14.));
+    }
+
+    #[test]
+    fn poison_does_not_kill() {
+        let mut game = Game::new();
+        let mut effects = StatusEffects::new();
+        effects.add(StatusEffectInstance::new(StatusEffect::Poison, 4, 200));
+        let entity = game.ecs.spawn((Health(5.), effects));
+
+        run_ticks(&mut game, 200);
+        assert_eq!(*game.ecs.get::<Health>(entity).unwrap(), Health(1.));
+    }
+
+    #[test]
+    fn clear_effect() {
+        let mut effects = StatusEffects::new();
+        let speed = StatusEffectInstance::new(StatusEffect::Speed, 0, 600);
+        assert!(effects.add(speed));
+        assert!(effects.add(StatusEffectInstance::new(StatusEffect::Slowness, 1, 600)));
+        // Weaker effects don't replace stronger ones
+        assert!(!effects.add(StatusEffectInstance::new(StatusEffect::Slowness, 0, 1200)));
+
+        assert_eq!(effects.remove(StatusEffect::Speed), Some(speed));
+        assert_eq!(effects.get(StatusEffect::Speed), None);
+        assert_eq!(effects.remove(StatusEffect::Speed), None);
+        assert_eq!(effects.clear().len(), 1);
+        assert!(effects.is_empty());
+    }
+}
@@ -1,8 +1,69 @@
-use base::EntityKind;
+use base::{Area, EntityKind, ItemStack};
 use ecs::EntityBuilder;
 use quill_common::entities::ArmorStand;
 
+use crate::window::armor_area;
+
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
-    builder.add(ArmorStand).add(EntityKind::ArmorStand);
+    builder
+        .add(ArmorStand)
+        .add(ArmorStandEquipment::default())
+        .add(EntityKind::ArmorStand);
+}
+
+/// The armor worn by an armor stand.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ArmorStandEquipment {
+    pub helmet: Option<ItemStack>,
+    pub chestplate: Option<ItemStack>,
+    pub leggings: Option<ItemStack>,
+    pub boots: Option<ItemStack>,
+}
+
+impl ArmorStandEquipment {
+    /// Gets the item in an armor slot. `area` must be one of
+    /// `Helmet`, `Chestplate`, `Leggings` or `Boots`.
+    pub fn slot_mut(&mut self, area: Area) -> Option<&mut Option<ItemStack>> {
+        match area {
+            Area::Helmet => Some(&mut self.helmet),
+            Area::Chestplate => Some(&mut self.chestplate),
+            Area::Leggings => Some(&mut self.leggings),
+            Area::Boots => Some(&mut self.boots),
+            _ => None,
+        }
+    }
+
+    /// Handles a player using the armor stand while holding `held`.
+    /// `target_y` is the height on the stand that was clicked,
+    /// relative to its feet.
+    ///
+    /// Held armor is swapped with the armor in its slot. With an empty
+    /// hand, the armor at the clicked height is taken.
+    /// Returns the slot that changed, if any.
+    pub fn interact(&mut self, target_y: f64, held: &mut Option<ItemStack>) -> Option<Area> {
+        let area = match held {
+            Some(stack) => armor_area(stack.item())?,
+            None => self.clicked_slot(target_y)?,
+        };
+        let slot = self.slot_mut(area)?;
+        std::mem::swap(slot, held);
+        Some(area)
+    }
+
+    /// Determines which piece of armor is clicked at a height,
+    /// using the same bounds as vanilla.
+    fn clicked_slot(&self, target_y: f64) -> Option<Area> {
+        if (0.1..0.55).contains(&target_y) && self.boots.is_some() {
+            Some(Area::Boots)
+        } else if (0.9..1.6).contains(&target_y) && self.chestplate.is_some() {
+            Some(Area::Chestplate)
+        } else if (0.4..1.2).contains(&target_y) && self.leggings.is_some() {
+            Some(Area::Leggings)
+        } else if target_y >= 1.6 && self.helmet.is_some() {
+            Some(Area::Helmet)
+        } else {
+            None
+        }
+    }
 }
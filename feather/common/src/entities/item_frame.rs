@@ -1,8 +1,58 @@
-use base::EntityKind;
+use base::{EntityKind, ItemStack};
 use ecs::EntityBuilder;
 use quill_common::entities::ItemFrame;
 
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
-    builder.add(ItemFrame).add(EntityKind::ItemFrame);
+    builder
+        .add(ItemFrame)
+        .add(ItemFrameContents::default())
+        .add(EntityKind::ItemFrame);
+}
+
+/// Number of distinct rotations of an item in a frame.
+const ROTATIONS: u8 = 8;
+
+/// The item displayed in an item frame.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemFrameContents {
+    pub item: Option<ItemStack>,
+    /// Rotation of the item in steps of 45 degrees.
+    pub rotation: u8,
+}
+
+impl ItemFrameContents {
+    /// Handles a player using the frame while holding `held`.
+    ///
+    /// An empty frame takes one item from `held`; it is only removed
+    /// from `held` if `consume` is set. A filled frame rotates its item.
+    /// Returns whether the frame changed.
+    pub fn interact(&mut self, held: &mut Option<ItemStack>, consume: bool) -> bool {
+        if self.item.is_some() {
+            self.rotation = (self.rotation + 1) % ROTATIONS;
+            return true;
+        }
+
+        let stack = match held {
+            Some(stack) => stack,
+            None => return false,
+        };
+        self.item = Some(if consume {
+            stack.take(1)
+        } else {
+            ItemStack::new(stack.item(), 1)
+        });
+        self.rotation = 0;
+        if stack.count() == 0 {
+            *held = None;
+        }
+        true
+    }
+
+    /// Removes the item from the frame, as done
+    /// when a player attacks the frame.
+    pub fn take_item(&mut self) -> Option<ItemStack> {
+        self.rotation = 0;
+        self.item.take()
+    }
 }
@@ -6,6 +6,11 @@ use quill_common::{
     entities::Player,
 };
 
+use crate::{
+    combat::AttackCooldown,
+    damage::{Health, MaxHealth},
+};
+
 pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
     builder
@@ -13,6 +18,9 @@ pub fn build_default(builder: &mut EntityBuilder) {
         .add(CreativeFlying(false))
         .add(Sneaking(false))
         .add(Sprinting(false))
+        .add(Health::PLAYER_MAX)
+        .add(MaxHealth(Health::PLAYER_MAX.0))
+        .add(AttackCooldown::default())
         .add(EntityKind::Player);
 }
 
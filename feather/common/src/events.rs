@@ -1,6 +1,7 @@
-use base::{ChunkHandle, ChunkPosition};
+use base::{ChunkHandle, ChunkPosition, ItemStack};
+use ecs::Entity;
 
-use crate::view::View;
+use crate::{damage::DamageSource, physics::SweepHit, view::View};
 
 mod block_change;
 mod plugin_message;
@@ -73,3 +74,35 @@ pub struct EntityRemoveEvent;
 /// Triggered when an entity is added into the world.
 #[derive(Debug)]
 pub struct EntityCreateEvent;
+
+/// Triggered on a player when they drop an item,
+/// for example by clicking outside their window.
+#[derive(Debug)]
+pub struct DropItemEvent {
+    /// The dropped items.
+    pub item: ItemStack,
+    /// The item entity that was spawned.
+    pub entity: Entity,
+}
+
+/// Triggered on an entity when it takes damage.
+#[derive(Debug, Clone)]
+pub struct EntityDamageEvent {
+    pub amount: f32,
+    pub source: DamageSource,
+}
+
+/// Triggered on a player when their health drops to zero.
+#[derive(Debug, Clone)]
+pub struct PlayerDeathEvent {
+    /// Whether the player kept their inventory
+    /// because of the `keepInventory` game rule.
+    pub keep_inventory: bool,
+}
+
+/// Triggered on a projectile when it hits a block
+/// or an entity, right before it is removed.
+#[derive(Debug, Clone)]
+pub struct ProjectileHitEvent {
+    pub hit: SweepHit,
+}
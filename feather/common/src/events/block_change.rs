@@ -1,5 +1,6 @@
-use std::iter;
+use std::{iter, sync::Arc};
 
+use ahash::AHashMap;
 use base::{
     chunk::{SECTION_HEIGHT, SECTION_VOLUME},
     BlockPosition, ChunkPosition,
@@ -32,22 +33,36 @@ impl BlockChangeEvent {
         }
     }
 
+    /// Creates an event affecting the given blocks.
+    ///
+    /// Used for bulk updates that don't fill whole chunk
+    /// sections, so they can be sent to clients at once.
+    pub fn many(positions: impl Into<Arc<[BlockPosition]>>) -> Self {
+        Self {
+            changes: BlockChanges::Many {
+                positions: positions.into(),
+            },
+        }
+    }
+
     /// Determines the number of blocks that were
     /// changed in this block change event.
     pub fn count(&self) -> usize {
         match &self.changes {
             BlockChanges::Single { .. } => 1,
             BlockChanges::FillChunkSection { .. } => SECTION_VOLUME,
+            BlockChanges::Many { positions } => positions.len(),
         }
     }
 
     /// Returns an iterator over block positions affected by this block change.
     pub fn iter_changed_blocks(&self) -> impl Iterator<Item = BlockPosition> + '_ {
         match &self.changes {
-            BlockChanges::Single { pos } => Either::Left(iter::once(*pos)),
+            BlockChanges::Single { pos } => Either::Left(Either::Left(iter::once(*pos))),
             BlockChanges::FillChunkSection { chunk, section } => {
-                Either::Right(iter_section_blocks(*chunk, *section))
+                Either::Left(Either::Right(iter_section_blocks(*chunk, *section)))
             }
+            BlockChanges::Many { positions } => Either::Right(positions.iter().copied()),
         }
     }
 
@@ -59,11 +74,27 @@ impl BlockChangeEvent {
         &self,
     ) -> impl Iterator<Item = (ChunkPosition, usize, usize)> + '_ {
         match &self.changes {
-            BlockChanges::Single { pos } => {
-                iter::once((pos.chunk(), pos.y as usize / SECTION_HEIGHT, 1))
-            }
+            BlockChanges::Single { pos } => Either::Left(iter::once((
+                pos.chunk(),
+                pos.y as usize / SECTION_HEIGHT,
+                1,
+            ))),
             BlockChanges::FillChunkSection { chunk, section } => {
-                iter::once((*chunk, *section as usize, SECTION_VOLUME))
+                Either::Left(iter::once((*chunk, *section as usize, SECTION_VOLUME)))
+            }
+            BlockChanges::Many { positions } => {
+                let mut sections: AHashMap<(ChunkPosition, usize), usize> = AHashMap::new();
+                for pos in positions.iter() {
+                    *sections
+                        .entry((pos.chunk(), pos.y as usize / SECTION_HEIGHT))
+                        .or_default() += 1;
+                }
+                let mut sections: Vec<_> = sections
+                    .into_iter()
+                    .map(|((chunk, section), count)| (chunk, section, count))
+                    .collect();
+                sections.sort_unstable_by_key(|(chunk, section, _)| (chunk.x, chunk.z, *section));
+                Either::Right(sections.into_iter())
             }
         }
     }
@@ -87,6 +118,8 @@ enum BlockChanges {
     Single { pos: BlockPosition },
     /// A whole chunk section was filled with the same block.
     FillChunkSection { chunk: ChunkPosition, section: u32 },
+    /// Any number of blocks were changed.
+    Many { positions: Arc<[BlockPosition]> },
 }
 
 #[cfg(test)]
@@ -121,6 +154,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn create_many() {
+        let positions = vec![
+            BlockPosition::new(0, 0, 0),
+            BlockPosition::new(1, 2, 3),
+            BlockPosition::new(0, 20, 0),
+            BlockPosition::new(-1, 20, 0),
+        ];
+        let event = BlockChangeEvent::many(positions.clone());
+        assert_eq!(event.count(), 4);
+        assert_eq!(event.iter_changed_blocks().collect::<Vec<_>>(), positions);
+        assert_eq!(
+            event.iter_affected_chunk_sections().collect::<Vec<_>>(),
+            vec![
+                (ChunkPosition::new(-1, 0), 1, 1),
+                (ChunkPosition::new(0, 0), 0, 2),
+                (ChunkPosition::new(0, 0), 1, 1),
+            ]
+        );
+    }
+
     #[test]
     fn test_iter_section_blocks() {
         let blocks: Vec<BlockPosition> =
@@ -1,6 +1,6 @@
 use std::{cell::RefCell, mem, rc::Rc, sync::Arc};
 
-use base::{BlockId, BlockPosition, ChunkPosition, Position, Text, Title};
+use base::{BlockId, BlockPosition, ChunkPosition, Difficulty, GameRules, Position, Text, Title};
 use ecs::{
     Ecs, Entity, EntityBuilder, HasEcs, HasResources, NoSuchEntity, Resources, SysResult,
     SystemExecutor,
@@ -11,7 +11,7 @@ use crate::{
     chat::{ChatKind, ChatMessage},
     chunk::entities::ChunkEntities,
     events::{BlockChangeEvent, EntityCreateEvent, EntityRemoveEvent, PlayerJoinEvent},
-    ChatBox, World,
+    ChatBox, GameSnapshot, Level, TickMetrics, World, WorldBorder,
 };
 
 type EntitySpawnCallback = Box<dyn FnMut(&mut EntityBuilder, &EntityInit)>;
@@ -42,6 +42,10 @@ pub struct Game {
 
     /// User-defined resources.
     ///
+    /// Always contains the [`GameRules`], the [`Difficulty`], the [`Level`],
+    /// the [`WorldBorder`] and the [`TickMetrics`];
+    /// the rest is added by the server and plugins.
+    ///
     /// Stored in an `Arc` for borrow-checker purposes.
     pub resources: Arc<Resources>,
 
@@ -51,6 +55,19 @@ pub struct Game {
     /// Total ticks elapsed since the server started.
     pub tick_count: u64,
 
+    /// Whether systems should process entities and block
+    /// updates in a deterministic order (by entity ID or position)
+    /// instead of the order yielded by the ECS or hash maps.
+    ///
+    /// Useful for reproducible tests and recordings.
+    pub deterministic_ticking: bool,
+
+    /// Whether players can damage each other.
+    ///
+    /// Damage dealt by mobs and the environment
+    /// is not affected.
+    pub pvp: bool,
+
     entity_spawn_callbacks: Vec<EntitySpawnCallback>,
 
     entity_builder: EntityBuilder,
@@ -65,18 +82,40 @@ impl Default for Game {
 impl Game {
     /// Creates a new, empty `Game`.
     pub fn new() -> Self {
+        let mut resources = Resources::new();
+        resources.insert(GameRules::default());
+        resources.insert(Difficulty::default());
+        resources.insert(Level::default());
+        resources.insert(WorldBorder::default());
+        resources.insert(TickMetrics::default());
         Self {
             world: World::new(),
             ecs: Ecs::new(),
             system_executor: Rc::new(RefCell::new(SystemExecutor::new())),
-            resources: Arc::new(Resources::new()),
+            resources: Arc::new(resources),
             chunk_entities: ChunkEntities::default(),
             tick_count: 0,
+            deterministic_ticking: false,
+            pvp: true,
             entity_spawn_callbacks: Vec::new(),
             entity_builder: EntityBuilder::new(),
         }
     }
 
+    /// Captures a serializable snapshot of the game state
+    /// for debugging.
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot::new(self)
+    }
+
+    /// Sorts `items` by entity if [`deterministic_ticking`](Game::deterministic_ticking)
+    /// is enabled. Otherwise, leaves the order unchanged.
+    pub fn sort_for_tick<T>(&self, items: &mut [(Entity, T)]) {
+        if self.deterministic_ticking {
+            items.sort_by_key(|(entity, _)| *entity);
+        }
+    }
+
     /// Inserts a new resource.
     ///
     /// An existing resource with type `T` is overriden.
@@ -196,6 +235,27 @@ impl Game {
         was_successful
     }
 
+    /// Sets many blocks at once.
+    ///
+    /// Triggers a single `BlockChangeEvent` for all blocks that
+    /// were changed, so that they are sent to clients together.
+    /// Returns the number of blocks changed.
+    pub fn set_blocks(
+        &mut self,
+        blocks: impl IntoIterator<Item = (BlockPosition, BlockId)>,
+    ) -> usize {
+        let changed: Vec<BlockPosition> = blocks
+            .into_iter()
+            .filter(|&(pos, block)| self.world.set_block_at(pos, block))
+            .map(|(pos, _)| pos)
+            .collect();
+        let count = changed.len();
+        if count > 0 {
+            self.ecs.insert_event(BlockChangeEvent::many(changed));
+        }
+        count
+    }
+
     /// Fills the given chunk section (16x16x16 blocks).
     ///
     /// All blocks in the chunk section are overwritten with `block`.
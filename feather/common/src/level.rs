@@ -0,0 +1,87 @@
+//! World-wide data stored in `level.dat`.
+
+use std::{
+    fs::{self, File},
+    path::PathBuf,
+};
+
+use base::{
+    anvil::level::{LevelData, LevelLoadError},
+    position, BlockPosition, Position,
+};
+
+/// Height of the spawn point of new worlds.
+const DEFAULT_SPAWN_Y: i32 = 64;
+
+/// Resource storing the [`LevelData`] of the world.
+#[derive(Debug, Clone)]
+pub struct Level {
+    pub data: LevelData,
+    /// The `level.dat` file the data is saved to.
+    /// `None` for worlds without one.
+    path: Option<PathBuf>,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self {
+            data: LevelData {
+                spawn_y: DEFAULT_SPAWN_Y,
+                ..Default::default()
+            },
+            path: None,
+        }
+    }
+}
+
+impl Level {
+    /// Creates the level of a new world, which
+    /// is saved to the `level.dat` file at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: Some(path.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Loads the level stored in the `level.dat` file at `path`.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, LevelLoadError> {
+        let path = path.into();
+        let data = LevelData::load(&path)?;
+        Ok(Self {
+            data,
+            path: Some(path),
+        })
+    }
+
+    /// Writes the level back to its file.
+    /// Does nothing if the level has no file.
+    pub fn save(&self) -> anyhow::Result<()> {
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            self.data.save_to_file(&mut File::create(path)?)?;
+        }
+        Ok(())
+    }
+
+    /// Gets the world spawn point.
+    pub fn spawn(&self) -> BlockPosition {
+        BlockPosition::new(self.data.spawn_x, self.data.spawn_y, self.data.spawn_z)
+    }
+
+    /// Sets the world spawn point.
+    pub fn set_spawn(&mut self, spawn: BlockPosition) {
+        self.data.spawn_x = spawn.x;
+        self.data.spawn_y = spawn.y;
+        self.data.spawn_z = spawn.z;
+    }
+
+    /// Gets the position new players appear at,
+    /// centered on top of the spawn block.
+    pub fn spawn_position(&self) -> Position {
+        let spawn = self.spawn();
+        position!(spawn.x as f64 + 0.5, spawn.y as f64, spawn.z as f64 + 0.5)
+    }
+}
@@ -10,7 +10,7 @@ use ecs::SystemExecutor;
 pub use game::Game;
 
 mod tick_loop;
-pub use tick_loop::TickLoop;
+pub use tick_loop::{TickLoop, TickMetrics};
 
 pub mod view;
 
@@ -25,6 +25,14 @@ mod region_worker;
 pub mod world;
 pub use world::World;
 
+pub mod world_storage;
+pub use world_storage::{FileStorage, MemoryStorage, WorldStorage};
+
+pub mod world_border;
+pub use world_border::WorldBorder;
+
+pub mod block_entity;
+
 pub mod chat;
 pub use chat::ChatBox;
 
@@ -32,11 +40,32 @@ pub mod entities;
 
 pub mod interactable;
 
+pub mod level;
+pub use level::Level;
+
+pub mod combat;
+pub mod damage;
+pub mod death;
+pub mod effects;
+pub mod mining;
+pub mod mob_spawning;
+pub mod physics;
+pub mod projectile;
+
+pub mod snapshot;
+pub use snapshot::GameSnapshot;
+
 /// Registers gameplay systems with the given `Game` and `SystemExecutor`.
 pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
     view::register(game, systems);
     chunk::loading::register(game, systems);
     chunk::entities::register(systems);
+    projectile::register(systems);
+    effects::register(systems);
+    death::register(systems);
+    world_border::register(systems);
+    mob_spawning::register(game, systems);
+    physics::register(systems);
     interactable::register(game);
 
     game.add_entity_spawn_callback(entities::add_entity_components);
@@ -0,0 +1,96 @@
+//! Block breaking speed.
+
+use base::{BlockPosition, EnchantmentKind, ItemStack};
+use blocks::BlockKind;
+
+/// Fraction of the dig time after which a player may finish
+/// digging. Allows for latency between the client and server.
+const MIN_DIG_PROGRESS: f32 = 0.7;
+
+/// Component of a player digging a block that doesn't
+/// break instantly.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Digging {
+    pub position: BlockPosition,
+    /// The tick digging started on.
+    pub start_tick: u64,
+}
+
+impl Digging {
+    /// Returns whether a block taking `dig_time` ticks to break
+    /// has been dug for long enough by tick `tick`.
+    pub fn is_done(&self, tick: u64, dig_time: u32) -> bool {
+        let elapsed = tick.saturating_sub(self.start_tick);
+        elapsed as f32 >= dig_time as f32 * MIN_DIG_PROGRESS
+    }
+}
+
+/// Gets the number of ticks needed to break a block of kind `block`
+/// with `tool`, or `None` if the block can't be broken.
+///
+/// Efficiency speeds up tools that are effective against the block.
+pub fn dig_time(block: BlockKind, tool: Option<&ItemStack>) -> Option<u32> {
+    let hardness = block.hardness();
+    if !block.diggable() || hardness < 0. {
+        return None;
+    }
+
+    let mut speed = tool
+        .and_then(|tool| {
+            block
+                .dig_multipliers()
+                .iter()
+                .find(|(item, _)| *item == tool.item)
+        })
+        .map_or(1., |(_, multiplier)| *multiplier);
+    if speed > 1. {
+        let efficiency = tool.map_or(0, |tool| {
+            tool.enchantment_level(EnchantmentKind::Efficiency)
+        });
+        if efficiency > 0 {
+            speed += (efficiency as f32).powi(2) + 1.;
+        }
+    }
+
+    let can_harvest = match block.harvest_tools() {
+        Some(tools) => tool.map_or(false, |tool| tools.contains(&tool.item)),
+        None => true,
+    };
+    let progress_per_tick = speed / hardness / if can_harvest { 30. } else { 100. };
+    if progress_per_tick >= 1. {
+        // Instant breaking
+        return Some(0);
+    }
+    Some((1. / progress_per_tick).ceil() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{Enchantment, Item};
+
+    use super::*;
+
+    #[test]
+    fn efficiency_mines_faster() {
+        let pickaxe = ItemStack::new(Item::IronPickaxe, 1);
+        let efficient_pickaxe = ItemStack {
+            enchantments: vec![Enchantment::new(EnchantmentKind::Efficiency, 3)],
+            ..pickaxe.clone()
+        };
+
+        let normal = dig_time(BlockKind::Stone, Some(&pickaxe)).unwrap();
+        let efficient = dig_time(BlockKind::Stone, Some(&efficient_pickaxe)).unwrap();
+        assert_eq!(normal, 8);
+        assert_eq!(efficient, 3);
+        assert!(dig_time(BlockKind::Stone, None).unwrap() > normal);
+
+        // Efficiency doesn't help when using the wrong tool
+        let dirt = dig_time(BlockKind::Dirt, Some(&pickaxe));
+        assert_eq!(dig_time(BlockKind::Dirt, Some(&efficient_pickaxe)), dirt);
+    }
+
+    #[test]
+    fn unbreakable_blocks() {
+        assert_eq!(dig_time(BlockKind::Bedrock, None), None);
+    }
+}
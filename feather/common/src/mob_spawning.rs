@@ -0,0 +1,445 @@
+//! Natural mob spawning.
+//!
+//! Each tick, one random position in every loaded chunk is
+//! evaluated for each [`MobCategory`]. If the position is suitable
+//! and the category's mob cap isn't reached, a mob spawns there.
+//!
+//! The [`Difficulty`] scales the monster cap. On peaceful,
+//! monsters don't spawn and existing ones are removed.
+//!
+//! Light isn't computed yet, so the light levels stored in
+//! generated chunks are meaningless. Until it is, light is
+//! estimated from sky exposure: as there is no day-night cycle,
+//! positions open to the sky are lit and covered ones are dark.
+//!
+//! Only zombies and cows are spawned for now.
+
+use base::{
+    vec3, Biome, BlockId, BlockPosition, Chunk, ChunkPosition, Difficulty, EntityKind, GameRules,
+    Position, CHUNK_HEIGHT, CHUNK_WIDTH,
+};
+use blocks::BlockKind;
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::{entities::Player, entity_init::EntityInit};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::Game;
+
+/// Mobs don't spawn closer than this to a player.
+const MIN_PLAYER_DISTANCE: f64 = 24.;
+
+/// Creatures only attempt to spawn once per this many ticks.
+const CREATURE_SPAWN_INTERVAL: u64 = 400;
+
+/// Estimated light level of positions open to the sky.
+const DAYLIGHT: u8 = 15;
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(MobSpawner::new(SpawnSettings::default()));
+    systems.group::<MobSpawner>().add_system(spawn_mobs);
+}
+
+/// Configures natural mob spawning.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpawnSettings {
+    pub spawn_monsters: bool,
+    pub spawn_animals: bool,
+    /// Maximum number of monsters in the world.
+    pub monster_cap: usize,
+    /// Maximum number of creatures (animals) in the world.
+    pub creature_cap: usize,
+}
+
+impl Default for SpawnSettings {
+    fn default() -> Self {
+        Self {
+            spawn_monsters: true,
+            spawn_animals: true,
+            monster_cap: 70,
+            creature_cap: 10,
+        }
+    }
+}
+
+/// Resource storing the state of natural mob spawning.
+pub struct MobSpawner {
+    pub settings: SpawnSettings,
+    rng: StdRng,
+}
+
+impl MobSpawner {
+    pub fn new(settings: SpawnSettings) -> Self {
+        Self {
+            settings,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Creates a `MobSpawner` whose choices are
+    /// determined by `seed`.
+    pub fn with_seed(settings: SpawnSettings, seed: u64) -> Self {
+        Self {
+            settings,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    fn is_enabled(&self, category: MobCategory) -> bool {
+        match category {
+            MobCategory::Monster => self.settings.spawn_monsters,
+            MobCategory::Creature => self.settings.spawn_animals,
+        }
+    }
+
+    /// Gets the mob cap of `category` at `difficulty`.
+    fn cap(&self, category: MobCategory, difficulty: Difficulty) -> usize {
+        match (category, difficulty) {
+            (MobCategory::Monster, Difficulty::Peaceful) => 0,
+            (MobCategory::Monster, Difficulty::Easy) => self.settings.monster_cap / 2,
+            (MobCategory::Monster, _) => self.settings.monster_cap,
+            (MobCategory::Creature, _) => self.settings.creature_cap,
+        }
+    }
+}
+
+/// A group of mobs sharing spawn rules and a mob cap.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MobCategory {
+    /// Hostile mobs, spawning in the dark.
+    Monster,
+    /// Passive animals, spawning on grass in the light.
+    Creature,
+}
+
+impl MobCategory {
+    const ALL: [MobCategory; 2] = [MobCategory::Monster, MobCategory::Creature];
+
+    /// Gets the category counting towards the mob cap
+    /// of the given entity type, if any.
+    pub fn of(kind: EntityKind) -> Option<Self> {
+        match kind {
+            EntityKind::Zombie
+            | EntityKind::ZombieVillager
+            | EntityKind::Husk
+            | EntityKind::Drowned
+            | EntityKind::Skeleton
+            | EntityKind::Stray
+            | EntityKind::Creeper
+            | EntityKind::Spider
+            | EntityKind::Enderman
+            | EntityKind::Slime
+            | EntityKind::Witch => Some(MobCategory::Monster),
+            EntityKind::Cow
+            | EntityKind::Pig
+            | EntityKind::Sheep
+            | EntityKind::Chicken
+            | EntityKind::Rabbit
+            | EntityKind::Horse => Some(MobCategory::Creature),
+            _ => None,
+        }
+    }
+
+    fn entity_init(self) -> EntityInit {
+        match self {
+            MobCategory::Monster => EntityInit::Zombie,
+            MobCategory::Creature => EntityInit::Cow,
+        }
+    }
+
+    /// Determines whether a mob of this category can spawn
+    /// at the given chunk-relative coordinates.
+    fn can_spawn_at(self, chunk: &Chunk, x: usize, y: usize, z: usize) -> bool {
+        let is_clear = |y| chunk.block_at(x, y, z).map_or(false, is_passable);
+        let below = match chunk.block_at(x, y - 1, z) {
+            Some(block) => block,
+            None => return false,
+        };
+        if !below.is_solid() || !is_clear(y) || !is_clear(y + 1) {
+            return false;
+        }
+
+        let light = estimated_light(chunk, x, y, z);
+        let biome = chunk.biomes().get_at_block(x, y, z);
+        match self {
+            MobCategory::Monster => light <= 7 && is_monster_biome(biome),
+            MobCategory::Creature => light >= 9 && below.kind() == BlockKind::GrassBlock,
+        }
+    }
+}
+
+/// Estimates the light level at a passable position from
+/// whether it is open to the sky.
+fn estimated_light(chunk: &Chunk, x: usize, y: usize, z: usize) -> u8 {
+    let top = chunk.heightmaps().motion_blocking.height(x, z).unwrap_or(0);
+    if y >= top {
+        DAYLIGHT
+    } else {
+        0
+    }
+}
+
+fn is_passable(block: BlockId) -> bool {
+    !block.is_solid() && !block.is_fluid()
+}
+
+fn is_monster_biome(biome: Biome) -> bool {
+    !matches!(
+        biome,
+        Biome::MushroomFields
+            | Biome::MushroomFieldShore
+            | Biome::NetherWastes
+            | Biome::SoulSandValley
+            | Biome::CrimsonForest
+            | Biome::WarpedForest
+            | Biome::BasaltDeltas
+            | Biome::TheEnd
+            | Biome::SmallEndIslands
+            | Biome::EndMidlands
+            | Biome::EndHighlands
+            | Biome::EndBarrens
+            | Biome::TheVoid
+    )
+}
+
+fn spawn_mobs(game: &mut Game, spawner: &mut MobSpawner) -> SysResult {
+    let difficulty = *game.resources.get::<Difficulty>()?;
+    if difficulty == Difficulty::Peaceful {
+        remove_monsters(game);
+    }
+    if !game.resources.get::<GameRules>()?.do_mob_spawning {
+        return Ok(());
+    }
+
+    let mut counts = [0; MobCategory::ALL.len()];
+    for (_, &kind) in game.ecs.query::<&EntityKind>().iter() {
+        if let Some(category) = MobCategory::of(kind) {
+            counts[category as usize] += 1;
+        }
+    }
+
+    let players: Vec<Position> = game
+        .ecs
+        .query::<(&Player, &Position)>()
+        .iter()
+        .map(|(_, (_, &position))| position)
+        .collect();
+
+    let mut chunks: Vec<ChunkPosition> = game
+        .world
+        .chunk_map()
+        .iter_chunks()
+        .into_iter()
+        .map(|chunk| chunk.read().position())
+        .collect();
+    if game.deterministic_ticking {
+        chunks.sort_unstable_by_key(|chunk| (chunk.x, chunk.z));
+    }
+
+    for category in MobCategory::ALL.iter().copied() {
+        if !spawner.is_enabled(category)
+            || (category == MobCategory::Creature && game.tick_count % CREATURE_SPAWN_INTERVAL != 0)
+        {
+            continue;
+        }
+
+        let cap = spawner.cap(category, difficulty);
+        for &chunk_pos in &chunks {
+            if counts[category as usize] >= cap {
+                break;
+            }
+
+            let position = match pick_spawn_position(game, spawner, chunk_pos, category) {
+                Some(position) => position,
+                None => continue,
+            };
+            let too_close = players.iter().any(|player| {
+                player.distance_squared_to(position) < MIN_PLAYER_DISTANCE * MIN_PLAYER_DISTANCE
+            });
+            if too_close {
+                continue;
+            }
+
+            let builder = game.create_entity_builder(position, category.entity_init());
+            game.spawn_entity(builder);
+            counts[category as usize] += 1;
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes all monsters, which can't exist on peaceful.
+fn remove_monsters(game: &mut Game) {
+    let monsters: Vec<Entity> = game
+        .ecs
+        .query::<&EntityKind>()
+        .iter()
+        .filter(|(_, &kind)| MobCategory::of(kind) == Some(MobCategory::Monster))
+        .map(|(entity, _)| entity)
+        .collect();
+    for monster in monsters {
+        let _ = game.remove_entity(monster);
+    }
+}
+
+/// Picks a random position in a chunk and returns it
+/// if a mob of `category` can spawn there.
+fn pick_spawn_position(
+    game: &Game,
+    spawner: &mut MobSpawner,
+    chunk_pos: ChunkPosition,
+    category: MobCategory,
+) -> Option<Position> {
+    let chunk = game.world.chunk_map().chunk_at(chunk_pos)?;
+
+    let x = spawner.rng.gen_range(0..CHUNK_WIDTH);
+    let z = spawner.rng.gen_range(0..CHUNK_WIDTH);
+    let top = chunk.heightmaps().motion_blocking.height(x, z)?;
+    let y = spawner.rng.gen_range(1..=top + 1).min(CHUNK_HEIGHT - 2);
+
+    if !category.can_spawn_at(&chunk, x, y, z) {
+        return None;
+    }
+
+    let block = BlockPosition::new(
+        chunk_pos.x * CHUNK_WIDTH as i32 + x as i32,
+        y as i32,
+        chunk_pos.z * CHUNK_WIDTH as i32 + z as i32,
+    );
+    Some(block.position() + vec3(0.5, 0., 0.5))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a game with a stone floor at y = 16 in chunk (0, 0),
+    /// covered by a stone roof at y = 19 if `roofed`.
+    fn game_with_floor(roofed: bool) -> Game {
+        let mut game = Game::new();
+        game.add_entity_spawn_callback(crate::entities::add_entity_components);
+        game.deterministic_ticking = true;
+        game.world
+            .chunk_map_mut()
+            .insert_chunk(Chunk::new(ChunkPosition::new(0, 0)));
+
+        for x in 0..16 {
+            for z in 0..16 {
+                game.set_block(BlockPosition::new(x, 16, z), BlockId::stone());
+                if roofed {
+                    game.set_block(BlockPosition::new(x, 19, z), BlockId::stone());
+                }
+            }
+        }
+        game
+    }
+
+    fn run(game: &mut Game, settings: SpawnSettings, ticks: u64) {
+        let mut systems = SystemExecutor::new();
+        register(game, &mut systems);
+        game.insert_resource(MobSpawner::with_seed(settings, 0));
+        for _ in 0..ticks {
+            systems.run(game);
+            game.tick_count += 1;
+        }
+    }
+
+    fn count(game: &Game, kind: EntityKind) -> usize {
+        game.ecs
+            .query::<&EntityKind>()
+            .iter()
+            .filter(|(_, k)| **k == kind)
+            .count()
+    }
+
+    #[test]
+    fn no_monsters_under_open_sky() {
+        let mut game = game_with_floor(false);
+        run(&mut game, SpawnSettings::default(), 1000);
+        assert_eq!(count(&game, EntityKind::Zombie), 0);
+    }
+
+    #[test]
+    fn stored_light_is_ignored() {
+        // Generated chunks store full light everywhere
+        let game = game_with_floor(true);
+        let chunk = game
+            .world
+            .chunk_map()
+            .chunk_at(ChunkPosition::new(0, 0))
+            .unwrap();
+        assert_eq!(chunk.sky_light_at(0, 17, 0), Some(15));
+        assert_eq!(estimated_light(&chunk, 0, 17, 0), 0);
+        assert_eq!(estimated_light(&chunk, 0, 20, 0), DAYLIGHT);
+    }
+
+    #[test]
+    fn mob_cap_limits_spawns() {
+        let mut game = game_with_floor(true);
+        game.insert_resource(Difficulty::Normal);
+        let settings = SpawnSettings {
+            monster_cap: 3,
+            ..Default::default()
+        };
+        run(&mut game, settings, 1000);
+        assert_eq!(count(&game, EntityKind::Zombie), 3);
+
+        for (_, (&kind, &position)) in game.ecs.query::<(&EntityKind, &Position)>().iter() {
+            assert_eq!(kind, EntityKind::Zombie);
+            assert_eq!(position.y, 17.);
+        }
+    }
+
+    #[test]
+    fn spawn_monsters_flag_disables_monsters() {
+        let mut game = game_with_floor(true);
+        let settings = SpawnSettings {
+            spawn_monsters: false,
+            ..Default::default()
+        };
+        run(&mut game, settings, 1000);
+        assert_eq!(count(&game, EntityKind::Zombie), 0);
+    }
+
+    #[test]
+    fn difficulty_scales_monster_cap() {
+        let settings = SpawnSettings {
+            monster_cap: 4,
+            ..Default::default()
+        };
+
+        let mut game = game_with_floor(true);
+        game.insert_resource(Difficulty::Hard);
+        run(&mut game, settings.clone(), 1000);
+        assert_eq!(count(&game, EntityKind::Zombie), 4);
+
+        let mut game = game_with_floor(true);
+        game.insert_resource(Difficulty::Easy);
+        run(&mut game, settings, 1000);
+        assert_eq!(count(&game, EntityKind::Zombie), 2);
+    }
+
+    #[test]
+    fn peaceful_removes_monsters() {
+        let mut game = game_with_floor(true);
+        let cow = game.ecs.spawn((EntityKind::Cow,));
+        run(&mut game, SpawnSettings::default(), 100);
+        assert!(count(&game, EntityKind::Zombie) > 0);
+
+        game.insert_resource(Difficulty::Peaceful);
+        run(&mut game, SpawnSettings::default(), 1000);
+        assert_eq!(count(&game, EntityKind::Zombie), 0);
+        assert!(game.ecs.get::<EntityKind>(cow).is_ok());
+    }
+
+    #[test]
+    fn do_mob_spawning_rule_disables_spawning() {
+        let mut game = game_with_floor(true);
+        game.resources
+            .get_mut::<GameRules>()
+            .unwrap()
+            .do_mob_spawning = false;
+        run(&mut game, SpawnSettings::default(), 1000);
+        assert_eq!(count(&game, EntityKind::Zombie), 0);
+    }
+}
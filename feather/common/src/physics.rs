@@ -0,0 +1,317 @@
+//! Basic movement physics.
+//!
+//! Blocks are treated as full cubes and entities as
+//! their [`EntityKind::bounding_box`].
+//!
+//! Living entities overlapping each other are pushed apart, and
+//! take damage when more than the `maxEntityCramming` game rule
+//! are crammed together.
+
+use ahash::AHashMap;
+use base::{BlockPosition, ChunkPosition, EntityKind, GameRules, Gamemode, Position, Vec3d};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::entities::Player;
+
+use crate::{
+    damage::{self, DamageSource, Health},
+    Game,
+};
+
+/// Distance moved per step when sweeping through the world.
+/// Small enough that thin obstacles are not skipped.
+const SWEEP_STEP: f64 = 0.1;
+
+/// Strength of the push between two overlapping entities.
+const PUSH_STRENGTH: f64 = 0.05;
+
+/// Damage taken by crammed entities.
+const CRAMMING_DAMAGE: f32 = 6.;
+
+/// Crammed entities take damage once per this many ticks.
+const CRAMMING_INTERVAL: u64 = 4;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(push_entities);
+}
+
+/// The velocity of an entity, in blocks per tick.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Velocity(pub Vec3d);
+
+/// What a [`sweep`] collided with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SweepHit {
+    Block(BlockPosition),
+    Entity(Entity),
+}
+
+/// The result of a [`sweep`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sweep {
+    /// The position reached. If the sweep hit a block, this
+    /// is the last position before entering the block.
+    pub position: Position,
+    pub hit: Option<SweepHit>,
+}
+
+/// Moves a point from `from` by `motion`, stopping at the first
+/// solid block or entity in the way.
+///
+/// Only entities for which `can_hit` returns `true` are considered.
+/// Blocks in unloaded chunks are treated as air.
+pub fn sweep(
+    game: &Game,
+    from: Position,
+    motion: Vec3d,
+    mut can_hit: impl FnMut(Entity) -> bool,
+) -> Sweep {
+    let center = from.chunk();
+    let mut targets = Vec::new();
+    for dx in -1..=1 {
+        for dz in -1..=1 {
+            let chunk = ChunkPosition::new(center.x + dx, center.z + dz);
+            for &entity in game.chunk_entities.entities_in_chunk(chunk) {
+                if !can_hit(entity) {
+                    continue;
+                }
+                if let (Ok(position), Ok(kind)) = (
+                    game.ecs.get::<Position>(entity),
+                    game.ecs.get::<EntityKind>(entity),
+                ) {
+                    targets.push((entity, *position, kind.bounding_box().max));
+                }
+            }
+        }
+    }
+
+    let steps = (motion.magnitude() / SWEEP_STEP).ceil().max(1.);
+    let step = motion / steps;
+    let mut position = from;
+    for _ in 0..steps as usize {
+        let next = position + step;
+
+        let block = next.block();
+        if game.block(block).map_or(false, |block| block.is_solid()) {
+            return Sweep {
+                position,
+                hit: Some(SweepHit::Block(block)),
+            };
+        }
+
+        let hit_entity = targets.iter().find(|(_, target, size)| {
+            (next.x - target.x).abs() <= size.x / 2.
+                && (next.z - target.z).abs() <= size.z / 2.
+                && next.y >= target.y
+                && next.y <= target.y + size.y
+        });
+        if let Some((entity, _, _)) = hit_entity {
+            return Sweep {
+                position: next,
+                hit: Some(SweepHit::Entity(*entity)),
+            };
+        }
+
+        position = next;
+    }
+
+    Sweep {
+        position,
+        hit: None,
+    }
+}
+
+/// A living entity which can push and be pushed by others.
+struct Pushable {
+    position: Position,
+    size: Vec3d,
+    /// Players move themselves, applying pushes on the client.
+    is_player: bool,
+    can_take_damage: bool,
+    push: Vec3d,
+    /// The number of other entities overlapping this one.
+    overlapping: usize,
+}
+
+impl Pushable {
+    fn overlaps(&self, other: &Pushable) -> bool {
+        (self.position.x - other.position.x).abs() < (self.size.x + other.size.x) / 2.
+            && (self.position.z - other.position.z).abs() < (self.size.z + other.size.z) / 2.
+            && self.position.y < other.position.y + other.size.y
+            && other.position.y < self.position.y + self.size.y
+    }
+}
+
+/// Pushes apart overlapping living entities and damages
+/// those crammed together. Spectators are ignored.
+fn push_entities(game: &mut Game) -> SysResult {
+    let mut entities: Vec<(Entity, Pushable)> = game
+        .ecs
+        .query::<(
+            &Position,
+            &EntityKind,
+            &Health,
+            Option<&Gamemode>,
+            Option<&Player>,
+        )>()
+        .iter()
+        .filter(|(_, (_, _, _, gamemode, _))| gamemode != &Some(&Gamemode::Spectator))
+        .map(|(entity, (&position, kind, _, gamemode, player))| {
+            let pushable = Pushable {
+                position,
+                size: kind.bounding_box().max,
+                is_player: player.is_some(),
+                can_take_damage: gamemode != Some(&Gamemode::Creative),
+                push: Vec3d::zero(),
+                overlapping: 0,
+            };
+            (entity, pushable)
+        })
+        .collect();
+    game.sort_for_tick(&mut entities);
+
+    let mut chunks: AHashMap<ChunkPosition, Vec<usize>> = AHashMap::new();
+    for (i, (_, pushable)) in entities.iter().enumerate() {
+        chunks.entry(pushable.position.chunk()).or_default().push(i);
+    }
+
+    let mut pairs = Vec::new();
+    for (i, (_, a)) in entities.iter().enumerate() {
+        let center = a.position.chunk();
+        for dx in -1..=1 {
+            for dz in -1..=1 {
+                let chunk = ChunkPosition::new(center.x + dx, center.z + dz);
+                for &j in chunks.get(&chunk).map(Vec::as_slice).unwrap_or_default() {
+                    let b = &entities[j].1;
+                    if j > i && a.overlaps(b) {
+                        pairs.push((i, j, push_between(a, b)));
+                    }
+                }
+            }
+        }
+    }
+    for (i, j, push) in pairs {
+        entities[i].1.push -= push;
+        entities[i].1.overlapping += 1;
+        entities[j].1.push += push;
+        entities[j].1.overlapping += 1;
+    }
+
+    for (entity, pushable) in &entities {
+        if pushable.is_player || pushable.push == Vec3d::zero() {
+            continue;
+        }
+        let position = pushable.position + pushable.push;
+        if game
+            .block(position.block())
+            .map_or(false, |block| block.is_solid())
+        {
+            continue;
+        }
+        *game.ecs.get_mut::<Position>(*entity)? = position;
+    }
+
+    let max_cramming = game.resources.get::<GameRules>()?.max_entity_cramming as usize;
+    if max_cramming > 0 && game.tick_count % CRAMMING_INTERVAL == 0 {
+        for (entity, pushable) in &entities {
+            if pushable.overlapping >= max_cramming && pushable.can_take_damage {
+                damage::damage(game, *entity, CRAMMING_DAMAGE, DamageSource::Cramming)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Gets the horizontal push applied to `b`
+/// by `a`. `a` is pushed by the opposite.
+fn push_between(a: &Pushable, b: &Pushable) -> Vec3d {
+    let mut dx = b.position.x - a.position.x;
+    let mut dz = b.position.z - a.position.z;
+    let distance = dx.abs().max(dz.abs());
+    if distance < 0.01 {
+        return Vec3d::zero();
+    }
+    let distance = distance.sqrt();
+    let strength = (1. / distance).min(1.) * PUSH_STRENGTH / distance;
+    dx *= strength;
+    dz *= strength;
+    Vec3d::new(dx, 0., dz)
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+
+    use super::*;
+
+    fn spawn_zombie(game: &mut Game, x: f64) -> Entity {
+        game.ecs
+            .spawn((position!(x, 64.0, 0.0), EntityKind::Zombie, Health(20.)))
+    }
+
+    #[test]
+    fn overlapping_entities_push_apart() {
+        let mut game = Game::new();
+        let left = spawn_zombie(&mut game, 0.);
+        let right = spawn_zombie(&mut game, 0.2);
+        let far = spawn_zombie(&mut game, 5.);
+
+        push_entities(&mut game).unwrap();
+        let x = |entity| game.ecs.get::<Position>(entity).unwrap().x;
+        assert!(x(left) < 0.);
+        assert!(x(right) > 0.2);
+        assert!((x(left) + x(right) - 0.2).abs() < 1e-9);
+        assert_eq!(x(far), 5.);
+        assert_eq!(game.ecs.get::<Position>(left).unwrap().z, 0.);
+    }
+
+    #[test]
+    fn players_and_spectators_are_not_pushed() {
+        let mut game = Game::new();
+        let zombie = spawn_zombie(&mut game, 0.);
+        let player = game.ecs.spawn((
+            position!(0.2, 64.0, 0.0),
+            EntityKind::Player,
+            Health(20.),
+            Gamemode::Survival,
+            Player,
+        ));
+        let spectator = game.ecs.spawn((
+            position!(-0.2, 64.0, 0.0),
+            EntityKind::Player,
+            Health(20.),
+            Gamemode::Spectator,
+            Player,
+        ));
+
+        push_entities(&mut game).unwrap();
+        // Only the player pushes the zombie
+        assert!(game.ecs.get::<Position>(zombie).unwrap().x < 0.);
+        assert_eq!(game.ecs.get::<Position>(player).unwrap().x, 0.2);
+        assert_eq!(game.ecs.get::<Position>(spectator).unwrap().x, -0.2);
+    }
+
+    #[test]
+    fn cramming_deals_damage() {
+        let mut game = Game::new();
+        game.resources
+            .get_mut::<GameRules>()
+            .unwrap()
+            .max_entity_cramming = 3;
+        let zombies: Vec<Entity> = (0..3)
+            .map(|i| spawn_zombie(&mut game, i as f64 * 0.1))
+            .collect();
+
+        push_entities(&mut game).unwrap();
+        for &zombie in &zombies {
+            assert_eq!(*game.ecs.get::<Health>(zombie).unwrap(), Health(20.));
+        }
+
+        let crammed = spawn_zombie(&mut game, 0.05);
+        game.tick_count = CRAMMING_INTERVAL;
+        push_entities(&mut game).unwrap();
+        for &zombie in zombies.iter().chain(&[crammed]) {
+            assert_eq!(*game.ecs.get::<Health>(zombie).unwrap(), Health(14.));
+        }
+    }
+}
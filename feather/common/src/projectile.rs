@@ -0,0 +1,206 @@
+//! Projectiles like arrows and snowballs.
+//!
+//! A projectile flies along its [`Velocity`] until it hits
+//! a block or an entity. It then triggers a [`ProjectileHitEvent`]
+//! and removes itself.
+
+use base::{Position, Vec3d};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::entity_init::EntityInit;
+
+use crate::{
+    damage::{self, DamageSource},
+    events::{EntityRemoveEvent, ProjectileHitEvent},
+    physics::{self, SweepHit, Velocity},
+    Game,
+};
+
+/// Fraction of its velocity a projectile keeps each tick.
+const DRAG: f64 = 0.99;
+
+/// Projectiles falling below this height are removed.
+const MIN_Y: f64 = -64.;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(update_projectiles);
+}
+
+/// Component for entities that fly until they hit something.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Projectile {
+    /// The entity that launched this projectile.
+    pub shooter: Option<Entity>,
+    /// Damage dealt to an entity on impact.
+    pub damage: f32,
+    /// Downward acceleration in blocks per tick squared.
+    pub gravity: f64,
+}
+
+/// Gets the gravity of projectiles of type `init`.
+///
+/// Arrows and tridents fall faster than other projectiles.
+pub fn gravity(init: &EntityInit) -> f64 {
+    match init {
+        EntityInit::Arrow | EntityInit::SpectralArrow | EntityInit::Trident => 0.05,
+        _ => 0.03,
+    }
+}
+
+/// Spawns a projectile of type `init` moving with `velocity`.
+pub fn launch(
+    game: &mut Game,
+    init: EntityInit,
+    position: Position,
+    velocity: Vec3d,
+    shooter: Option<Entity>,
+    damage: f32,
+) -> Entity {
+    let gravity = gravity(&init);
+    let mut builder = game.create_entity_builder(position, init);
+    builder.add(Velocity(velocity)).add(Projectile {
+        shooter,
+        damage,
+        gravity,
+    });
+    game.spawn_entity(builder)
+}
+
+fn update_projectiles(game: &mut Game) -> SysResult {
+    let mut projectiles: Vec<(Entity, (Position, Velocity, Projectile))> = game
+        .ecs
+        .query::<(
+            &Position,
+            &Velocity,
+            &Projectile,
+            Option<&EntityRemoveEvent>,
+        )>()
+        .iter()
+        .filter(|(_, (_, _, _, removed))| removed.is_none())
+        .map(|(entity, (&position, &velocity, &projectile, _))| {
+            (entity, (position, velocity, projectile))
+        })
+        .collect();
+    game.sort_for_tick(&mut projectiles);
+
+    for (entity, (position, velocity, projectile)) in projectiles {
+        // Don't simulate projectiles in unloaded chunks
+        // or ones that fell out of the world.
+        if game.world.chunk_map().chunk_at(position.chunk()).is_none() || position.y < MIN_Y {
+            game.remove_entity(entity)?;
+            continue;
+        }
+
+        let sweep = physics::sweep(game, position, velocity.0, |target| {
+            target != entity
+                && Some(target) != projectile.shooter
+                && game.ecs.get::<Projectile>(target).is_err()
+        });
+        *game.ecs.get_mut::<Position>(entity)? = sweep.position;
+
+        let hit = match sweep.hit {
+            Some(hit) => hit,
+            None => {
+                let mut velocity = game.ecs.get_mut::<Velocity>(entity)?;
+                velocity.0 *= DRAG;
+                velocity.0.y -= projectile.gravity;
+                continue;
+            }
+        };
+
+        game.ecs
+            .insert_entity_event(entity, ProjectileHitEvent { hit })?;
+        if let SweepHit::Entity(target) = hit {
+            let source = DamageSource::Entity(projectile.shooter.unwrap_or(entity));
+            damage::damage(game, target, projectile.damage, source)?;
+        }
+        game.remove_entity(entity)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{position, BlockId, BlockPosition, Chunk, ChunkPosition, EntityKind};
+
+    use super::*;
+    use crate::{damage::Health, events::EntityDamageEvent};
+
+    fn game_and_systems() -> (Game, SystemExecutor<Game>) {
+        let mut game = Game::new();
+        game.add_entity_spawn_callback(crate::entities::add_entity_components);
+        game.world
+            .chunk_map_mut()
+            .insert_chunk(Chunk::new(ChunkPosition::new(0, 0)));
+
+        let mut systems = SystemExecutor::new();
+        crate::chunk::entities::register(&mut systems);
+        register(&mut systems);
+        (game, systems)
+    }
+
+    /// Runs ticks until the projectile hits something.
+    fn run_until_hit(
+        game: &mut Game,
+        systems: &mut SystemExecutor<Game>,
+        projectile: Entity,
+    ) -> SweepHit {
+        for _ in 0..20 {
+            systems.run(game);
+            if let Ok(event) = game.ecs.get::<ProjectileHitEvent>(projectile) {
+                assert!(game.ecs.get::<EntityRemoveEvent>(projectile).is_ok());
+                return event.hit;
+            }
+        }
+        panic!("projectile never hit anything");
+    }
+
+    #[test]
+    fn projectile_stops_at_wall() {
+        let (mut game, mut systems) = game_and_systems();
+        for y in 60..70 {
+            for z in 0..16 {
+                game.set_block(BlockPosition::new(8, y, z), BlockId::stone());
+            }
+        }
+
+        let arrow = launch(
+            &mut game,
+            EntityInit::Arrow,
+            position!(0.5, 64.5, 0.5),
+            Vec3d::new(3., 0., 0.),
+            None,
+            2.,
+        );
+        let hit = run_until_hit(&mut game, &mut systems, arrow);
+
+        assert_eq!(hit, SweepHit::Block(BlockPosition::new(8, 64, 0)));
+        let position = *game.ecs.get::<Position>(arrow).unwrap();
+        assert!(position.x < 8. && position.x > 7.8, "{:?}", position);
+    }
+
+    #[test]
+    fn projectile_damages_entity() {
+        let (mut game, mut systems) = game_and_systems();
+        let mut builder = game.create_entity_builder(position!(6.5, 64., 0.5), EntityInit::Zombie);
+        builder.add(Health(20.));
+        let zombie = game.spawn_entity(builder);
+        let shooter = game.ecs.spawn((EntityKind::Skeleton,));
+
+        let arrow = launch(
+            &mut game,
+            EntityInit::Arrow,
+            position!(0.5, 65., 0.5),
+            Vec3d::new(1.5, 0., 0.),
+            Some(shooter),
+            6.,
+        );
+        let hit = run_until_hit(&mut game, &mut systems, arrow);
+
+        assert_eq!(hit, SweepHit::Entity(zombie));
+        assert_eq!(*game.ecs.get::<Health>(zombie).unwrap(), Health(14.));
+        let event = game.ecs.get::<EntityDamageEvent>(zombie).unwrap();
+        assert_eq!(event.amount, 6.);
+        assert_eq!(event.source, DamageSource::Entity(shooter));
+    }
+}
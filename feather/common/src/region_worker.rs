@@ -1,50 +1,25 @@
-use std::{
-    collections::hash_map::Entry,
-    path::PathBuf,
-    time::{Duration, Instant},
-};
+use std::{sync::Arc, time::Duration};
 
-use ahash::AHashMap;
-use base::anvil::{
-    self,
-    region::{RegionHandle, RegionPosition},
-};
 use flume::{Receiver, Sender};
+use parking_lot::Mutex;
 
-use crate::chunk::worker::{ChunkLoadResult, LoadRequest, LoadedChunk, SaveRequest, WorkerRequest};
-
-/// Duration to keep a region file open when not in use.
-const CACHE_TIME: Duration = Duration::from_secs(60);
-
-struct OpenRegionFile {
-    handle: RegionHandle,
-    last_used: Instant,
-}
-
-impl OpenRegionFile {
-    pub fn new(handle: RegionHandle) -> Self {
-        Self {
-            handle,
-            last_used: Instant::now(),
-        }
-    }
+use crate::{
+    chunk::worker::{ChunkLoadResult, LoadRequest, SaveRequest, WorkerRequest},
+    world_storage::WorldStorage,
+};
 
-    pub fn should_close(&self) -> bool {
-        self.last_used.elapsed() >= CACHE_TIME
-    }
-}
+/// Maximum time between two calls to [`WorldStorage::update`].
+const UPDATE_INTERVAL: Duration = Duration::from_secs(60);
 
 pub struct RegionWorker {
     request_receiver: Receiver<WorkerRequest>,
     result_sender: Sender<ChunkLoadResult>,
-    world_dir: PathBuf,
-    region_files: AHashMap<RegionPosition, OpenRegionFile>,
-    last_cache_update: Instant,
+    storage: Arc<Mutex<dyn WorldStorage>>,
 }
 
 impl RegionWorker {
     pub fn new(
-        world_dir: PathBuf,
+        storage: Arc<Mutex<dyn WorldStorage>>,
         request_receiver: Receiver<WorkerRequest>,
     ) -> (Self, Receiver<ChunkLoadResult>) {
         let (result_sender, result_receiver) = flume::bounded(256);
@@ -52,9 +27,7 @@ impl RegionWorker {
             Self {
                 request_receiver,
                 result_sender,
-                world_dir,
-                region_files: AHashMap::new(),
-                last_cache_update: Instant::now(),
+                storage,
             },
             result_receiver,
         )
@@ -67,10 +40,10 @@ impl RegionWorker {
             .expect("failed to create chunk worker thread");
     }
 
-    fn run(mut self) {
+    fn run(self) {
         log::info!("Chunk worker started");
         loop {
-            match self.request_receiver.recv_timeout(CACHE_TIME) {
+            match self.request_receiver.recv_timeout(UPDATE_INTERVAL) {
                 Ok(req) => match req {
                     WorkerRequest::Load(load) => self.load_chunk(load),
                     WorkerRequest::Save(save) => self.save_chunk(save).unwrap(),
@@ -81,85 +54,20 @@ impl RegionWorker {
                     return;
                 }
             }
-            self.update_cache();
+            self.storage.lock().update();
         }
     }
 
-    fn save_chunk(&mut self, req: SaveRequest) -> anyhow::Result<()> {
-        let reg_pos = RegionPosition::from_chunk(req.pos);
-        let handle = &mut match self.region_file_handle(reg_pos) {
-            Some(h) => h,
-            None => {
-                let new_handle = anvil::region::create_region(&self.world_dir, reg_pos)?;
-                self.region_files
-                    .insert(reg_pos, OpenRegionFile::new(new_handle));
-                self.region_file_handle(reg_pos).unwrap()
-            }
-        }
-        .handle;
-        handle.save_chunk(
+    fn save_chunk(&self, req: SaveRequest) -> anyhow::Result<()> {
+        self.storage.lock().save_chunk(
             &req.chunk.read(),
             &req.entities[..],
             &req.block_entities[..],
-        )?;
-        Ok(())
+        )
     }
 
-    fn load_chunk(&mut self, req: LoadRequest) {
-        let result = self.get_chunk_load_result(req);
+    fn load_chunk(&self, req: LoadRequest) {
+        let result = self.storage.lock().load_chunk(req.pos);
         let _ = self.result_sender.send(result);
     }
-
-    fn get_chunk_load_result(&mut self, req: LoadRequest) -> ChunkLoadResult {
-        let pos = req.pos;
-        let region = RegionPosition::from_chunk(pos);
-        let file = match self.region_file_handle(region) {
-            Some(file) => file,
-            None => return ChunkLoadResult::Missing(pos),
-        };
-
-        let chunk = match file.handle.load_chunk(pos) {
-            Ok((chunk, _, _)) => chunk,
-            Err(e) => match e {
-                anvil::region::Error::ChunkNotExist => return ChunkLoadResult::Missing(pos),
-                err => return ChunkLoadResult::Error(err.into()),
-            },
-        };
-
-        file.last_used = Instant::now();
-
-        ChunkLoadResult::Loaded(LoadedChunk { pos, chunk })
-    }
-
-    fn region_file_handle(&mut self, region: RegionPosition) -> Option<&mut OpenRegionFile> {
-        match self.region_files.entry(region) {
-            Entry::Occupied(e) => Some(e.into_mut()),
-            Entry::Vacant(e) => {
-                let handle = base::anvil::region::load_region(&self.world_dir, region);
-                if let Ok(handle) = handle {
-                    Some(e.insert(OpenRegionFile::new(handle)))
-                } else {
-                    None
-                }
-            }
-        }
-    }
-
-    fn update_cache(&mut self) {
-        if self.last_cache_update.elapsed() >= CACHE_TIME {
-            let initial_len = self.region_files.len();
-
-            self.region_files.retain(|_, file| !file.should_close());
-            self.last_cache_update = Instant::now();
-
-            let num_closed = initial_len - self.region_files.len();
-            if num_closed != 0 {
-                log::debug!(
-                    "Closed {} region files ({} still open)",
-                    num_closed,
-                    self.region_files.len()
-                );
-            }
-        }
-    }
 }
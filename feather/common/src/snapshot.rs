@@ -0,0 +1,123 @@
+//! Compact dumps of the game state, used for
+//! debugging and integration tests.
+
+use base::{EntityKind, Position};
+use quill_common::{components::Name, entities::Player};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::Game;
+
+/// A serializable view of a [`Game`] at one point in time.
+///
+/// Create one with [`Game::snapshot`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GameSnapshot {
+    pub tick_count: u64,
+    pub loaded_chunks: usize,
+    /// All entities with a position, sorted by ID.
+    pub entities: Vec<EntitySnapshot>,
+    /// Online players, sorted by name.
+    pub players: Vec<PlayerSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EntitySnapshot {
+    /// The entity's ID, as returned by `Entity::to_bits`.
+    pub id: u64,
+    /// The entity type, or `None` for entities
+    /// without an `EntityKind`.
+    pub kind: Option<String>,
+    pub position: [f64; 3],
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlayerSnapshot {
+    pub name: String,
+    pub uuid: Option<Uuid>,
+    pub position: [f64; 3],
+}
+
+impl GameSnapshot {
+    pub(crate) fn new(game: &Game) -> Self {
+        let mut entities: Vec<EntitySnapshot> = game
+            .ecs
+            .query::<(&Position, Option<&EntityKind>)>()
+            .iter()
+            .map(|(entity, (position, kind))| EntitySnapshot {
+                id: entity.to_bits(),
+                kind: kind.map(|kind| kind.name().to_owned()),
+                position: [position.x, position.y, position.z],
+            })
+            .collect();
+        entities.sort_by_key(|entity| entity.id);
+
+        let mut players: Vec<PlayerSnapshot> = game
+            .ecs
+            .query::<(&Player, &Name, &Position, Option<&Uuid>)>()
+            .iter()
+            .map(|(_, (_, name, position, uuid))| PlayerSnapshot {
+                name: name.to_string(),
+                uuid: uuid.copied(),
+                position: [position.x, position.y, position.z],
+            })
+            .collect();
+        players.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self {
+            tick_count: game.tick_count,
+            loaded_chunks: game.world.chunk_map().len(),
+            entities,
+            players,
+        }
+    }
+
+    /// Serializes this snapshot to pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("snapshot is always serializable")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+    use quill_common::entity_init::EntityInit;
+
+    use super::*;
+
+    #[test]
+    fn snapshot_reflects_entities_and_tick_count() {
+        let mut game = Game::new();
+        game.add_entity_spawn_callback(crate::entities::add_entity_components);
+        game.tick_count = 42;
+
+        let builder = game.create_entity_builder(position!(1.0, 64.0, 2.0), EntityInit::Cow);
+        let cow = game.spawn_entity(builder);
+        let builder = game.create_entity_builder(position!(-5.0, 70.0, 8.0), EntityInit::Zombie);
+        game.spawn_entity(builder);
+        let mut builder = game.create_entity_builder(position!(0.0, 65.0, 0.0), EntityInit::Player);
+        builder.add(Name::new("Steve"));
+        game.spawn_entity(builder);
+
+        let snapshot = game.snapshot();
+        assert_eq!(snapshot.tick_count, 42);
+        assert_eq!(snapshot.loaded_chunks, 0);
+        assert_eq!(snapshot.entities.len(), 3);
+        assert_eq!(
+            snapshot.entities[0],
+            EntitySnapshot {
+                id: cow.to_bits(),
+                kind: Some("cow".to_owned()),
+                position: [1.0, 64.0, 2.0],
+            }
+        );
+        assert_eq!(snapshot.entities[1].kind.as_deref(), Some("zombie"));
+        assert_eq!(snapshot.players.len(), 1);
+        assert_eq!(snapshot.players[0].name, "Steve");
+        assert_eq!(snapshot.players[0].position, [0.0, 65.0, 0.0]);
+
+        let json: serde_json::Value = serde_json::from_str(&snapshot.to_json()).unwrap();
+        assert_eq!(json["tick_count"], 42);
+        assert_eq!(json["entities"][0]["kind"], "cow");
+    }
+}
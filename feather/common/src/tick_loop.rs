@@ -1,4 +1,7 @@
-use std::time::Instant;
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
 
 use base::TICK_DURATION;
 
@@ -36,3 +39,67 @@ impl TickLoop {
         }
     }
 }
+
+/// Number of recent ticks averaged by [`TickMetrics`].
+const TICK_WINDOW: usize = 100;
+
+/// Resource recording how long recent ticks took.
+#[derive(Debug, Default)]
+pub struct TickMetrics {
+    tick_times: VecDeque<Duration>,
+}
+
+impl TickMetrics {
+    /// Records the time spent running a tick.
+    pub fn record(&mut self, tick_time: Duration) {
+        if self.tick_times.len() == TICK_WINDOW {
+            self.tick_times.pop_front();
+        }
+        self.tick_times.push_back(tick_time);
+    }
+
+    /// Gets the mean time spent running recent ticks.
+    pub fn mean_tick_time(&self) -> Duration {
+        if self.tick_times.is_empty() {
+            return Duration::default();
+        }
+        self.tick_times.iter().sum::<Duration>() / self.tick_times.len() as u32
+    }
+
+    /// Gets the number of ticks per second, based on recent ticks.
+    /// At most 20, since the tick loop waits for the rest
+    /// of the tick after running fast ticks.
+    pub fn tps(&self) -> f64 {
+        if self.tick_times.is_empty() {
+            return 1. / TICK_DURATION.as_secs_f64();
+        }
+        let elapsed: Duration = self
+            .tick_times
+            .iter()
+            .map(|&tick_time| tick_time.max(TICK_DURATION))
+            .sum();
+        self.tick_times.len() as f64 / elapsed.as_secs_f64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tick_metrics() {
+        let mut metrics = TickMetrics::default();
+        assert_eq!(metrics.tps(), 20.);
+
+        metrics.record(Duration::from_millis(10));
+        metrics.record(Duration::from_millis(30));
+        assert_eq!(metrics.mean_tick_time(), Duration::from_millis(20));
+        assert_eq!(metrics.tps(), 20.);
+
+        for _ in 0..TICK_WINDOW {
+            metrics.record(Duration::from_millis(100));
+        }
+        assert_eq!(metrics.mean_tick_time(), Duration::from_millis(100));
+        assert_eq!(metrics.tps(), 10.);
+    }
+}
@@ -1,7 +1,7 @@
-use std::mem;
+use std::{mem, ops::Range};
 
 use anyhow::{anyhow, bail};
-use base::{Area, Item, ItemStack};
+use base::{Area, Inventory, Item, ItemStack};
 
 use ecs::SysResult;
 pub use generated::Window as BackingWindow;
@@ -21,6 +21,9 @@ pub struct Window {
     cursor_item: Option<ItemStack>,
     /// Current painting state (mouse drag)
     paint_state: Option<PaintState>,
+    /// The action number of the last click, or `None`
+    /// if the window hasn't been clicked yet.
+    last_action_number: Option<i16>,
 }
 
 impl Window {
@@ -30,9 +33,30 @@ impl Window {
             inner,
             cursor_item: None,
             paint_state: None,
+            last_action_number: None,
         }
     }
 
+    /// Checks the action number of a click against the previous one.
+    ///
+    /// The client numbers its clicks sequentially. Numbers may be
+    /// skipped, e.g. after the window was reopened, so any number
+    /// newer than the last one is accepted. Older numbers indicate
+    /// a replayed click, which must be rejected. The window should be
+    /// resent to the client whenever a number isn't the expected one.
+    /// The first click in a window may use any number.
+    pub fn check_action_number(&mut self, action_number: i16) -> bool {
+        let is_newer = match self.last_action_number {
+            // Action numbers wrap around
+            Some(last) => action_number.wrapping_sub(last) > 0,
+            None => true,
+        };
+        if is_newer {
+            self.last_action_number = Some(action_number);
+        }
+        is_newer
+    }
+
     /// Left-click a slot in the window.
     pub fn left_click(&mut self, slot: usize) -> SysResult {
         let mut slot_item = self.inner.item(slot)?;
@@ -88,6 +112,9 @@ impl Window {
     }
 
     /// Shift-clicks the given slot. (Either right or left click.)
+    ///
+    /// The item is moved to the regions returned by
+    /// [`Window::shift_click_targets`], in order.
     pub fn shift_click(&mut self, slot: usize) -> SysResult {
         let mut slot_item_guard = self.inner.item(slot)?;
         let slot_item = match slot_item_guard.as_mut() {
@@ -95,54 +122,128 @@ impl Window {
             None => return Ok(()),
         };
 
-        let (inventory, slot_area, _) = self.inner.index_to_slot(slot).unwrap();
-        // TODO: correctly support non-player windows
-        let areas_to_try = [
-            Area::Hotbar,
-            Area::Storage,
-            Area::Helmet,
-            Area::Chestplate,
-            Area::Leggings,
-            Area::Boots,
-            Area::CraftingInput,
-        ];
-        for &area in &areas_to_try {
-            if area == slot_area || !will_accept(area, slot_item) {
-                continue;
+        for region in self.shift_click_targets(slot) {
+            let targets: Vec<usize> = region
+                .filter(|&index| index != slot)
+                .filter(|&index| match self.inner.index_to_slot(index) {
+                    Some((_, area, _)) => will_accept(area, slot_item),
+                    None => false,
+                })
+                .collect();
+
+            self.insert_into(&targets, slot_item)?;
+            if slot_item.count() == 0 {
+                break;
             }
+        }
 
-            // Find slot with same type first
-            let mut i = 0;
-            while let Some(mut stack) = inventory.item(area, i) {
-                if let Some(stack) = stack.as_mut() {
-                    if stack.has_same_type(slot_item) {
-                        slot_item.transfer_to(u32::MAX, stack);
-                    }
+        drop(slot_item_guard);
+        self.refresh();
+
+        Ok(())
+    }
+
+    /// Moves the cursor item into the player's inventory, as done
+    /// when the window is closed.
+    ///
+    /// Returns the items that didn't fit, which should be dropped.
+    pub fn return_cursor_item(&mut self) -> SysResult<Option<ItemStack>> {
+        let mut item = match self.cursor_item.take() {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+
+        for region in [self.hotbar(), self.main_inventory()].iter().cloned() {
+            let targets: Vec<usize> = region.collect();
+            self.insert_into(&targets, &mut item)?;
+        }
+
+        self.refresh();
+        if item.count() == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(item))
+        }
+    }
+
+    /// Moves as many items as possible from `item` into the slots
+    /// at `targets`, merging with stacks of the same type first.
+    fn insert_into(&self, targets: &[usize], item: &mut ItemStack) -> SysResult {
+        // Find slot with same type first
+        for &index in targets {
+            if let Some(stack) = self.inner.item(index)?.as_mut() {
+                if stack.has_same_type(item) {
+                    item.transfer_to(u32::MAX, stack);
                 }
-                i += 1;
             }
+        }
 
-            // If we still haven't moved all the items, transfer to any empty space
-            i = 0;
-            while let Some(mut stack) = inventory.item(area, i) {
+        // If we still haven't moved all the items, transfer to any empty space
+        if item.count() > 0 {
+            for &index in targets {
+                let mut stack = self.inner.item(index)?;
                 if stack.is_none() {
-                    *stack = Some(slot_item.take(u32::MAX));
+                    *stack = Some(item.take(u32::MAX));
                     break;
                 }
-                i += 1;
-            }
-
-            if slot_item.count() == 0 {
-                break;
             }
         }
 
-        drop(slot_item_guard);
-        self.refresh();
-
         Ok(())
     }
 
+    /// Returns the regions an item shift-clicked at `index`
+    /// is moved to, in order of preference.
+    ///
+    /// * In the player window, items move between the hotbar and the
+    ///   main inventory. Armor is equipped if its slot is empty.
+    /// * In storage containers (chests, hoppers, ...), items move between
+    ///   the container and the player's inventory.
+    /// * In other windows, items move into the container if it accepts
+    ///   them and otherwise between the hotbar and the main inventory.
+    pub fn shift_click_targets(&self, index: usize) -> Vec<Range<usize>> {
+        let hotbar = self.hotbar();
+        let main_inventory = self.main_inventory();
+        let from_hotbar = hotbar.contains(&index);
+        let from_main_inventory = main_inventory.contains(&index);
+
+        match &self.inner {
+            BackingWindow::Player { .. } => {
+                if from_hotbar {
+                    vec![self.armor(), main_inventory]
+                } else if from_main_inventory {
+                    vec![self.armor(), hotbar]
+                } else {
+                    vec![main_inventory, hotbar]
+                }
+            }
+            BackingWindow::Generic9x1 { .. }
+            | BackingWindow::Generic9x2 { .. }
+            | BackingWindow::Generic9x3 { .. }
+            | BackingWindow::Generic9x4 { .. }
+            | BackingWindow::Generic9x5 { .. }
+            | BackingWindow::Generic9x6 { .. }
+            | BackingWindow::Generic3x3 { .. }
+            | BackingWindow::Hopper { .. }
+            | BackingWindow::ShulkerBox { .. } => {
+                if from_hotbar || from_main_inventory {
+                    vec![self.container()]
+                } else {
+                    vec![hotbar, main_inventory]
+                }
+            }
+            _ => {
+                if from_hotbar {
+                    vec![self.container(), main_inventory]
+                } else if from_main_inventory {
+                    vec![self.container(), hotbar]
+                } else {
+                    vec![hotbar, main_inventory]
+                }
+            }
+        }
+    }
+
     /// Starts a left mouse paint operation.
     pub fn begin_left_mouse_paint(&mut self) {
         self.paint_state = Some(PaintState::new(Mouse::Left));
@@ -176,6 +277,14 @@ impl Window {
         self.cursor_item.clone()
     }
 
+    /// Removes up to `amount` items from the cursor and returns them,
+    /// or `None` if the cursor is empty.
+    pub fn take_cursor_item(&mut self, amount: u32) -> Option<ItemStack> {
+        let taken = self.cursor_item.as_mut()?.take(amount);
+        Self::refresh_item(&mut self.cursor_item);
+        Some(taken)
+    }
+
     /// Refreshes items by fixing item stacks with count=0.
     fn refresh(&mut self) {
         Self::refresh_item(&mut self.cursor_item);
@@ -205,6 +314,127 @@ impl Window {
     pub fn inner(&self) -> &BackingWindow {
         &self.inner
     }
+
+    /// Returns the window indices of the player's hotbar.
+    pub fn hotbar(&self) -> Range<usize> {
+        self.player_area(Area::Hotbar)
+    }
+
+    /// Returns the window indices of the player's main
+    /// inventory, i.e. the 27 slots above the hotbar.
+    pub fn main_inventory(&self) -> Range<usize> {
+        self.player_area(Area::Storage)
+    }
+
+    /// Returns the window indices of the player's armor slots,
+    /// ordered helmet, chestplate, leggings, boots.
+    ///
+    /// Empty if the window doesn't show the armor slots.
+    pub fn armor(&self) -> Range<usize> {
+        let helmet = self.player_area(Area::Helmet);
+        let boots = self.player_area(Area::Boots);
+        if helmet.is_empty() || boots.is_empty() {
+            return 0..0;
+        }
+        helmet.start..boots.end
+    }
+
+    /// Returns the window index of the player's offhand slot.
+    ///
+    /// Empty if the window doesn't show the offhand.
+    pub fn offhand(&self) -> Range<usize> {
+        self.player_area(Area::Offhand)
+    }
+
+    /// Returns the window indices of the crafting grid. This is the
+    /// player's 2x2 grid in the player window and the 3x3 grid
+    /// in a crafting table window.
+    ///
+    /// Empty if the window has no crafting grid.
+    pub fn crafting_grid(&self) -> Range<usize> {
+        self.area_indices(|_, area| area == Area::CraftingInput)
+    }
+
+    /// Returns the window indices of the slots that don't belong
+    /// to the player, e.g. the chest in a chest window.
+    fn container(&self) -> Range<usize> {
+        match self.player_inventory() {
+            Some(player) => self.area_indices(|inventory, _| !inventory.ptr_eq(player)),
+            None => 0..0,
+        }
+    }
+
+    /// Returns the indices of `area` within the player's inventory.
+    fn player_area(&self, area: Area) -> Range<usize> {
+        match self.player_inventory() {
+            Some(player) => self.area_indices(|inventory, a| a == area && inventory.ptr_eq(player)),
+            None => 0..0,
+        }
+    }
+
+    fn player_inventory(&self) -> Option<&Inventory> {
+        // The hotbar is only ever part of the player's inventory
+        self.slots()
+            .find(|(_, _, area, _)| *area == Area::Hotbar)
+            .map(|(_, player, _, _)| player)
+    }
+
+    /// Returns the range of window indices whose slots match `predicate`.
+    /// Areas are always contiguous within a window.
+    fn area_indices(&self, predicate: impl Fn(&Inventory, Area) -> bool) -> Range<usize> {
+        let mut indices = self
+            .slots()
+            .filter(|(_, inventory, area, _)| predicate(inventory, *area))
+            .map(|(index, _, _, _)| index);
+        match indices.next() {
+            Some(start) => start..indices.last().unwrap_or(start) + 1,
+            None => 0..0,
+        }
+    }
+
+    /// Iterates over all slots in the window as
+    /// `(index, inventory, area, slot)`.
+    fn slots(&self) -> impl Iterator<Item = (usize, &Inventory, Area, usize)> + '_ {
+        (0..)
+            .map(move |index| self.inner.index_to_slot(index))
+            .take_while(Option::is_some)
+            .flatten()
+            .enumerate()
+            .map(|(index, (inventory, area, slot))| (index, inventory, area, slot))
+    }
+}
+
+/// Gets the armor slot an item can be worn in,
+/// or `None` if the item isn't armor.
+pub fn armor_area(item: Item) -> Option<Area> {
+    match item {
+        Item::LeatherHelmet
+        | Item::ChainmailHelmet
+        | Item::GoldenHelmet
+        | Item::IronHelmet
+        | Item::DiamondHelmet
+        | Item::NetheriteHelmet
+        | Item::TurtleHelmet => Some(Area::Helmet),
+        Item::LeatherChestplate
+        | Item::ChainmailChestplate
+        | Item::GoldenChestplate
+        | Item::IronChestplate
+        | Item::DiamondChestplate
+        | Item::NetheriteChestplate => Some(Area::Chestplate),
+        Item::LeatherLeggings
+        | Item::ChainmailLeggings
+        | Item::GoldenLeggings
+        | Item::IronLeggings
+        | Item::DiamondLeggings
+        | Item::NetheriteLeggings => Some(Area::Leggings),
+        Item::LeatherBoots
+        | Item::ChainmailBoots
+        | Item::GoldenBoots
+        | Item::IronBoots
+        | Item::DiamondBoots
+        | Item::NetheriteBoots => Some(Area::Boots),
+        _ => None,
+    }
 }
 
 /// Determines whether the given area will accept the given item
@@ -214,42 +444,9 @@ fn will_accept(area: Area, stack: &ItemStack) -> bool {
         Area::Storage => true,
         Area::CraftingOutput => false,
         Area::CraftingInput => false,
-        Area::Helmet => matches!(
-            stack.item(),
-            Item::LeatherHelmet
-                | Item::ChainmailHelmet
-                | Item::GoldenHelmet
-                | Item::IronHelmet
-                | Item::DiamondHelmet
-                | Item::NetheriteHelmet
-        ),
-        Area::Chestplate => matches!(
-            stack.item(),
-            Item::LeatherChestplate
-                | Item::ChainmailChestplate
-                | Item::GoldenChestplate
-                | Item::IronChestplate
-                | Item::DiamondChestplate
-                | Item::NetheriteChestplate
-        ),
-        Area::Leggings => matches!(
-            stack.item(),
-            Item::LeatherHelmet
-                | Item::ChainmailLeggings
-                | Item::GoldenLeggings
-                | Item::IronLeggings
-                | Item::DiamondLeggings
-                | Item::NetheriteLeggings
-        ),
-        Area::Boots => matches!(
-            stack.item(),
-            Item::LeatherBoots
-                | Item::ChainmailBoots
-                | Item::GoldenBoots
-                | Item::IronBoots
-                | Item::DiamondBoots
-                | Item::NetheriteBoots
-        ),
+        Area::Helmet | Area::Chestplate | Area::Leggings | Area::Boots => {
+            armor_area(stack.item()) == Some(area)
+        }
         Area::Hotbar => true,
         Area::Offhand => true,
         Area::FurnaceIngredient => true,
@@ -536,6 +733,100 @@ mod tests {
         assert!(window.item(storage_index).unwrap().is_none());
     }
 
+    #[test]
+    fn window_shift_click_chest_to_hotbar() {
+        let (mut window, chest, player) = chest_window();
+        *chest.item(Area::Storage, 4).unwrap() = Some(ItemStack::new(Item::Stone, 7));
+
+        window.shift_click(4).unwrap();
+        assert!(window.item(4).unwrap().is_none());
+        assert_eq!(
+            player.item(Area::Hotbar, 0).unwrap().as_ref(),
+            Some(&ItemStack::new(Item::Stone, 7))
+        );
+    }
+
+    #[test]
+    fn window_shift_click_hotbar_to_chest() {
+        let (mut window, chest, player) = chest_window();
+        *player.item(Area::Hotbar, 2).unwrap() = Some(ItemStack::new(Item::Stone, 7));
+        *chest.item(Area::Storage, 5).unwrap() = Some(ItemStack::new(Item::Stone, 60));
+
+        let index = window.hotbar().start + 2;
+        window.shift_click(index).unwrap();
+        assert!(window.item(index).unwrap().is_none());
+        assert_eq!(
+            chest.item(Area::Storage, 5).unwrap().as_ref(),
+            Some(&ItemStack::new(Item::Stone, 64))
+        );
+        assert_eq!(
+            chest.item(Area::Storage, 0).unwrap().as_ref(),
+            Some(&ItemStack::new(Item::Stone, 3))
+        );
+        // Items never move within the player's inventory
+        assert!(player.item(Area::Storage, 0).unwrap().is_none());
+    }
+
+    #[test]
+    fn window_shift_click_equips_helmet() {
+        let inventory = Inventory::player();
+        *inventory.item(Area::Storage, 0).unwrap() = Some(ItemStack::new(Item::IronHelmet, 1));
+        let mut window = Window::new(BackingWindow::Player {
+            player: inventory.new_handle(),
+        });
+
+        let index = window.main_inventory().start;
+        window.shift_click(index).unwrap();
+        assert!(window.item(index).unwrap().is_none());
+        assert_eq!(
+            inventory.item(Area::Helmet, 0).unwrap().as_ref(),
+            Some(&ItemStack::new(Item::IronHelmet, 1))
+        );
+
+        // With the helmet slot taken, a second helmet goes to the hotbar
+        *inventory.item(Area::Storage, 0).unwrap() = Some(ItemStack::new(Item::GoldenHelmet, 1));
+        window.shift_click(index).unwrap();
+        assert_eq!(
+            inventory.item(Area::Hotbar, 0).unwrap().as_ref(),
+            Some(&ItemStack::new(Item::GoldenHelmet, 1))
+        );
+    }
+
+    #[test]
+    fn action_numbers_must_increase() {
+        let mut window = window();
+        assert!(window.check_action_number(5));
+        assert!(window.check_action_number(6));
+        assert!(!window.check_action_number(6));
+        assert!(!window.check_action_number(2));
+        assert!(window.check_action_number(8));
+        assert!(!window.check_action_number(7));
+        assert!(window.check_action_number(9));
+
+        window.last_action_number = Some(i16::MAX);
+        assert!(window.check_action_number(i16::MIN + 2));
+    }
+
+    #[test]
+    fn return_cursor_item_to_inventory() {
+        let mut window = window();
+        window
+            .set_item(36, Some(ItemStack::new(Item::Stone, 60)))
+            .unwrap();
+        window.cursor_item = Some(ItemStack::new(Item::Stone, 10));
+
+        assert_eq!(window.return_cursor_item().unwrap(), None);
+        assert_eq!(window.cursor_item, None);
+        assert_eq!(
+            window.item(36).unwrap().as_ref(),
+            Some(&ItemStack::new(Item::Stone, 64))
+        );
+        assert_eq!(
+            window.item(37).unwrap().as_ref(),
+            Some(&ItemStack::new(Item::Stone, 6))
+        );
+    }
+
     #[test]
     fn left_mouse_paint() {
         let mut window = window();
@@ -586,6 +877,39 @@ mod tests {
         assert_eq!(window.cursor_item, Some(ItemStack::new(Item::Stone, 62)));
     }
 
+    #[test]
+    fn player_window_regions() {
+        let window = window();
+        assert_eq!(window.crafting_grid(), 1..5);
+        assert_eq!(window.armor(), 5..9);
+        assert_eq!(window.main_inventory(), 9..36);
+        assert_eq!(window.hotbar(), 36..45);
+        assert_eq!(window.offhand(), 45..46);
+    }
+
+    #[test]
+    fn chest_window_regions() {
+        let window = Window::new(BackingWindow::Generic9x3 {
+            block: Inventory::chest(),
+            player: Inventory::player(),
+        });
+        assert_eq!(window.main_inventory(), 27..54);
+        assert_eq!(window.hotbar(), 54..63);
+        assert!(window.armor().is_empty());
+        assert!(window.offhand().is_empty());
+        assert!(window.crafting_grid().is_empty());
+    }
+
+    fn chest_window() -> (Window, Inventory, Inventory) {
+        let chest = Inventory::chest();
+        let player = Inventory::player();
+        let window = Window::new(BackingWindow::Generic9x3 {
+            block: chest.new_handle(),
+            player: player.new_handle(),
+        });
+        (window, chest, player)
+    }
+
     fn window() -> Window {
         Window::new(BackingWindow::Player {
             player: Inventory::player(),
@@ -1,43 +1,58 @@
 use ahash::{AHashMap, AHashSet};
-use base::{BlockPosition, Chunk, ChunkHandle, ChunkLock, ChunkPosition, CHUNK_HEIGHT};
+use base::{
+    anvil::{
+        block_entity::BlockEntityData, entity::EntityData, player::PlayerData,
+        region::DEFAULT_COMPRESSION_LEVEL,
+    },
+    BlockPosition, Chunk, ChunkHandle, ChunkLock, ChunkPosition, CHUNK_HEIGHT,
+};
 use blocks::BlockId;
 use ecs::{Ecs, SysResult};
-use parking_lot::{RwLockReadGuard, RwLockWriteGuard};
-use std::{path::PathBuf, sync::Arc};
+use parking_lot::{Mutex, RwLockReadGuard, RwLockWriteGuard};
+use std::{mem, path::PathBuf, sync::Arc};
+use uuid::Uuid;
 use worldgen::{ComposableGenerator, WorldGenerator};
 
 use crate::{
+    block_entity::{BlockEntities, BlockEntity},
     chunk::cache::ChunkCache,
     chunk::worker::{ChunkWorker, LoadRequest, SaveRequest},
     events::ChunkLoadEvent,
+    world_storage::{FileStorage, WorldStorage},
 };
 
 /// Stores all blocks and chunks in a world,
 /// along with global world data like weather, time,
-/// and the [`WorldSource`](crate::world_source::WorldSource).
+/// and the [`WorldStorage`] the world is saved to.
 ///
 /// NB: _not_ what most Rust ECSs call "world."
-/// This does not store entities; it only contains blocks.
+/// This does not store entities; it only contains blocks
+/// and their block entities.
 pub struct World {
     chunk_map: ChunkMap,
+    block_entities: BlockEntities,
     pub cache: ChunkCache,
     chunk_worker: ChunkWorker,
+    /// Shared with the chunk worker.
+    storage: Arc<Mutex<dyn WorldStorage>>,
     loading_chunks: AHashSet<ChunkPosition>,
     canceled_chunk_loads: AHashSet<ChunkPosition>,
+    /// Entities of chunks in the cache, restored
+    /// when the chunk is loaded again.
+    cached_entities: AHashMap<ChunkPosition, Vec<EntityData>>,
+    /// Block entities of chunks in the cache.
+    cached_block_entities: AHashMap<ChunkPosition, Vec<BlockEntityData>>,
+    /// Entities of newly loaded chunks that have yet to be spawned.
+    loaded_entities: Vec<EntityData>,
 }
 
 impl Default for World {
     fn default() -> Self {
-        Self {
-            chunk_map: ChunkMap::new(),
-            chunk_worker: ChunkWorker::new(
-                "world",
-                Arc::new(ComposableGenerator::default_with_seed(0)),
-            ),
-            cache: ChunkCache::new(),
-            loading_chunks: AHashSet::new(),
-            canceled_chunk_loads: AHashSet::new(),
-        }
+        Self::with_gen_and_path(
+            Arc::new(ComposableGenerator::default_with_seed(0)),
+            "world",
+            DEFAULT_COMPRESSION_LEVEL,
+        )
     }
 }
 
@@ -46,13 +61,36 @@ impl World {
         Self::default()
     }
 
+    /// Creates a world stored in `world_dir`, generating missing chunks
+    /// with `generator`. Saved chunks are compressed at the zlib `compression_level`.
     pub fn with_gen_and_path(
         generator: Arc<dyn WorldGenerator>,
         world_dir: impl Into<PathBuf>,
+        compression_level: u32,
     ) -> Self {
+        Self::with_gen_and_storage(generator, FileStorage::new(world_dir, compression_level))
+    }
+
+    /// Creates a world saved to `storage`, generating missing chunks
+    /// with `generator`.
+    ///
+    /// Use a [`MemoryStorage`](crate::MemoryStorage) to keep the world in memory.
+    pub fn with_gen_and_storage(
+        generator: Arc<dyn WorldGenerator>,
+        storage: impl WorldStorage,
+    ) -> Self {
+        let storage: Arc<Mutex<dyn WorldStorage>> = Arc::new(Mutex::new(storage));
         Self {
-            chunk_worker: ChunkWorker::new(world_dir, generator),
-            ..Default::default()
+            chunk_map: ChunkMap::new(),
+            block_entities: BlockEntities::new(),
+            chunk_worker: ChunkWorker::new(Arc::clone(&storage), generator),
+            storage,
+            cache: ChunkCache::new(),
+            loading_chunks: AHashSet::new(),
+            canceled_chunk_loads: AHashSet::new(),
+            cached_entities: AHashMap::new(),
+            cached_block_entities: AHashMap::new(),
+            loaded_entities: Vec::new(),
         }
     }
 
@@ -65,6 +103,12 @@ impl World {
                 .0
                 .insert(pos, self.cache.remove(pos).unwrap());
             self.chunk_map.chunk_handle_at(pos).unwrap().set_loaded();
+            if let Some(entities) = self.cached_entities.remove(&pos) {
+                self.loaded_entities.extend(entities);
+            }
+            if let Some(block_entities) = self.cached_block_entities.remove(&pos) {
+                self.block_entities.load_chunk(&block_entities);
+            }
         } else {
             self.loading_chunks.insert(req.pos);
             self.chunk_worker.queue_load(req);
@@ -80,6 +124,8 @@ impl World {
                 continue;
             }
             let chunk = loaded.chunk;
+            self.loaded_entities.extend(loaded.entities);
+            self.block_entities.load_chunk(&loaded.block_entities);
 
             self.chunk_map.insert_chunk(chunk);
             ecs.insert_event(ChunkLoadEvent {
@@ -91,17 +137,25 @@ impl World {
         Ok(())
     }
 
-    /// Unloads the given chunk.
-    pub fn unload_chunk(&mut self, pos: ChunkPosition) -> anyhow::Result<()> {
+    /// Unloads the given chunk, saving it along
+    /// with the given entities and its block entities.
+    pub fn unload_chunk(
+        &mut self,
+        pos: ChunkPosition,
+        entities: Vec<EntityData>,
+    ) -> anyhow::Result<()> {
         if let Some((pos, handle)) = self.chunk_map.0.remove_entry(&pos) {
             handle.set_unloaded()?;
+            let block_entities = self.block_entities.take_chunk(pos);
             self.chunk_worker.queue_chunk_save(SaveRequest {
                 pos,
                 chunk: handle.clone(),
-                entities: vec![],
-                block_entities: vec![],
+                entities: entities.clone(),
+                block_entities: block_entities.clone(),
             });
             self.cache.insert(pos, handle);
+            self.cached_entities.insert(pos, entities);
+            self.cached_block_entities.insert(pos, block_entities);
         }
         self.chunk_map.remove_chunk(pos);
         if self.is_chunk_loading(pos) {
@@ -112,6 +166,33 @@ impl World {
         Ok(())
     }
 
+    /// Removes unused chunks from the cache.
+    pub fn purge_cache(&mut self) {
+        self.cache.purge_unused();
+        let cache = &self.cache;
+        self.cached_entities.retain(|pos, _| cache.contains(pos));
+        self.cached_block_entities
+            .retain(|pos, _| cache.contains(pos));
+    }
+
+    /// Takes the entities stored in chunks loaded
+    /// since the last call. They should be spawned
+    /// into the `Game`.
+    pub fn take_loaded_entities(&mut self) -> Vec<EntityData> {
+        mem::take(&mut self.loaded_entities)
+    }
+
+    /// Loads the data of the player with `uuid` from the world's storage.
+    /// Returns `Ok(None)` if the player has no saved data.
+    pub fn load_player_data(&self, uuid: Uuid) -> anyhow::Result<Option<PlayerData>> {
+        self.storage.lock().load_player_data(uuid)
+    }
+
+    /// Saves the data of the player with `uuid` to the world's storage.
+    pub fn save_player_data(&self, uuid: Uuid, data: &PlayerData) -> anyhow::Result<()> {
+        self.storage.lock().save_player_data(uuid, data)
+    }
+
     /// Returns whether the given chunk is loaded.
     pub fn is_chunk_loaded(&self, pos: ChunkPosition) -> bool {
         self.chunk_map.0.contains_key(&pos)
@@ -128,8 +209,26 @@ impl World {
     /// if its chunk was not loaded or the coordinates
     /// are out of bounds and thus no operation
     /// was performed.
-    pub fn set_block_at(&self, pos: BlockPosition, block: BlockId) -> bool {
-        self.chunk_map.set_block_at(pos, block)
+    ///
+    /// The block entity at the position is removed if the
+    /// block type changes, and the new block's block entity
+    /// (if any) is created.
+    pub fn set_block_at(&mut self, pos: BlockPosition, block: BlockId) -> bool {
+        let old = match self.chunk_map.block_at(pos) {
+            Some(old) => old,
+            None => return false,
+        };
+        if !self.chunk_map.set_block_at(pos, block) {
+            return false;
+        }
+
+        if old.kind() != block.kind() {
+            self.block_entities.remove(pos);
+            if let Some(block_entity) = BlockEntity::new_for_block(block) {
+                self.block_entities.insert(pos, block_entity);
+            }
+        }
+        true
     }
 
     /// Retrieves the block at the specified
@@ -140,6 +239,16 @@ impl World {
         self.chunk_map.block_at(pos)
     }
 
+    /// Returns the block entities of loaded chunks.
+    pub fn block_entities(&self) -> &BlockEntities {
+        &self.block_entities
+    }
+
+    /// Mutably returns the block entities of loaded chunks.
+    pub fn block_entities_mut(&mut self) -> &mut BlockEntities {
+        &mut self.block_entities
+    }
+
     /// Returns the chunk map.
     pub fn chunk_map(&self) -> &ChunkMap {
         &self.chunk_map
@@ -206,6 +315,16 @@ impl ChunkMap {
             .is_some()
     }
 
+    /// Returns the number of loaded chunks.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether no chunks are loaded.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     /// Returns an iterator over chunks.
     pub fn iter_chunks(&self) -> impl IntoIterator<Item = &ChunkHandle> {
         self.0.values()
@@ -253,4 +372,22 @@ mod tests {
         assert!(world.block_at(BlockPosition::new(0, -1, 0)).is_none());
         assert!(world.block_at(BlockPosition::new(0, 0, 0)).is_some());
     }
+
+    #[test]
+    fn set_block_updates_block_entity() {
+        let mut world = World::new();
+        world
+            .chunk_map_mut()
+            .insert_chunk(Chunk::new(ChunkPosition::new(0, 0)));
+        let pos = BlockPosition::new(1, 64, 1);
+
+        assert!(world.set_block_at(pos, BlockId::chest()));
+        assert!(matches!(
+            world.block_entities().get(pos),
+            Some(BlockEntity::Chest(_))
+        ));
+
+        assert!(world.set_block_at(pos, BlockId::air()));
+        assert!(world.block_entities().get(pos).is_none());
+    }
 }
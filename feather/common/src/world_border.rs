@@ -0,0 +1,268 @@
+//! The world border.
+//!
+//! The border is a square centered on [`WorldBorder::center_x`]
+//! and [`WorldBorder::center_z`]. It can grow or shrink over time,
+//! and damages entities that stay outside of it.
+
+use base::{anvil::level::LevelData, Gamemode, Position};
+use ecs::{Entity, SysResult, SystemExecutor};
+
+use crate::{
+    damage::{self, DamageSource, Health},
+    Game,
+};
+
+/// Diameter of the border in new worlds.
+pub const DEFAULT_DIAMETER: f64 = 59_999_968.;
+
+/// The border can't be wider than this.
+pub const MAX_DIAMETER: f64 = 60_000_000.;
+
+/// Entities outside the border take damage once per this many ticks.
+const DAMAGE_INTERVAL: u64 = 10;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(update_world_border);
+}
+
+/// Resource storing the world border.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldBorder {
+    pub center_x: f64,
+    pub center_z: f64,
+    /// Damage dealt for each block an entity is
+    /// past the border and its buffer.
+    pub damage_per_block: f64,
+    /// Distance past the border entities can go without taking damage.
+    pub damage_buffer: f64,
+    /// Distance from the border at which players see a warning.
+    pub warning_blocks: u32,
+    /// Players see a warning when a shrinking border
+    /// will reach them within this many seconds.
+    pub warning_time: u32,
+    diameter: f64,
+    lerp: Option<Lerp>,
+}
+
+/// A gradual change of the border's diameter.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Lerp {
+    from: f64,
+    to: f64,
+    total_ticks: u64,
+    elapsed_ticks: u64,
+}
+
+impl Default for WorldBorder {
+    fn default() -> Self {
+        Self {
+            center_x: 0.,
+            center_z: 0.,
+            damage_per_block: 0.2,
+            damage_buffer: 5.,
+            warning_blocks: 5,
+            warning_time: 15,
+            diameter: DEFAULT_DIAMETER,
+            lerp: None,
+        }
+    }
+}
+
+impl WorldBorder {
+    /// Reads the border stored in a level.
+    pub fn from_level(level: &LevelData) -> Self {
+        let mut border = Self {
+            center_x: level.border_center_x,
+            center_z: level.border_center_z,
+            damage_per_block: level.border_damage_per_block,
+            damage_buffer: level.border_safe_zone,
+            warning_blocks: level.border_warning_blocks as u32,
+            warning_time: level.border_warning_time as u32,
+            diameter: level.border_size,
+            lerp: None,
+        };
+        if level.border_size_lerp_time > 0 {
+            let ticks = level.border_size_lerp_time as u64 / 50;
+            border.lerp_diameter(level.border_size_lerp_target, ticks);
+        }
+        border
+    }
+
+    /// Stores the border in a level.
+    pub fn write_to_level(&self, level: &mut LevelData) {
+        level.border_center_x = self.center_x;
+        level.border_center_z = self.center_z;
+        level.border_damage_per_block = self.damage_per_block;
+        level.border_safe_zone = self.damage_buffer;
+        level.border_warning_blocks = self.warning_blocks as f64;
+        level.border_warning_time = self.warning_time as f64;
+        level.border_size = self.diameter();
+        level.border_size_lerp_target = self.target_diameter();
+        level.border_size_lerp_time = (self.remaining_ticks() * 50) as i64;
+    }
+
+    /// Gets the current width of the border.
+    pub fn diameter(&self) -> f64 {
+        match self.lerp {
+            Some(lerp) => {
+                let progress = lerp.elapsed_ticks as f64 / lerp.total_ticks as f64;
+                lerp.from + (lerp.to - lerp.from) * progress
+            }
+            None => self.diameter,
+        }
+    }
+
+    /// Gets the width the border is moving towards,
+    /// or its current width if it isn't moving.
+    pub fn target_diameter(&self) -> f64 {
+        self.lerp.map_or(self.diameter, |lerp| lerp.to)
+    }
+
+    /// Gets the number of ticks until the border
+    /// reaches its target width.
+    pub fn remaining_ticks(&self) -> u64 {
+        self.lerp
+            .map_or(0, |lerp| lerp.total_ticks - lerp.elapsed_ticks)
+    }
+
+    /// Immediately sets the width of the border.
+    pub fn set_diameter(&mut self, diameter: f64) {
+        self.diameter = diameter;
+        self.lerp = None;
+    }
+
+    /// Moves the border to `diameter` over `ticks` ticks,
+    /// starting from its current width.
+    pub fn lerp_diameter(&mut self, diameter: f64, ticks: u64) {
+        if ticks == 0 {
+            self.set_diameter(diameter);
+            return;
+        }
+        self.lerp = Some(Lerp {
+            from: self.diameter(),
+            to: diameter,
+            total_ticks: ticks,
+            elapsed_ticks: 0,
+        });
+    }
+
+    /// Gets how far `position` is outside the border.
+    /// Negative for positions inside it.
+    pub fn distance_outside(&self, position: Position) -> f64 {
+        let radius = self.diameter() / 2.;
+        let dx = (position.x - self.center_x).abs() - radius;
+        let dz = (position.z - self.center_z).abs() - radius;
+        dx.max(dz)
+    }
+
+    fn tick(&mut self) {
+        if let Some(lerp) = &mut self.lerp {
+            lerp.elapsed_ticks += 1;
+            if lerp.elapsed_ticks >= lerp.total_ticks {
+                let target = lerp.to;
+                self.set_diameter(target);
+            }
+        }
+    }
+}
+
+fn update_world_border(game: &mut Game) -> SysResult {
+    let border = {
+        let mut border = game.resources.get_mut::<WorldBorder>()?;
+        border.tick();
+        border.clone()
+    };
+    if game.tick_count % DAMAGE_INTERVAL != 0 || border.damage_per_block <= 0. {
+        return Ok(());
+    }
+
+    let mut outside: Vec<(Entity, f32)> = game
+        .ecs
+        .query::<(&Position, &Health, Option<&Gamemode>)>()
+        .iter()
+        .filter(|(_, (_, _, gamemode))| {
+            !matches!(
+                gamemode,
+                Some(Gamemode::Creative) | Some(Gamemode::Spectator)
+            )
+        })
+        .filter_map(|(entity, (&position, _, _))| {
+            let distance = border.distance_outside(position) - border.damage_buffer;
+            if distance <= 0. {
+                return None;
+            }
+            let amount = (distance * border.damage_per_block).floor().max(1.);
+            Some((entity, amount as f32))
+        })
+        .collect();
+    game.sort_for_tick(&mut outside);
+
+    for (entity, amount) in outside {
+        damage::damage(game, entity, amount, DamageSource::Generic)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+
+    use super::*;
+
+    #[test]
+    fn lerp_diameter() {
+        let mut border = WorldBorder::default();
+        border.set_diameter(100.);
+        border.lerp_diameter(50., 10);
+        for _ in 0..4 {
+            border.tick();
+        }
+        assert_eq!(border.diameter(), 80.);
+        assert_eq!(border.target_diameter(), 50.);
+        assert_eq!(border.remaining_ticks(), 6);
+
+        for _ in 0..6 {
+            border.tick();
+        }
+        assert_eq!(border.diameter(), 50.);
+        assert_eq!(border.remaining_ticks(), 0);
+    }
+
+    #[test]
+    fn stale_lerp_target_is_ignored() {
+        let level = LevelData {
+            border_size: 100.,
+            border_size_lerp_target: 0.,
+            border_size_lerp_time: 0,
+            ..Default::default()
+        };
+        let border = WorldBorder::from_level(&level);
+        assert_eq!(border.diameter(), 100.);
+        assert_eq!(border.remaining_ticks(), 0);
+
+        let level = LevelData {
+            border_size_lerp_target: 50.,
+            border_size_lerp_time: 1000,
+            ..level
+        };
+        let border = WorldBorder::from_level(&level);
+        assert_eq!(border.target_diameter(), 50.);
+        assert_eq!(border.remaining_ticks(), 20);
+    }
+
+    #[test]
+    fn damages_entities_outside() {
+        let mut game = Game::new();
+        game.resources
+            .get_mut::<WorldBorder>()
+            .unwrap()
+            .set_diameter(20.);
+        let inside = game.ecs.spawn((position!(8.0, 64.0, 0.0), Health(20.)));
+        let outside = game.ecs.spawn((position!(0.0, 64.0, -30.0), Health(20.)));
+
+        update_world_border(&mut game).unwrap();
+        assert_eq!(*game.ecs.get::<Health>(inside).unwrap(), Health(20.));
+        // 20 blocks past the border minus a buffer of 5
+        assert_eq!(*game.ecs.get::<Health>(outside).unwrap(), Health(17.));
+    }
+}
@@ -0,0 +1,396 @@
+//! Persistence of chunks and player data.
+//!
+//! A [`World`](crate::World) saves to a [`WorldStorage`] chosen
+//! when it is created: [`FileStorage`] uses the Anvil files of
+//! a world directory, while [`MemoryStorage`] keeps everything in
+//! memory, which is useful for tests.
+
+use std::{
+    collections::hash_map::Entry,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+use base::{
+    anvil::{
+        self,
+        block_entity::BlockEntityData,
+        entity::EntityData,
+        player::PlayerData,
+        region::{RegionHandle, RegionPosition, DEFAULT_COMPRESSION_LEVEL},
+    },
+    Chunk, ChunkPosition,
+};
+use uuid::Uuid;
+
+use crate::chunk::worker::{ChunkLoadResult, LoadedChunk};
+
+/// Duration to keep a region file open when not in use.
+const CACHE_TIME: Duration = Duration::from_secs(60);
+
+/// Storage backend of a world's chunks and player data.
+///
+/// Chunks are loaded and saved on the chunk worker thread.
+pub trait WorldStorage: Send + 'static {
+    /// Loads the chunk at `pos` along with its entities
+    /// and block entities.
+    fn load_chunk(&mut self, pos: ChunkPosition) -> ChunkLoadResult;
+
+    /// Saves a chunk along with its entities and block entities,
+    /// replacing the chunk stored at its position.
+    fn save_chunk(
+        &mut self,
+        chunk: &Chunk,
+        entities: &[EntityData],
+        block_entities: &[BlockEntityData],
+    ) -> anyhow::Result<()>;
+
+    /// Loads the data of the player with `uuid`. Returns `Ok(None)`
+    /// if no data was saved for the player.
+    fn load_player_data(&mut self, uuid: Uuid) -> anyhow::Result<Option<PlayerData>>;
+
+    /// Saves the data of the player with `uuid`.
+    fn save_player_data(&mut self, uuid: Uuid, data: &PlayerData) -> anyhow::Result<()>;
+
+    /// Called regularly by the chunk worker to free
+    /// resources that are no longer used.
+    fn update(&mut self) {}
+}
+
+struct OpenRegionFile {
+    handle: RegionHandle,
+    last_used: Instant,
+}
+
+impl OpenRegionFile {
+    pub fn new(handle: RegionHandle) -> Self {
+        Self {
+            handle,
+            last_used: Instant::now(),
+        }
+    }
+
+    pub fn should_close(&self) -> bool {
+        self.last_used.elapsed() >= CACHE_TIME
+    }
+}
+
+/// Stores a world in the region and player data files
+/// of a world directory, like vanilla does.
+pub struct FileStorage {
+    world_dir: PathBuf,
+    /// The zlib compression level of saved chunks.
+    compression_level: u32,
+    region_files: AHashMap<RegionPosition, OpenRegionFile>,
+    last_cache_update: Instant,
+}
+
+impl FileStorage {
+    /// Creates a storage for the world in `world_dir`. Saved chunks
+    /// are compressed at the zlib `compression_level`.
+    pub fn new(world_dir: impl Into<PathBuf>, compression_level: u32) -> Self {
+        Self {
+            world_dir: world_dir.into(),
+            compression_level,
+            region_files: AHashMap::new(),
+            last_cache_update: Instant::now(),
+        }
+    }
+
+    fn region_file_handle(&mut self, region: RegionPosition) -> Option<&mut OpenRegionFile> {
+        match self.region_files.entry(region) {
+            Entry::Occupied(e) => Some(e.into_mut()),
+            Entry::Vacant(e) => {
+                let handle = anvil::region::load_region(&self.world_dir, region);
+                if let Ok(mut handle) = handle {
+                    handle.set_compression_level(self.compression_level);
+                    Some(e.insert(OpenRegionFile::new(handle)))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+impl WorldStorage for FileStorage {
+    fn load_chunk(&mut self, pos: ChunkPosition) -> ChunkLoadResult {
+        let region = RegionPosition::from_chunk(pos);
+        let file = match self.region_file_handle(region) {
+            Some(file) => file,
+            None => return ChunkLoadResult::Missing(pos),
+        };
+
+        let (chunk, entities, block_entities) = match file.handle.load_chunk(pos) {
+            Ok(loaded) => loaded,
+            Err(e) => match e {
+                anvil::region::Error::ChunkNotExist => return ChunkLoadResult::Missing(pos),
+                err => return ChunkLoadResult::Error(err.into()),
+            },
+        };
+
+        file.last_used = Instant::now();
+
+        ChunkLoadResult::Loaded(LoadedChunk {
+            pos,
+            chunk,
+            entities,
+            block_entities,
+        })
+    }
+
+    fn save_chunk(
+        &mut self,
+        chunk: &Chunk,
+        entities: &[EntityData],
+        block_entities: &[BlockEntityData],
+    ) -> anyhow::Result<()> {
+        let reg_pos = RegionPosition::from_chunk(chunk.position());
+        let handle = &mut match self.region_file_handle(reg_pos) {
+            Some(h) => h,
+            None => {
+                let mut new_handle = anvil::region::create_region(&self.world_dir, reg_pos)?;
+                new_handle.set_compression_level(self.compression_level);
+                self.region_files
+                    .insert(reg_pos, OpenRegionFile::new(new_handle));
+                self.region_file_handle(reg_pos).unwrap()
+            }
+        }
+        .handle;
+        handle.save_chunk(chunk, entities, block_entities)?;
+        Ok(())
+    }
+
+    fn load_player_data(&mut self, uuid: Uuid) -> anyhow::Result<Option<PlayerData>> {
+        if !anvil::player::player_data_exists(&self.world_dir, uuid) {
+            return Ok(None);
+        }
+        Ok(Some(anvil::player::load_player_data(
+            &self.world_dir,
+            uuid,
+        )?))
+    }
+
+    fn save_player_data(&mut self, uuid: Uuid, data: &PlayerData) -> anyhow::Result<()> {
+        anvil::player::save_player_data(&self.world_dir, uuid, data)
+    }
+
+    fn update(&mut self) {
+        if self.last_cache_update.elapsed() >= CACHE_TIME {
+            let initial_len = self.region_files.len();
+
+            self.region_files.retain(|_, file| !file.should_close());
+            self.last_cache_update = Instant::now();
+
+            let num_closed = initial_len - self.region_files.len();
+            if num_closed != 0 {
+                log::debug!(
+                    "Closed {} region files ({} still open)",
+                    num_closed,
+                    self.region_files.len()
+                );
+            }
+        }
+    }
+}
+
+/// Keeps a world in memory. Nothing is written to disk.
+///
+/// Chunks and player data are still serialized as they would
+/// be in files, so saving and loading behaves like [`FileStorage`].
+#[derive(Default)]
+pub struct MemoryStorage {
+    chunks: AHashMap<ChunkPosition, Vec<u8>>,
+    players: AHashMap<Uuid, Vec<u8>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl WorldStorage for MemoryStorage {
+    fn load_chunk(&mut self, pos: ChunkPosition) -> ChunkLoadResult {
+        let buf = match self.chunks.get(&pos) {
+            Some(buf) => buf,
+            None => return ChunkLoadResult::Missing(pos),
+        };
+        match anvil::region::deserialize_chunk(pos, buf) {
+            Ok((chunk, entities, block_entities)) => ChunkLoadResult::Loaded(LoadedChunk {
+                pos,
+                chunk,
+                entities,
+                block_entities,
+            }),
+            Err(e) => ChunkLoadResult::Error(e.into()),
+        }
+    }
+
+    fn save_chunk(
+        &mut self,
+        chunk: &Chunk,
+        entities: &[EntityData],
+        block_entities: &[BlockEntityData],
+    ) -> anyhow::Result<()> {
+        let buf = anvil::region::serialize_chunk(
+            chunk,
+            entities,
+            block_entities,
+            DEFAULT_COMPRESSION_LEVEL,
+        )?;
+        self.chunks.insert(chunk.position(), buf);
+        Ok(())
+    }
+
+    fn load_player_data(&mut self, uuid: Uuid) -> anyhow::Result<Option<PlayerData>> {
+        match self.players.get(&uuid) {
+            Some(buf) => Ok(Some(anvil::player::deserialize_player_data(buf)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn save_player_data(&mut self, uuid: Uuid, data: &PlayerData) -> anyhow::Result<()> {
+        let buf = anvil::player::serialize_player_data(data)?;
+        self.players.insert(uuid, buf);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use base::{
+        anvil::{
+            entity::{BaseEntityData, ItemData, ItemEntityData},
+            player::InventorySlot,
+        },
+        position, BlockId, BlockPosition,
+    };
+    use ecs::Ecs;
+    use tempfile::TempDir;
+    use worldgen::EmptyWorldGenerator;
+
+    use super::*;
+    use crate::{chunk::worker::LoadRequest, World};
+
+    /// What a world observes after saving and reloading a chunk.
+    #[derive(Debug, PartialEq)]
+    struct ChunkOutcome {
+        blocks: Vec<BlockId>,
+        block_entity: String,
+        entities: String,
+    }
+
+    /// Saves a chunk with a block, a block entity and an entity
+    /// to `storage`, then loads it back.
+    fn chunk_round_trip(storage: impl WorldStorage) -> ChunkOutcome {
+        let mut world = World::with_gen_and_storage(Arc::new(EmptyWorldGenerator {}), storage);
+        let pos = ChunkPosition::new(-1, 2);
+        world.chunk_map_mut().insert_chunk(Chunk::new(pos));
+        let stone = BlockPosition::new(-3, 64, 40);
+        let chest = BlockPosition::new(-3, 65, 40);
+        assert!(world.set_block_at(stone, BlockId::stone()));
+        assert!(world.set_block_at(chest, BlockId::chest()));
+
+        let item = EntityData::Item(ItemEntityData {
+            entity: BaseEntityData::new(position!(-2.5, 66.0, 41.5), Default::default()),
+            item: ItemData {
+                count: 3,
+                item: "minecraft:diamond".to_owned(),
+                nbt: None,
+            },
+            ..Default::default()
+        });
+        world.unload_chunk(pos, vec![item]).unwrap();
+
+        // Make sure the chunk is loaded from storage
+        world.cache.purge_all();
+        world.queue_chunk_load(LoadRequest { pos });
+        let mut ecs = Ecs::new();
+        for _ in 0..1000 {
+            world.load_chunks(&mut ecs).unwrap();
+            if world.is_chunk_loaded(pos) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(world.is_chunk_loaded(pos), "chunk was not loaded");
+
+        let chunk = world.chunk_map().chunk_at(pos).unwrap();
+        let mut blocks = Vec::new();
+        for x in 0..16 {
+            for y in 0..256 {
+                for z in 0..16 {
+                    blocks.push(chunk.block_at(x, y, z).unwrap());
+                }
+            }
+        }
+        drop(chunk);
+        ChunkOutcome {
+            blocks,
+            block_entity: format!("{:?}", world.block_entities().get(chest)),
+            entities: format!("{:?}", world.take_loaded_entities()),
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct PlayerOutcome {
+        missing: bool,
+        gamemode: i32,
+        held_item: i32,
+        inventory: Vec<InventorySlot>,
+    }
+
+    fn player_round_trip(mut storage: impl WorldStorage) -> PlayerOutcome {
+        let uuid = Uuid::new_v4();
+        let missing = storage.load_player_data(uuid).unwrap().is_none();
+
+        let data = PlayerData {
+            gamemode: 1,
+            held_item: 4,
+            inventory: vec![InventorySlot {
+                count: 16,
+                slot: 4,
+                item: "minecraft:torch".to_owned(),
+                nbt: None,
+            }],
+            ..Default::default()
+        };
+        storage.save_player_data(uuid, &data).unwrap();
+        let loaded = storage.load_player_data(uuid).unwrap().unwrap();
+        PlayerOutcome {
+            missing,
+            gamemode: loaded.gamemode,
+            held_item: loaded.held_item,
+            inventory: loaded.inventory,
+        }
+    }
+
+    #[test]
+    fn chunks_round_trip_identically() {
+        let dir = TempDir::new().unwrap();
+        let from_files = chunk_round_trip(FileStorage::new(dir.path(), DEFAULT_COMPRESSION_LEVEL));
+        let from_memory = chunk_round_trip(MemoryStorage::new());
+
+        for outcome in &[&from_files, &from_memory] {
+            assert_eq!(outcome.blocks.iter().filter(|b| !b.is_air()).count(), 2);
+            assert!(outcome.block_entity.starts_with("Some(Chest("));
+            assert!(outcome.entities.contains("minecraft:diamond"));
+        }
+        assert_eq!(from_files, from_memory);
+    }
+
+    #[test]
+    fn player_data_round_trips_identically() {
+        let dir = TempDir::new().unwrap();
+        let from_files = player_round_trip(FileStorage::new(dir.path(), DEFAULT_COMPRESSION_LEVEL));
+        let from_memory = player_round_trip(MemoryStorage::new());
+
+        assert!(from_files.missing);
+        assert_eq!(from_files.inventory.len(), 1);
+        assert_eq!(from_files, from_memory);
+    }
+}
@@ -0,0 +1,120 @@
+/// A kind of enchantment.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EnchantmentKind {
+    Protection,
+    FireProtection,
+    FeatherFalling,
+    BlastProtection,
+    ProjectileProtection,
+    Respiration,
+    AquaAffinity,
+    Thorns,
+    DepthStrider,
+    FrostWalker,
+    BindingCurse,
+    SoulSpeed,
+    Sharpness,
+    Smite,
+    BaneOfArthropods,
+    Knockback,
+    FireAspect,
+    Looting,
+    Sweeping,
+    Efficiency,
+    SilkTouch,
+    Unbreaking,
+    Fortune,
+    Power,
+    Punch,
+    Flame,
+    Infinity,
+    LuckOfTheSea,
+    Lure,
+    Loyalty,
+    Impaling,
+    Riptide,
+    Channeling,
+    Multishot,
+    QuickCharge,
+    Piercing,
+    Mending,
+    VanishingCurse,
+}
+
+const ENCHANTMENTS: &[(EnchantmentKind, &str)] = &[
+    (EnchantmentKind::Protection, "protection"),
+    (EnchantmentKind::FireProtection, "fire_protection"),
+    (EnchantmentKind::FeatherFalling, "feather_falling"),
+    (EnchantmentKind::BlastProtection, "blast_protection"),
+    (
+        EnchantmentKind::ProjectileProtection,
+        "projectile_protection",
+    ),
+    (EnchantmentKind::Respiration, "respiration"),
+    (EnchantmentKind::AquaAffinity, "aqua_affinity"),
+    (EnchantmentKind::Thorns, "thorns"),
+    (EnchantmentKind::DepthStrider, "depth_strider"),
+    (EnchantmentKind::FrostWalker, "frost_walker"),
+    (EnchantmentKind::BindingCurse, "binding_curse"),
+    (EnchantmentKind::SoulSpeed, "soul_speed"),
+    (EnchantmentKind::Sharpness, "sharpness"),
+    (EnchantmentKind::Smite, "smite"),
+    (EnchantmentKind::BaneOfArthropods, "bane_of_arthropods"),
+    (EnchantmentKind::Knockback, "knockback"),
+    (EnchantmentKind::FireAspect, "fire_aspect"),
+    (EnchantmentKind::Looting, "looting"),
+    (EnchantmentKind::Sweeping, "sweeping"),
+    (EnchantmentKind::Efficiency, "efficiency"),
+    (EnchantmentKind::SilkTouch, "silk_touch"),
+    (EnchantmentKind::Unbreaking, "unbreaking"),
+    (EnchantmentKind::Fortune, "fortune"),
+    (EnchantmentKind::Power, "power"),
+    (EnchantmentKind::Punch, "punch"),
+    (EnchantmentKind::Flame, "flame"),
+    (EnchantmentKind::Infinity, "infinity"),
+    (EnchantmentKind::LuckOfTheSea, "luck_of_the_sea"),
+    (EnchantmentKind::Lure, "lure"),
+    (EnchantmentKind::Loyalty, "loyalty"),
+    (EnchantmentKind::Impaling, "impaling"),
+    (EnchantmentKind::Riptide, "riptide"),
+    (EnchantmentKind::Channeling, "channeling"),
+    (EnchantmentKind::Multishot, "multishot"),
+    (EnchantmentKind::QuickCharge, "quick_charge"),
+    (EnchantmentKind::Piercing, "piercing"),
+    (EnchantmentKind::Mending, "mending"),
+    (EnchantmentKind::VanishingCurse, "vanishing_curse"),
+];
+
+impl EnchantmentKind {
+    /// Gets the name of this enchantment, e.g. `efficiency`.
+    pub fn name(self) -> &'static str {
+        ENCHANTMENTS
+            .iter()
+            .find(|(kind, _)| *kind == self)
+            .map(|(_, name)| *name)
+            .unwrap()
+    }
+
+    /// Gets an enchantment by its name, with or
+    /// without the `minecraft:` namespace.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+        ENCHANTMENTS
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(kind, _)| *kind)
+    }
+}
+
+/// An enchantment on an item.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Enchantment {
+    pub kind: EnchantmentKind,
+    pub level: u16,
+}
+
+impl Enchantment {
+    pub fn new(kind: EnchantmentKind, level: u16) -> Self {
+        Self { kind, level }
+    }
+}
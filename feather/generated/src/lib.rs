@@ -5,6 +5,7 @@ use std::sync::Arc;
 mod biome;
 #[allow(clippy::all)]
 mod block;
+mod enchantment;
 #[allow(clippy::all)]
 mod entity;
 #[allow(clippy::all)]
@@ -18,6 +19,7 @@ mod simplified_block;
 
 pub use biome::Biome;
 pub use block::BlockKind;
+pub use enchantment::{Enchantment, EnchantmentKind};
 pub use entity::EntityKind;
 pub use inventory::{Area, InventoryBacking, Window};
 pub use item::Item;
@@ -31,6 +33,9 @@ pub struct ItemStack {
 
     /// Damage to the item, if it's damageable.
     pub damage: Option<u32>,
+
+    /// The enchantments on the item.
+    pub enchantments: Vec<Enchantment>,
 }
 
 impl ItemStack {
@@ -40,6 +45,7 @@ impl ItemStack {
             item,
             count,
             damage: item.durability().map(|_| 0),
+            enchantments: Vec::new(),
         }
     }
 
@@ -47,7 +53,9 @@ impl ItemStack {
     /// the same type as (but not necessarily the same
     /// amount as) `self`.
     pub fn has_same_type(&self, other: &ItemStack) -> bool {
-        other.item == self.item && other.damage == self.damage
+        other.item == self.item
+            && other.damage == self.damage
+            && other.enchantments == self.enchantments
     }
 
     /// Returns the item type for this `ItemStack`.
@@ -55,6 +63,20 @@ impl ItemStack {
         self.item
     }
 
+    /// Returns the enchantments on this `ItemStack`.
+    pub fn enchantments(&self) -> &[Enchantment] {
+        &self.enchantments
+    }
+
+    /// Returns the level of the given enchantment,
+    /// or 0 if the item doesn't have it.
+    pub fn enchantment_level(&self, kind: EnchantmentKind) -> u16 {
+        self.enchantments
+            .iter()
+            .find(|enchantment| enchantment.kind == kind)
+            .map_or(0, |enchantment| enchantment.level)
+    }
+
     /// Returns the number of items in this `ItemStack`.
     pub fn count(&self) -> u32 {
         self.count
@@ -193,6 +215,17 @@ impl Inventory {
         vec
     }
 
+    /// Removes all the items in this inventory and returns them.
+    pub fn take_all(&self) -> Vec<ItemStack> {
+        let mut items = Vec::new();
+        for area in self.backing.areas() {
+            for item in self.backing.area_slice(*area).unwrap() {
+                items.extend(item.lock().take());
+            }
+        }
+        items
+    }
+
     /// Creates a new handle to the same inventory.
     ///
     /// This operation is the same as calling `clone()`, but it's more explicit
@@ -25,6 +25,8 @@ pub struct MinecraftCodec {
     crypt_key: Option<CryptKey>,
     /// If compression is enabled, then this is the compression threshold.
     compression: Option<CompressionThreshold>,
+    /// The zlib compression level of compressed packets.
+    compression_level: Compression,
 
     /// A buffer of received bytes.
     received_buf: BytesMut,
@@ -51,6 +53,12 @@ impl MinecraftCodec {
         self.compression = Some(threshold);
     }
 
+    /// Sets the zlib compression level, from 0 to 9,
+    /// of compressed packets.
+    pub fn set_compression_level(&mut self, level: u32) {
+        self.compression_level = Compression::new(level);
+    }
+
     /// Gets another `MinecraftCodec` with the same compression and encryption
     /// parameters.
     pub fn clone_with_settings(&self) -> MinecraftCodec {
@@ -60,6 +68,7 @@ impl MinecraftCodec {
                 .map(|key| AesCfb8::new_from_slices(&key, &key).expect("key size is invalid")),
             crypt_key: self.crypt_key,
             compression: self.compression,
+            compression_level: self.compression_level,
             received_buf: BytesMut::new(),
             staging_buf: Vec::new(),
             compression_target: Vec::new(),
@@ -114,7 +123,7 @@ impl MinecraftCodec {
     }
 
     fn data_compressed(&mut self) -> (usize, &[u8]) {
-        let mut encoder = ZlibEncoder::new(self.staging_buf.as_slice(), Compression::default());
+        let mut encoder = ZlibEncoder::new(self.staging_buf.as_slice(), self.compression_level);
         encoder
             .read_to_end(&mut self.compression_target)
             .expect("compression failed");
@@ -189,3 +198,48 @@ impl MinecraftCodec {
         Ok(packet)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::packets::server::PluginMessage;
+
+    use super::*;
+
+    fn codec(level: u32) -> MinecraftCodec {
+        let mut codec = MinecraftCodec::new();
+        codec.enable_compression(64);
+        codec.set_compression_level(level);
+        codec
+    }
+
+    #[test]
+    fn compression_levels() {
+        // Random words compress much better with a slower level
+        let words = ["block", "chunk", "entity", "feather", "player", "world"];
+        let mut seed: u32 = 1;
+        let text: Vec<&str> = (0..4000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                words[(seed >> 16) as usize % words.len()]
+            })
+            .collect();
+        let packet = PluginMessage {
+            channel: "feather:test".to_owned(),
+            data: text.join(" ").into_bytes(),
+        };
+
+        let mut fast = Vec::new();
+        codec(1).encode(&packet, &mut fast).unwrap();
+        let mut best = Vec::new();
+        codec(9).encode(&packet, &mut best).unwrap();
+        assert!(best.len() < fast.len());
+
+        for bytes in [fast, best].iter() {
+            let mut codec = codec(6);
+            codec.accept(bytes);
+            let decoded: PluginMessage = codec.next_packet().unwrap().unwrap();
+            assert_eq!(decoded.channel, packet.channel);
+            assert_eq!(decoded.data, packet.data);
+        }
+    }
+}
@@ -101,7 +101,9 @@ packets! {
 
 def_enum! {
     InteractEntityKind (VarInt) {
-        0 = Interact,
+        0 = Interact {
+            hand VarInt;
+        },
         1 = Attack,
         2 = InteractAt {
             target_x f64;
@@ -13,7 +13,7 @@ pub use update_light::UpdateLight;
 packets! {
     SpawnEntity {
         entity_id VarInt;
-        uuid String;
+        uuid Uuid;
         kind VarInt;
         x f64;
         y f64;
@@ -858,7 +858,7 @@ def_enum! {
         1 = LerpSize {
             old_diameter f64;
             new_diameter f64;
-            speed u64;
+            speed VarLong;
         },
         2 = SetCenter {
             x f64;
@@ -869,7 +869,7 @@ def_enum! {
             z f64;
             old_diameter f64;
             new_diameter f64;
-            speed u64;
+            speed VarLong;
             portal_teeport_boundary VarInt;
             warning_time VarInt;
             warning_blocks VarInt;
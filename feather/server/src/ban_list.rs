@@ -0,0 +1,269 @@
+//! Lists of banned players and IP addresses.
+//!
+//! The lists are persisted in the vanilla format,
+//! `banned-players.json` and `banned-ips.json`.
+
+use std::{
+    fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::DisconnectReason;
+
+const BANNED_PLAYERS_FILE: &str = "banned-players.json";
+const BANNED_IPS_FILE: &str = "banned-ips.json";
+
+/// The reason used when a ban doesn't specify one.
+pub const DEFAULT_BAN_REASON: &str = "Banned by an operator.";
+
+/// A banned player.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerBan {
+    /// `None` if the player was banned by name
+    /// while offline, so their UUID wasn't known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<Uuid>,
+    pub name: String,
+    pub created: String,
+    pub source: String,
+    pub expires: String,
+    pub reason: String,
+}
+
+/// A banned IP address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpBan {
+    pub ip: IpAddr,
+    pub created: String,
+    pub source: String,
+    pub expires: String,
+    pub reason: String,
+}
+
+/// Stores banned players and IP addresses.
+#[derive(Debug, Default)]
+pub struct BanList {
+    players: Vec<PlayerBan>,
+    ips: Vec<IpBan>,
+    /// The directory the lists are saved to.
+    /// If `None`, the lists are only kept in memory.
+    directory: Option<PathBuf>,
+}
+
+impl BanList {
+    /// Creates an empty ban list which is not persisted.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the ban lists from the given directory.
+    /// Missing files are treated as empty lists.
+    pub fn load(directory: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let directory = directory.into();
+        Ok(Self {
+            players: load_list(&directory.join(BANNED_PLAYERS_FILE))?,
+            ips: load_list(&directory.join(BANNED_IPS_FILE))?,
+            directory: Some(directory),
+        })
+    }
+
+    /// Writes the ban lists to disk. Does nothing
+    /// if the list was created with [`BanList::new`].
+    pub fn save(&self) -> anyhow::Result<()> {
+        if let Some(directory) = &self.directory {
+            save_list(&directory.join(BANNED_PLAYERS_FILE), &self.players)?;
+            save_list(&directory.join(BANNED_IPS_FILE), &self.ips)?;
+        }
+        Ok(())
+    }
+
+    pub fn players(&self) -> &[PlayerBan] {
+        &self.players
+    }
+
+    pub fn ips(&self) -> &[IpBan] {
+        &self.ips
+    }
+
+    /// Gets the ban for the player with the given UUID and name, if any.
+    ///
+    /// Bans without a UUID match the name case-insensitively.
+    pub fn player_ban(&self, uuid: Uuid, name: &str) -> Option<&PlayerBan> {
+        self.players.iter().find(|ban| match ban.uuid {
+            Some(banned_uuid) => banned_uuid == uuid,
+            None => ban.name.eq_ignore_ascii_case(name),
+        })
+    }
+
+    /// Gets the ban for the given IP address, if any.
+    pub fn ip_ban(&self, ip: IpAddr) -> Option<&IpBan> {
+        self.ips.iter().find(|ban| ban.ip == ip)
+    }
+
+    /// Returns the reason a player with the given UUID, name and
+    /// IP address is banned, or `None` if they aren't banned.
+    pub fn ban_reason(&self, uuid: Uuid, name: &str, ip: IpAddr) -> Option<&str> {
+        self.player_ban(uuid, name)
+            .map(|ban| ban.reason.as_str())
+            .or_else(|| self.ip_ban(ip).map(|ban| ban.reason.as_str()))
+    }
+
+    /// Returns the message shown to a player with the given UUID, name
+    /// and IP address, or `None` if they aren't banned.
+    pub fn disconnect_reason(
+        &self,
+        uuid: Uuid,
+        name: &str,
+        ip: IpAddr,
+    ) -> Option<DisconnectReason> {
+        let (reason, expires) = self
+            .player_ban(uuid, name)
+            .map(|ban| (&ban.reason, &ban.expires))
+            .or_else(|| self.ip_ban(ip).map(|ban| (&ban.reason, &ban.expires)))?;
+        Some(DisconnectReason::banned(reason, expires))
+    }
+
+    /// Bans a player, replacing any existing ban for them.
+    pub fn ban_player(&mut self, uuid: Uuid, name: String, source: String, reason: String) {
+        self.players.retain(|ban| ban.uuid != Some(uuid));
+        self.players.push(PlayerBan {
+            uuid: Some(uuid),
+            name,
+            created: now(),
+            source,
+            expires: "forever".to_owned(),
+            reason,
+        });
+    }
+
+    /// Bans a player by name, for players who are offline
+    /// and whose UUID isn't known. Replaces any existing
+    /// ban for the name.
+    pub fn ban_name(&mut self, name: String, source: String, reason: String) {
+        self.players
+            .retain(|ban| !ban.name.eq_ignore_ascii_case(&name));
+        self.players.push(PlayerBan {
+            uuid: None,
+            name,
+            created: now(),
+            source,
+            expires: "forever".to_owned(),
+            reason,
+        });
+    }
+
+    /// Removes the ban for the player with the given name.
+    /// Names are compared case-insensitively.
+    ///
+    /// Returns the removed ban, or `None` if the player wasn't banned.
+    pub fn pardon_player(&mut self, name: &str) -> Option<PlayerBan> {
+        let index = self
+            .players
+            .iter()
+            .position(|ban| ban.name.eq_ignore_ascii_case(name))?;
+        Some(self.players.remove(index))
+    }
+
+    /// Bans an IP address, replacing any existing ban for it.
+    pub fn ban_ip(&mut self, ip: IpAddr, source: String, reason: String) {
+        self.ips.retain(|ban| ban.ip != ip);
+        self.ips.push(IpBan {
+            ip,
+            created: now(),
+            source,
+            expires: "forever".to_owned(),
+            reason,
+        });
+    }
+
+    /// Removes the ban for the given IP address.
+    ///
+    /// Returns the removed ban, or `None` if the address wasn't banned.
+    pub fn pardon_ip(&mut self, ip: IpAddr) -> Option<IpBan> {
+        let index = self.ips.iter().position(|ban| ban.ip == ip)?;
+        Some(self.ips.remove(index))
+    }
+}
+
+fn now() -> String {
+    chrono::Local::now()
+        .format("%Y-%m-%d %H:%M:%S %z")
+        .to_string()
+}
+
+fn load_list<T: DeserializeOwned>(path: &Path) -> anyhow::Result<Vec<T>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents = fs::read_to_string(path)?;
+    serde_json::from_str(&contents).with_context(|| format!("malformed {}", path.display()))
+}
+
+fn save_list<T: Serialize>(path: &Path, list: &[T]) -> anyhow::Result<()> {
+    let contents = serde_json::to_string_pretty(list)?;
+    fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ban_and_pardon_player() {
+        let mut bans = BanList::new();
+        let uuid = Uuid::from_u128(1);
+        bans.ban_player(
+            uuid,
+            "Steve".to_owned(),
+            "Server".to_owned(),
+            "griefing".to_owned(),
+        );
+
+        let ip = IpAddr::from([127, 0, 0, 1]);
+        assert_eq!(bans.ban_reason(uuid, "Steve", ip), Some("griefing"));
+        assert_eq!(bans.ban_reason(Uuid::from_u128(2), "Steve", ip), None);
+
+        assert!(bans.pardon_player("steve").is_some());
+        assert_eq!(bans.ban_reason(uuid, "Steve", ip), None);
+        assert!(bans.pardon_player("steve").is_none());
+    }
+
+    #[test]
+    fn ban_name() {
+        let mut bans = BanList::new();
+        bans.ban_name(
+            "Steve".to_owned(),
+            "Server".to_owned(),
+            "griefing".to_owned(),
+        );
+
+        let ip = IpAddr::from([127, 0, 0, 1]);
+        assert_eq!(
+            bans.ban_reason(Uuid::from_u128(1), "steve", ip),
+            Some("griefing")
+        );
+        assert_eq!(bans.ban_reason(Uuid::from_u128(1), "Alex", ip), None);
+
+        let json = serde_json::to_string(&bans.players()[0]).unwrap();
+        assert!(!json.contains("uuid"));
+    }
+
+    #[test]
+    fn ban_ip() {
+        let mut bans = BanList::new();
+        let ip = IpAddr::from([10, 0, 0, 5]);
+        bans.ban_ip(ip, "Server".to_owned(), DEFAULT_BAN_REASON.to_owned());
+
+        assert_eq!(
+            bans.ban_reason(Uuid::from_u128(1), "Steve", ip),
+            Some(DEFAULT_BAN_REASON)
+        );
+        assert!(bans.pardon_ip(ip).is_some());
+        assert!(bans.ip_ban(ip).is_none());
+    }
+}
@@ -24,6 +24,12 @@ impl ChunkSubscriptions {
             .map(Vec::as_slice)
             .unwrap_or_default()
     }
+
+    /// Subscribes a client to a chunk without a view update.
+    #[cfg(test)]
+    pub fn subscribe(&mut self, chunk: ChunkPosition, client_id: ClientId) {
+        self.chunks.entry(chunk).or_default().push(client_id);
+    }
 }
 
 pub fn register(systems: &mut SystemExecutor<Game>) {
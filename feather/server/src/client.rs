@@ -2,41 +2,61 @@ use std::{
     cell::{Cell, RefCell},
     collections::VecDeque,
     io::Cursor,
+    net::IpAddr,
     sync::Arc,
 };
 
 use ahash::AHashSet;
+use anyhow::bail;
 use base::{
-    BlockId, BlockPosition, ChunkHandle, ChunkPosition, EntityKind, EntityMetadata, Gamemode,
-    ItemStack, Position, ProfileProperty, Text,
+    anvil::block_entity::{BlockEntityData, BlockEntityVariant},
+    BlockId, BlockPosition, ChunkHandle, ChunkPosition, Difficulty, EntityKind, EntityMetadata,
+    GameRules, Gamemode, ItemStack, Position, ProfileProperty, Text, Vec3d,
 };
 use common::{
     chat::{ChatKind, ChatMessage},
-    Window,
+    effects::{StatusEffect, StatusEffectInstance},
+    window::BackingWindow,
+    Window, WorldBorder,
 };
 use flume::{Receiver, Sender};
-use packets::server::{Particle, SetSlot, SpawnLivingEntity, UpdateLight, WindowConfirmation};
+use packets::server::{
+    MultiBlockChange, OpenWindow, Particle, SetSlot, SpawnEntity, SpawnLivingEntity, UpdateLight,
+    WindowConfirmation,
+};
 use protocol::{
     packets::{
         self,
         server::{
-            AddPlayer, Animation, BlockChange, ChatPosition, ChunkData, ChunkDataKind,
-            DestroyEntities, Disconnect, EntityAnimation, EntityHeadLook, EntityTeleport, JoinGame,
-            KeepAlive, PlayerInfo, PlayerPositionAndLook, PluginMessage, SendEntityMetadata,
-            SpawnPlayer, Title, UnloadChunk, UpdateViewPosition, WindowItems,
+            AddPlayer, Animation, BlockChange, BlockEntityData as BlockEntityDataPacket,
+            ChangeGameState, ChatPosition, ChunkData, ChunkDataKind, DestroyEntities,
+            EntityAnimation, EntityEffect, EntityEquipment, EntityHeadLook, EntityStatus,
+            EntityTeleport, EntityVelocity, EquipmentEntry, JoinGame, KeepAlive, PlayerInfo,
+            PlayerPositionAndLook, PluginMessage, RemoveEntityEffect, SendEntityMetadata,
+            ServerDifficulty, SpawnPlayer, SpawnPosition, Title, UnloadChunk, UpdateViewPosition,
+            WindowItems, WorldBorder as WorldBorderPacket,
         },
     },
-    ClientPlayPacket, Nbt, ProtocolVersion, ServerPlayPacket, Writeable,
+    ClientPlayPacket, Nbt, ProtocolVersion, ServerPlayPacket, VarLong, Writeable,
 };
 use quill_common::components::OnGround;
 use uuid::Uuid;
 use vec_arena::Arena;
 
-use crate::{initial_handler::NewPlayer, network_id_registry::NetworkId, Options};
+use crate::{
+    initial_handler::NewPlayer, network_id_registry::NetworkId, DisconnectReason, Options,
+};
 
 /// Max number of chunks to send to a client per tick.
 const MAX_CHUNKS_PER_TICK: usize = 10;
 
+/// Chunks aren't sent to a client while more than this many
+/// packets are waiting to be written to its connection.
+const MAX_QUEUED_PACKETS: usize = 128;
+
+/// The ID of the player's own inventory window.
+const PLAYER_WINDOW_ID: u8 = 0;
+
 /// ID of a client. Can be reused.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ClientId(usize);
@@ -80,6 +100,7 @@ pub struct Client {
     username: String,
     profile: Vec<ProfileProperty>,
     uuid: Uuid,
+    ip: IpAddr,
 
     teleport_id_counter: Cell<i32>,
 
@@ -95,6 +116,10 @@ pub struct Client {
     /// Used to detect when we need to teleport the client.
     client_known_position: Cell<Option<Position>>,
 
+    /// The ID of the window the client has open.
+    open_window_id: Cell<u8>,
+    window_id_counter: Cell<u8>,
+
     disconnected: Cell<bool>,
 }
 
@@ -109,11 +134,14 @@ impl Client {
             network_id,
             profile: player.profile,
             uuid: player.uuid,
+            ip: player.ip,
             sent_entities: RefCell::new(AHashSet::new()),
             knows_position: Cell::new(false),
             known_chunks: RefCell::new(AHashSet::new()),
             chunk_send_queue: RefCell::new(VecDeque::new()),
             client_known_position: Cell::new(None),
+            open_window_id: Cell::new(PLAYER_WINDOW_ID),
+            window_id_counter: Cell::new(PLAYER_WINDOW_ID),
             disconnected: Cell::new(false),
         }
     }
@@ -142,6 +170,10 @@ impl Client {
         &self.username
     }
 
+    pub fn ip(&self) -> IpAddr {
+        self.ip
+    }
+
     pub fn received_packets(&self) -> impl Iterator<Item = ClientPlayPacket> + '_ {
         self.received_packets.try_iter()
     }
@@ -158,9 +190,22 @@ impl Client {
         self.knows_position.get()
     }
 
+    /// Returns the number of chunks waiting to be sent.
+    pub fn queued_chunks(&self) -> usize {
+        self.chunk_send_queue.borrow().len()
+    }
+
     pub fn tick(&self) {
-        let num_to_send = MAX_CHUNKS_PER_TICK.min(self.chunk_send_queue.borrow().len());
-        for packet in self.chunk_send_queue.borrow_mut().drain(0..num_to_send) {
+        for _ in 0..MAX_CHUNKS_PER_TICK {
+            // Let slow connections catch up before
+            // queueing more chunks for them.
+            if self.packets_to_send.len() >= MAX_QUEUED_PACKETS {
+                break;
+            }
+            let packet = match self.chunk_send_queue.borrow_mut().pop_front() {
+                Some(packet) => packet,
+                None => break,
+            };
             log::trace!(
                 "Sending chunk at {:?} to {}",
                 packet.chunk.read().position(),
@@ -178,7 +223,7 @@ impl Client {
         self.sent_entities.borrow().contains(&network_id)
     }
 
-    pub fn send_join_game(&self, gamemode: Gamemode) {
+    pub fn send_join_game(&self, gamemode: Gamemode, rules: &GameRules) {
         log::trace!("Sending Join Game to {}", self.username);
         // Use the dimension codec sent by the default vanilla server. (Data acquired via tools/proxy)
         let dimension_codec = nbt::Blob::from_reader(&mut Cursor::new(include_bytes!(
@@ -202,13 +247,89 @@ impl Client {
             hashed_seed: 0,
             max_players: 0,
             view_distance: self.options.view_distance as i32,
-            reduced_debug_info: false,
-            enable_respawn_screen: true,
+            reduced_debug_info: rules.reduced_debug_info,
+            enable_respawn_screen: !rules.do_immediate_respawn,
             is_debug: false,
             is_flat: false,
         });
     }
 
+    /// Tells the client whether to hide coordinates
+    /// and other details on the debug screen.
+    pub fn send_reduced_debug_info(&self, reduced: bool) {
+        self.send_packet(EntityStatus {
+            entity_id: self.network_id.0,
+            status: if reduced { 22 } else { 23 },
+        });
+    }
+
+    /// Tells the client whether to skip the death screen.
+    pub fn send_immediate_respawn(&self, immediate: bool) {
+        self.send_packet(ChangeGameState {
+            reason: 11,
+            value: if immediate { 1. } else { 0. },
+        });
+    }
+
+    /// Sets the difficulty shown to the client.
+    pub fn send_difficulty(&self, difficulty: Difficulty) {
+        self.send_packet(ServerDifficulty {
+            difficulty: difficulty as u8,
+            locked: false,
+        });
+    }
+
+    /// Sets the point compasses on the client point to.
+    pub fn send_spawn_position(&self, position: BlockPosition) {
+        self.send_packet(SpawnPosition { position });
+    }
+
+    /// Sends the whole world border.
+    pub fn send_world_border(&self, border: &WorldBorder) {
+        self.send_packet(WorldBorderPacket::Initialize {
+            x: border.center_x,
+            z: border.center_z,
+            old_diameter: border.diameter(),
+            new_diameter: border.target_diameter(),
+            speed: VarLong((border.remaining_ticks() * 50) as i64),
+            portal_teeport_boundary: 29_999_984,
+            warning_time: border.warning_time as i32,
+            warning_blocks: border.warning_blocks as i32,
+        });
+    }
+
+    /// Updates the width of the world border,
+    /// which may be moving towards a new width.
+    pub fn send_world_border_size(&self, border: &WorldBorder) {
+        if border.remaining_ticks() == 0 {
+            self.send_packet(WorldBorderPacket::SetSize {
+                diameter: border.diameter(),
+            });
+        } else {
+            self.send_packet(WorldBorderPacket::LerpSize {
+                old_diameter: border.diameter(),
+                new_diameter: border.target_diameter(),
+                speed: VarLong((border.remaining_ticks() * 50) as i64),
+            });
+        }
+    }
+
+    pub fn send_world_border_center(&self, border: &WorldBorder) {
+        self.send_packet(WorldBorderPacket::SetCenter {
+            x: border.center_x,
+            z: border.center_z,
+        });
+    }
+
+    pub fn send_world_border_warnings(&self, border: &WorldBorder) {
+        self.send_packet(WorldBorderPacket::SetWarningTime {
+            warning_time: border.warning_time as i32,
+        });
+        self.send_packet(WorldBorderPacket::SetWarningBlocks {
+            warning_blocks: border.warning_blocks as i32,
+        });
+    }
+
     pub fn send_brand(&self) {
         let mut data = Vec::new();
         "Feather"
@@ -280,6 +401,57 @@ impl Client {
         });
     }
 
+    /// Sends changes to several blocks within the chunk section
+    /// `section` of `chunk`.
+    pub fn send_multi_block_change(
+        &self,
+        chunk: ChunkPosition,
+        section: usize,
+        blocks: &[(BlockPosition, BlockId)],
+    ) {
+        let chunk_section_coordinate = ((chunk.x as u64 & 0x3F_FFFF) << 42)
+            | ((chunk.z as u64 & 0x3F_FFFF) << 20)
+            | (section as u64 & 0xF_FFFF);
+        let records: Vec<VarLong> = blocks
+            .iter()
+            .map(|&(pos, block)| {
+                let local = ((pos.x & 15) << 8) | ((pos.z & 15) << 4) | (pos.y & 15);
+                VarLong((i64::from(block.vanilla_id()) << 12) | i64::from(local))
+            })
+            .collect();
+        self.send_packet(MultiBlockChange {
+            chunk_section_coordinate,
+            dont_trust_edges: true,
+            records: records.into(),
+        });
+    }
+
+    /// Sends the data of a block entity. Does nothing for
+    /// block entities the client doesn't need to know about.
+    pub fn send_block_entity_data(&self, data: &BlockEntityData) {
+        let action = match block_entity_action(data.kind.variant()) {
+            Some(action) => action,
+            None => return,
+        };
+
+        let mut bytes = Vec::new();
+        let blob = nbt::to_writer(&mut bytes, data, None)
+            .and_then(|()| nbt::Blob::from_reader(&mut Cursor::new(&bytes)));
+        let blob = match blob {
+            Ok(blob) => blob,
+            Err(e) => {
+                log::error!("Failed to serialize block entity: {:?}", e);
+                return;
+            }
+        };
+
+        self.send_packet(BlockEntityDataPacket {
+            position: BlockPosition::new(data.base.x, data.base.y, data.base.z),
+            action,
+            data: Nbt(blob),
+        });
+    }
+
     pub fn unload_chunk(&self, pos: ChunkPosition) {
         log::trace!("Unloading chunk at {:?} on {}", pos, self.username);
         self.send_packet(UnloadChunk {
@@ -365,6 +537,62 @@ impl Client {
         });
     }
 
+    pub fn send_item_entity(
+        &self,
+        network_id: NetworkId,
+        uuid: Uuid,
+        pos: Position,
+        item: &ItemStack,
+    ) {
+        log::trace!("Spawning an item entity on {}", self.username);
+        self.send_packet(SpawnEntity {
+            entity_id: network_id.0,
+            uuid,
+            kind: EntityKind::Item.id() as i32,
+            x: pos.x,
+            y: pos.y,
+            z: pos.z,
+            pitch: pos.pitch,
+            yaw: pos.yaw,
+            data: 1,
+            velocity_x: 0,
+            velocity_y: 0,
+            velocity_z: 0,
+        });
+        // Index 7 holds the item stack of an item entity
+        self.send_packet(SendEntityMetadata {
+            entity_id: network_id.0,
+            entries: EntityMetadata::entity_base().with(7, Some(item.clone())),
+        });
+    }
+
+    pub fn send_projectile(
+        &self,
+        network_id: NetworkId,
+        uuid: Uuid,
+        pos: Position,
+        kind: EntityKind,
+        velocity: Vec3d,
+    ) {
+        log::trace!("Spawning a {:?} projectile on {}", kind, self.username);
+        // Velocity is sent in units of 1/8000 block per tick
+        let velocity = velocity.map(|v| (v * 8000.).clamp(i16::MIN as f64, i16::MAX as f64) as i16);
+        self.send_packet(SpawnEntity {
+            entity_id: network_id.0,
+            uuid,
+            kind: kind.id() as i32,
+            x: pos.x,
+            y: pos.y,
+            z: pos.z,
+            pitch: pos.pitch,
+            yaw: pos.yaw,
+            data: 0,
+            velocity_x: velocity.x,
+            velocity_y: velocity.y,
+            velocity_z: velocity.z,
+        });
+    }
+
     pub fn update_entity_position(
         &self,
         network_id: NetworkId,
@@ -399,6 +627,49 @@ impl Client {
         });
     }
 
+    pub fn send_entity_metadata(&self, network_id: NetworkId, metadata: EntityMetadata) {
+        self.send_packet(SendEntityMetadata {
+            entity_id: network_id.0,
+            entries: metadata,
+        });
+    }
+
+    pub fn send_entity_equipment(&self, network_id: NetworkId, entries: Vec<EquipmentEntry>) {
+        self.send_packet(EntityEquipment {
+            entity_id: network_id.0,
+            entries,
+        });
+    }
+
+    pub fn send_entity_velocity(&self, network_id: NetworkId, velocity: Vec3d) {
+        // Velocity is sent in units of 1/8000 block per tick
+        let velocity = velocity.map(|v| (v * 8000.).clamp(i16::MIN as f64, i16::MAX as f64) as i16);
+        self.send_packet(EntityVelocity {
+            entity_id: network_id.0,
+            velocity_x: velocity.x,
+            velocity_y: velocity.y,
+            velocity_z: velocity.z,
+        });
+    }
+
+    pub fn send_entity_effect(&self, network_id: NetworkId, instance: &StatusEffectInstance) {
+        self.send_packet(EntityEffect {
+            entity_id: network_id.0,
+            effect_id: instance.effect.id(),
+            amplifier: instance.amplifier as i8,
+            duration: instance.duration.min(i32::MAX as u32) as i32,
+            // Show particles and the icon
+            flags: 0x02 | 0x04,
+        });
+    }
+
+    pub fn send_remove_entity_effect(&self, network_id: NetworkId, effect: StatusEffect) {
+        self.send_packet(RemoveEntityEffect {
+            entity_id: network_id.0,
+            effect_id: effect.id(),
+        });
+    }
+
     pub fn send_keepalive(&self) {
         log::trace!("Sending keepalive to {}", self.username);
         self.send_packet(KeepAlive { id: 0 });
@@ -473,10 +744,53 @@ impl Client {
         });
     }
 
+    /// Opens a window on the client, replacing the window it has open,
+    /// and returns the window's ID.
+    ///
+    /// The player's `Window` component needs to be replaced with
+    /// `window` for clicks to be applied to it; use
+    /// [`Server::open_window`](crate::Server::open_window) to do both.
+    pub(crate) fn open_window(
+        &self,
+        window: &Window,
+        title: impl Into<Text>,
+    ) -> anyhow::Result<u8> {
+        let window_kind = match window_kind(window.inner()) {
+            Some(kind) => kind,
+            None => bail!("window cannot be opened by the server"),
+        };
+
+        // Like vanilla, cycle through IDs 1 to 100
+        let window_id = self.window_id_counter.get() % 100 + 1;
+        self.window_id_counter.set(window_id);
+        self.open_window_id.set(window_id);
+
+        log::trace!("Opening window {} for {}", window_id, self.username);
+        self.send_packet(OpenWindow {
+            window_id: window_id.into(),
+            window_kind,
+            window_title: title.into().to_string(),
+        });
+        self.send_window_items(window);
+        Ok(window_id)
+    }
+
+    /// Returns the ID of the window the client has open.
+    /// This is 0 if the client only has its own inventory.
+    pub fn open_window_id(&self) -> u8 {
+        self.open_window_id.get()
+    }
+
+    /// Marks the open window as closed, so that the
+    /// client is back to its own inventory.
+    pub fn close_window(&self) {
+        self.open_window_id.set(PLAYER_WINDOW_ID);
+    }
+
     pub fn send_window_items(&self, window: &Window) {
         log::trace!("Updating window for {}", self.username);
         let packet = WindowItems {
-            window_id: 0,
+            window_id: self.open_window_id(),
             items: window.inner().to_vec(),
         };
         self.send_packet(packet);
@@ -485,7 +799,7 @@ impl Client {
     pub fn set_slot(&self, slot: i16, item: Option<ItemStack>) {
         log::trace!("Setting slot {} of {} to {:?}", slot, self.username, item);
         self.send_packet(SetSlot {
-            window_id: 0,
+            window_id: self.open_window_id(),
             slot,
             slot_data: item,
         });
@@ -528,11 +842,9 @@ impl Client {
         let _ = self.packets_to_send.try_send(packet.into());
     }
 
-    pub fn disconnect(&self, reason: &str) {
+    pub fn disconnect(&self, reason: DisconnectReason) {
         self.disconnected.set(true);
-        self.send_packet(Disconnect {
-            reason: Text::from(reason.to_owned()).to_string(),
-        });
+        self.send_packet(reason.play_packet());
     }
 }
 
@@ -547,3 +859,95 @@ fn chat_packet(message: ChatMessage) -> packets::server::ChatMessage {
         sender: Uuid::default(),
     }
 }
+
+/// Gets the action ID of the block entity data packet
+/// for a block entity type, or `None` if the client
+/// doesn't use its data.
+fn block_entity_action(variant: BlockEntityVariant) -> Option<u8> {
+    let action = match variant {
+        BlockEntityVariant::CommandBlock => 2,
+        BlockEntityVariant::Beacon => 3,
+        BlockEntityVariant::EndGateway => 8,
+        BlockEntityVariant::Sign => 9,
+        BlockEntityVariant::Bed => 11,
+        BlockEntityVariant::Jigsaw => 12,
+        _ => return None,
+    };
+    Some(action)
+}
+
+/// Gets the protocol ID of a window's type, or `None`
+/// if the window can't be opened by the server.
+fn window_kind(window: &BackingWindow) -> Option<i32> {
+    let kind = match window {
+        BackingWindow::Player { .. } => return None,
+        BackingWindow::Generic9x1 { .. } => 0,
+        BackingWindow::Generic9x2 { .. } => 1,
+        BackingWindow::Generic9x3 { .. } => 2,
+        BackingWindow::Generic9x4 { .. } => 3,
+        BackingWindow::Generic9x5 { .. } => 4,
+        BackingWindow::Generic9x6 { .. } => 5,
+        BackingWindow::Generic3x3 { .. } => 6,
+        BackingWindow::Anvil { .. } => 7,
+        BackingWindow::Beacon { .. } => 8,
+        BackingWindow::BlastFurnace { .. } => 9,
+        BackingWindow::BrewingStand { .. } => 10,
+        BackingWindow::Crafting { .. } => 11,
+        BackingWindow::Enchantment { .. } => 12,
+        BackingWindow::Furnace { .. } => 13,
+        BackingWindow::Grindstone { .. } => 14,
+        BackingWindow::Hopper { .. } => 15,
+        BackingWindow::Lectern { .. } => 16,
+        BackingWindow::Loom { .. } => 17,
+        BackingWindow::ShulkerBox { .. } => 19,
+        BackingWindow::Smoker { .. } => 21,
+        BackingWindow::Cartography { .. } => 22,
+        BackingWindow::Stonecutter { .. } => 23,
+    };
+    Some(kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use base::{Chunk, ChunkLock};
+
+    use crate::Server;
+
+    use super::*;
+
+    fn queue_chunks(client: &Client, count: i32) {
+        for x in 0..count {
+            let chunk = Chunk::new(ChunkPosition::new(x, 0));
+            client.send_chunk(&Arc::new(ChunkLock::new(chunk, true)));
+        }
+    }
+
+    #[test]
+    fn slow_clients_throttle_chunk_sends() {
+        let mut server = Server::for_testing();
+        let slow = server.connect_test_client("Slow", Ipv4Addr::LOCALHOST.into());
+        let fast = server.connect_test_client("Fast", Ipv4Addr::LOCALHOST.into());
+        queue_chunks(server.clients.get(slow.id).unwrap(), 200);
+        queue_chunks(server.clients.get(fast.id).unwrap(), 200);
+
+        // The slow client never reads its packets,
+        // while the fast one keeps up.
+        for _ in 0..20 {
+            for client in server.clients.iter() {
+                client.tick();
+            }
+            fast.sent_packets.drain();
+        }
+        let slow_client = server.clients.get(slow.id).unwrap();
+        assert_eq!(slow.sent_packets.len(), MAX_QUEUED_PACKETS);
+        assert_eq!(slow_client.queued_chunks(), 200 - MAX_QUEUED_PACKETS / 2);
+        assert_eq!(server.clients.get(fast.id).unwrap().queued_chunks(), 0);
+
+        // Sending resumes once the slow client catches up
+        slow.sent_packets.drain();
+        slow_client.tick();
+        assert_eq!(slow.sent_packets.len(), 2 * MAX_CHUNKS_PER_TICK);
+    }
+}
@@ -0,0 +1,293 @@
+//! Commands executed by players and the console.
+//!
+//! A command is a function taking a [`CommandContext`] and the
+//! whitespace-separated arguments following the command name.
+
+use std::fmt;
+
+use base::{BlockPosition, Position, Text};
+use common::{
+    chat::{ChatKind, ChatMessage},
+    Game,
+};
+use ecs::{Entity, SysResult};
+use quill_common::components::Name;
+
+use crate::{ClientId, Server};
+
+mod arguments;
+mod ban;
+mod clear;
+mod debug;
+mod effect;
+mod fill;
+mod gamerule;
+mod say;
+mod setworldspawn;
+mod worldborder;
+
+type CommandFn = fn(&mut CommandContext, &[&str]) -> Result<(), CommandError>;
+
+struct Command {
+    name: &'static str,
+    usage: &'static str,
+    /// Whether only operators may run this command.
+    requires_op: bool,
+    /// Whether command blocks may run this command.
+    command_blocks: bool,
+    run: CommandFn,
+}
+
+const COMMANDS: &[Command] = &[
+    Command {
+        name: "ban",
+        usage: "/ban <player> [reason]",
+        requires_op: true,
+        command_blocks: false,
+        run: ban::ban,
+    },
+    Command {
+        name: "ban-ip",
+        usage: "/ban-ip <ip|player> [reason]",
+        requires_op: true,
+        command_blocks: false,
+        run: ban::ban_ip,
+    },
+    Command {
+        name: "clear",
+        usage: "/clear [player] [item] [count]",
+        requires_op: true,
+        command_blocks: true,
+        run: clear::clear,
+    },
+    Command {
+        name: "debug",
+        usage: "/debug dump",
+        requires_op: true,
+        command_blocks: false,
+        run: debug::debug,
+    },
+    Command {
+        name: "effect",
+        usage:
+            "/effect give <player> <effect> [seconds] [amplifier] | /effect clear <player> [effect]",
+        requires_op: true,
+        command_blocks: true,
+        run: effect::effect,
+    },
+    Command {
+        name: "fill",
+        usage: "/fill <from> <to> <block> [replace [filter]|keep|destroy]",
+        requires_op: true,
+        command_blocks: true,
+        run: fill::fill,
+    },
+    Command {
+        name: "gamerule",
+        usage: "/gamerule [rule] [value]",
+        requires_op: true,
+        command_blocks: true,
+        run: gamerule::gamerule,
+    },
+    Command {
+        name: "pardon",
+        usage: "/pardon <player>",
+        requires_op: true,
+        command_blocks: false,
+        run: ban::pardon,
+    },
+    Command {
+        name: "pardon-ip",
+        usage: "/pardon-ip <ip>",
+        requires_op: true,
+        command_blocks: false,
+        run: ban::pardon_ip,
+    },
+    Command {
+        name: "say",
+        usage: "/say <message>",
+        requires_op: true,
+        command_blocks: true,
+        run: say::say,
+    },
+    Command {
+        name: "setworldspawn",
+        usage: "/setworldspawn [x y z]",
+        requires_op: true,
+        command_blocks: true,
+        run: setworldspawn::setworldspawn,
+    },
+    Command {
+        name: "worldborder",
+        usage: "/worldborder <add|center|damage|get|set|warning> ...",
+        requires_op: true,
+        command_blocks: true,
+        run: worldborder::worldborder,
+    },
+];
+
+/// An error returned by a command. The error
+/// message is sent to the command sender.
+#[derive(Debug)]
+pub enum CommandError {
+    /// The command was called with invalid arguments.
+    InvalidUsage,
+    /// The command failed with the given message.
+    Failed(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::InvalidUsage => f.write_str("Invalid command usage"),
+            CommandError::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Component for the entity running a command
+/// on behalf of the command block at `position`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CommandBlockSender {
+    pub position: BlockPosition,
+}
+
+/// State passed to a command.
+pub struct CommandContext<'a> {
+    pub game: &'a mut Game,
+    pub server: &'a mut Server,
+    /// The entity that ran the command. Either
+    /// a player or the console.
+    pub sender: Entity,
+}
+
+impl CommandContext<'_> {
+    /// Gets the name of the sender, "@" for
+    /// command blocks or "Server" if the sender is the console.
+    pub fn sender_name(&self) -> String {
+        if self.is_command_block() {
+            return "@".to_owned();
+        }
+        self.game
+            .ecs
+            .get::<Name>(self.sender)
+            .map(|name| name.to_string())
+            .unwrap_or_else(|_| "Server".to_owned())
+    }
+
+    /// Returns whether the sender is an operator.
+    /// The console is always an operator.
+    pub fn is_op(&self) -> bool {
+        let client_id = match self.game.ecs.get::<ClientId>(self.sender) {
+            Ok(client_id) => *client_id,
+            Err(_) => return true,
+        };
+        self.server
+            .clients
+            .get(client_id)
+            .map(|client| self.server.op_list.is_op(client.uuid()))
+            .unwrap_or(false)
+    }
+
+    /// Gets the position relative coordinates refer to:
+    /// the sender's position, or the origin for the console.
+    pub fn origin(&self) -> Position {
+        self.game
+            .ecs
+            .get::<Position>(self.sender)
+            .map(|position| *position)
+            .unwrap_or_default()
+    }
+
+    /// Returns whether the sender is a command block.
+    pub fn is_command_block(&self) -> bool {
+        self.game.ecs.get::<CommandBlockSender>(self.sender).is_ok()
+    }
+
+    /// Sends a message to the sender.
+    pub fn reply(&mut self, message: impl Into<Text>) {
+        let message = ChatMessage::new(ChatKind::System, message.into());
+        // The sender may not have a chat box; its
+        // feedback is then dropped.
+        let _ = self.game.send_message(self.sender, message);
+    }
+}
+
+fn find_command(name: &str) -> Option<&'static Command> {
+    COMMANDS
+        .iter()
+        .find(|command| command.name.eq_ignore_ascii_case(name))
+}
+
+/// Checks that a command line can be stored in a command block.
+pub fn validate_command_block_command(line: &str) -> Result<(), CommandError> {
+    let name = match line.split_whitespace().next() {
+        Some(name) => name,
+        // Empty command blocks do nothing
+        None => return Ok(()),
+    };
+    match find_command(name) {
+        Some(command) if command.command_blocks => Ok(()),
+        Some(_) => Err(CommandError::Failed(format!(
+            "/{} cannot be run by command blocks",
+            name
+        ))),
+        None => Err(CommandError::Failed(format!("Unknown command: {}", name))),
+    }
+}
+
+/// Executes a command line (without the leading slash)
+/// on behalf of `sender`.
+pub fn execute(game: &mut Game, server: &mut Server, sender: Entity, line: &str) -> SysResult {
+    run(game, server, sender, line);
+    Ok(())
+}
+
+/// Executes a command line, returning whether it succeeded.
+pub(crate) fn run(game: &mut Game, server: &mut Server, sender: Entity, line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    let name = match words.next() {
+        Some(name) => name,
+        None => return false,
+    };
+    let args: Vec<&str> = words.collect();
+
+    let mut ctx = CommandContext {
+        game,
+        server,
+        sender,
+    };
+
+    let command = match find_command(name) {
+        Some(command) => command,
+        None => {
+            ctx.reply(format!("Unknown command: {}", name));
+            return false;
+        }
+    };
+
+    if command.requires_op && !ctx.is_op() {
+        ctx.reply("You do not have permission to use this command");
+        return false;
+    }
+    if ctx.is_command_block() {
+        if !command.command_blocks {
+            ctx.reply(format!("/{} cannot be run by command blocks", name));
+            return false;
+        }
+    } else {
+        log::info!("{} issued server command: /{}", ctx.sender_name(), line);
+    }
+
+    match (command.run)(&mut ctx, &args) {
+        Ok(()) => true,
+        Err(CommandError::InvalidUsage) => {
+            ctx.reply(format!("Usage: {}", command.usage));
+            false
+        }
+        Err(e) => {
+            ctx.reply(e.to_string());
+            false
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Parsing of command arguments shared between commands.
+
+use base::{parse_block_state, BlockId, BlockPosition, Item, Position};
+use ecs::Entity;
+use quill_common::components::Name;
+
+use crate::ClientId;
+
+use super::{CommandContext, CommandError};
+
+/// Parses three block coordinates. Coordinates prefixed
+/// with `~` are relative to `origin`.
+pub fn parse_block_position(
+    args: &[&str],
+    origin: Position,
+) -> Result<BlockPosition, CommandError> {
+    match args {
+        [x, y, z] => Ok(BlockPosition::new(
+            parse_coordinate(x, origin.x)?,
+            parse_coordinate(y, origin.y)?,
+            parse_coordinate(z, origin.z)?,
+        )),
+        _ => Err(CommandError::InvalidUsage),
+    }
+}
+
+fn parse_coordinate(arg: &str, origin: f64) -> Result<i32, CommandError> {
+    let invalid = || CommandError::Failed(format!("Invalid coordinate: {}", arg));
+    match arg.strip_prefix('~') {
+        Some("") => Ok(origin.floor() as i32),
+        Some(offset) => {
+            let offset: f64 = offset.parse().map_err(|_| invalid())?;
+            Ok((origin + offset).floor() as i32)
+        }
+        None => arg.parse().map_err(|_| invalid()),
+    }
+}
+
+/// Parses a block state such as `oak_stairs[facing=east]`.
+/// Unspecified properties take their default values.
+pub fn parse_block(arg: &str) -> Result<BlockId, CommandError> {
+    parse_block_state(arg).map_err(|e| CommandError::Failed(format!("Invalid block: {}", e)))
+}
+
+/// Parses an item name, with or without the `minecraft:` namespace.
+pub fn parse_item(arg: &str) -> Result<Item, CommandError> {
+    Item::from_name(arg.strip_prefix("minecraft:").unwrap_or(arg))
+        .ok_or_else(|| CommandError::Failed(format!("Unknown item: {}", arg)))
+}
+
+/// Finds the online player named `arg`. `@s` refers to the sender,
+/// which must be a player.
+pub fn parse_player(ctx: &CommandContext, arg: &str) -> Result<Entity, CommandError> {
+    if arg == "@s" {
+        return sender_player(ctx);
+    }
+    ctx.game
+        .ecs
+        .query::<(&Name, &ClientId)>()
+        .iter()
+        .find(|(_, (name, _))| name.eq_ignore_ascii_case(arg))
+        .map(|(entity, _)| entity)
+        .ok_or_else(|| CommandError::Failed(format!("Unknown player: {}", arg)))
+}
+
+/// Gets the sender as the target of a command
+/// whose player argument was omitted.
+pub fn sender_player(ctx: &CommandContext) -> Result<Entity, CommandError> {
+    if ctx.game.ecs.get::<ClientId>(ctx.sender).is_ok() {
+        Ok(ctx.sender)
+    } else {
+        Err(CommandError::Failed(
+            "A player is required to run this command here".into(),
+        ))
+    }
+}
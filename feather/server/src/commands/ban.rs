@@ -0,0 +1,239 @@
+//! `/ban`, `/ban-ip`, `/pardon`, and `/pardon-ip`.
+
+use std::net::IpAddr;
+
+use crate::{ban_list::DEFAULT_BAN_REASON, Client};
+
+use super::{CommandContext, CommandError};
+
+pub fn ban(ctx: &mut CommandContext, args: &[&str]) -> Result<(), CommandError> {
+    let (target, reason) = split_reason(args)?;
+
+    let online_player =
+        find_online_player(ctx, target).map(|client| (client.uuid(), client.username().to_owned()));
+
+    let source = ctx.sender_name();
+    let name = match online_player {
+        Some((uuid, name)) => {
+            ctx.server
+                .ban_list
+                .write()
+                .ban_player(uuid, name.clone(), source, reason.clone());
+            name
+        }
+        // The UUID of an offline player isn't known,
+        // so they're banned by name instead.
+        None => {
+            let name = target.to_owned();
+            ctx.server
+                .ban_list
+                .write()
+                .ban_name(name.clone(), source, reason.clone());
+            name
+        }
+    };
+    save_ban_list(ctx);
+    ctx.server.disconnect_banned_clients();
+
+    ctx.reply(format!("Banned {}: {}", name, reason));
+    Ok(())
+}
+
+pub fn ban_ip(ctx: &mut CommandContext, args: &[&str]) -> Result<(), CommandError> {
+    let (target, reason) = split_reason(args)?;
+
+    let ip = match target.parse::<IpAddr>() {
+        Ok(ip) => ip,
+        Err(_) => match find_online_player(ctx, target) {
+            Some(client) => client.ip(),
+            None => {
+                return Err(CommandError::Failed(
+                    "Invalid IP address or unknown player".into(),
+                ))
+            }
+        },
+    };
+
+    let source = ctx.sender_name();
+    ctx.server
+        .ban_list
+        .write()
+        .ban_ip(ip, source, reason.clone());
+    save_ban_list(ctx);
+    ctx.server.disconnect_banned_clients();
+
+    ctx.reply(format!("Banned IP {}: {}", ip, reason));
+    Ok(())
+}
+
+pub fn pardon(ctx: &mut CommandContext, args: &[&str]) -> Result<(), CommandError> {
+    let name = match args {
+        [name] => *name,
+        _ => return Err(CommandError::InvalidUsage),
+    };
+
+    let ban = ctx
+        .server
+        .ban_list
+        .write()
+        .pardon_player(name)
+        .ok_or_else(|| CommandError::Failed("Nothing changed. The player isn't banned".into()))?;
+    save_ban_list(ctx);
+
+    ctx.reply(format!("Unbanned {}", ban.name));
+    Ok(())
+}
+
+pub fn pardon_ip(ctx: &mut CommandContext, args: &[&str]) -> Result<(), CommandError> {
+    let ip = match args {
+        [ip] => ip
+            .parse::<IpAddr>()
+            .map_err(|_| CommandError::Failed("Invalid IP address".into()))?,
+        _ => return Err(CommandError::InvalidUsage),
+    };
+
+    ctx.server
+        .ban_list
+        .write()
+        .pardon_ip(ip)
+        .ok_or_else(|| CommandError::Failed("Nothing changed. That IP isn't banned".into()))?;
+    save_ban_list(ctx);
+
+    ctx.reply(format!("Unbanned IP {}", ip));
+    Ok(())
+}
+
+/// Splits arguments into a target and an optional, multi-word reason.
+fn split_reason<'a>(args: &[&'a str]) -> Result<(&'a str, String), CommandError> {
+    match args {
+        [target] => Ok((*target, DEFAULT_BAN_REASON.to_owned())),
+        [target, reason @ ..] => Ok((*target, reason.join(" "))),
+        [] => Err(CommandError::InvalidUsage),
+    }
+}
+
+fn find_online_player<'a>(ctx: &'a CommandContext, name: &str) -> Option<&'a Client> {
+    ctx.server
+        .clients
+        .iter()
+        .find(|client| client.username().eq_ignore_ascii_case(name))
+}
+
+fn save_ban_list(ctx: &CommandContext) {
+    // The ban is already in effect, so a failed
+    // save shouldn't fail the command.
+    if let Err(e) = ctx.server.ban_list.read().save() {
+        log::error!("Failed to save the ban list: {:?}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::Game;
+    use protocol::ServerPlayPacket;
+    use uuid::Uuid;
+
+    use crate::{commands, Server};
+
+    use super::*;
+
+    #[test]
+    fn ban_online_player() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let steve = server.connect_test_client("Steve", IpAddr::from([127, 0, 0, 1]));
+        let alex = server.connect_test_client("Alex", IpAddr::from([127, 0, 0, 2]));
+        let console = game.ecs.spawn(());
+
+        commands::execute(&mut game, &mut server, console, "ban steve griefing spawn").unwrap();
+
+        let bans = server.ban_list();
+        let ban = &bans.players()[0];
+        assert_eq!(ban.name, "Steve");
+        assert_eq!(ban.reason, "griefing spawn");
+        assert_eq!(ban.source, "Server");
+
+        let client = server.clients.get(steve.id).unwrap();
+        assert!(client.is_disconnected());
+        assert!(steve
+            .sent_packets
+            .try_iter()
+            .any(|packet| matches!(packet, ServerPlayPacket::Disconnect(_))));
+        assert!(!server.clients.get(alex.id).unwrap().is_disconnected());
+    }
+
+    #[test]
+    fn ban_offline_player() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let alex = server.connect_test_client("Alex", IpAddr::from([127, 0, 0, 1]));
+        let console = game.ecs.spawn(());
+
+        commands::execute(&mut game, &mut server, console, "ban Steve").unwrap();
+
+        let bans = server.ban_list();
+        let ban = &bans.players()[0];
+        assert_eq!(ban.name, "Steve");
+        assert_eq!(ban.uuid, None);
+        assert!(bans
+            .disconnect_reason(Uuid::from_u128(42), "steve", IpAddr::from([10, 0, 0, 1]))
+            .is_some());
+        assert!(!server.clients.get(alex.id).unwrap().is_disconnected());
+    }
+
+    #[test]
+    fn ban_ip_of_online_player() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let ip = IpAddr::from([10, 0, 0, 1]);
+        let steve = server.connect_test_client("Steve", ip);
+        let console = game.ecs.spawn(());
+
+        commands::execute(&mut game, &mut server, console, "ban-ip Steve").unwrap();
+
+        assert_eq!(
+            server.ban_list().ip_ban(ip).unwrap().reason,
+            DEFAULT_BAN_REASON
+        );
+        assert!(server.clients.get(steve.id).unwrap().is_disconnected());
+    }
+
+    #[test]
+    fn pardon_player() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        server.connect_test_client("Steve", IpAddr::from([127, 0, 0, 1]));
+        let console = game.ecs.spawn(());
+
+        commands::execute(&mut game, &mut server, console, "ban Steve").unwrap();
+        assert_eq!(server.ban_list().players().len(), 1);
+
+        commands::execute(&mut game, &mut server, console, "pardon Steve").unwrap();
+        assert!(server.ban_list().players().is_empty());
+    }
+
+    #[test]
+    fn pardon_ip() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let console = game.ecs.spawn(());
+
+        commands::execute(&mut game, &mut server, console, "ban-ip 10.0.0.1").unwrap();
+        commands::execute(&mut game, &mut server, console, "pardon-ip 10.0.0.1").unwrap();
+        assert!(server.ban_list().ips().is_empty());
+    }
+
+    #[test]
+    fn ban_requires_op() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let steve = server.connect_test_client("Steve", IpAddr::from([127, 0, 0, 1]));
+        let alex = server.connect_test_client("Alex", IpAddr::from([127, 0, 0, 2]));
+        let alex_entity = game.ecs.spawn((alex.id,));
+
+        commands::execute(&mut game, &mut server, alex_entity, "ban Steve").unwrap();
+
+        assert!(server.ban_list().players().is_empty());
+        assert!(!server.clients.get(steve.id).unwrap().is_disconnected());
+    }
+}
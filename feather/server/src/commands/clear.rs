@@ -0,0 +1,248 @@
+//! `/clear`, which removes items from a player's inventory.
+
+use base::{Area, Inventory, Item};
+use common::Window;
+use quill_common::components::Name;
+
+use crate::ClientId;
+
+use super::{
+    arguments::{parse_item, parse_player, sender_player},
+    CommandContext, CommandError,
+};
+
+/// The areas of the player's inventory cleared by `/clear`.
+const CLEARED_AREAS: &[Area] = &[
+    Area::Hotbar,
+    Area::Storage,
+    Area::Helmet,
+    Area::Chestplate,
+    Area::Leggings,
+    Area::Boots,
+    Area::Offhand,
+];
+
+pub fn clear(ctx: &mut CommandContext, args: &[&str]) -> Result<(), CommandError> {
+    let (target, item, max_count) = match args {
+        [] => (sender_player(ctx)?, None, None),
+        [player] => (parse_player(ctx, player)?, None, None),
+        [player, item] => (parse_player(ctx, player)?, Some(parse_item(item)?), None),
+        [player, item, count] => {
+            let count = count
+                .parse::<u32>()
+                .map_err(|_| CommandError::Failed(format!("Invalid count: {}", count)))?;
+            (
+                parse_player(ctx, player)?,
+                Some(parse_item(item)?),
+                Some(count),
+            )
+        }
+        _ => return Err(CommandError::InvalidUsage),
+    };
+
+    let name = ctx
+        .game
+        .ecs
+        .get::<Name>(target)
+        .map(|name| name.to_string())
+        .unwrap_or_default();
+    let removed = clear_items(ctx, target, item, max_count)?;
+    if removed == 0 {
+        return Err(CommandError::Failed(format!(
+            "No items were found on player {}",
+            name
+        )));
+    }
+
+    ctx.reply(format!("Removed {} items from player {}", removed, name));
+    Ok(())
+}
+
+/// Removes up to `max_count` items of type `item` (or any
+/// type if `None`) from `player`'s inventory, returning the
+/// number of items removed.
+fn clear_items(
+    ctx: &mut CommandContext,
+    player: ecs::Entity,
+    item: Option<Item>,
+    max_count: Option<u32>,
+) -> Result<u32, CommandError> {
+    let inventory = ctx
+        .game
+        .ecs
+        .get::<Inventory>(player)
+        .map_err(|_| CommandError::Failed("The target has no inventory".into()))?
+        .new_handle();
+    let window = ctx.game.ecs.get::<Window>(player).ok();
+    let client = ctx
+        .game
+        .ecs
+        .get::<ClientId>(player)
+        .ok()
+        .and_then(|client_id| ctx.server.clients.get(*client_id));
+
+    let mut remaining = max_count.unwrap_or(u32::MAX);
+    let mut removed = 0;
+    for &area in CLEARED_AREAS {
+        for slot in 0.. {
+            if remaining == 0 {
+                return Ok(removed);
+            }
+            let mut stack = match inventory.item(area, slot) {
+                Some(stack) => stack,
+                None => break,
+            };
+            let count = match &mut *stack {
+                Some(stack) if item.map_or(true, |item| stack.item == item) => {
+                    let count = stack.count.min(remaining);
+                    stack.count -= count;
+                    count
+                }
+                _ => continue,
+            };
+            if stack.as_ref().map_or(false, |stack| stack.count == 0) {
+                *stack = None;
+            }
+            let new_item = stack.clone();
+            drop(stack);
+
+            removed += count;
+            remaining -= count;
+
+            // Slots that aren't part of the open window, such as
+            // the armor slots while a chest is open, can't be sent.
+            let index = window
+                .as_ref()
+                .and_then(|window| window.inner().slot_to_index(&inventory, area, slot));
+            if let (Some(client), Some(index)) = (client, index) {
+                client.set_slot(index as i16, new_item);
+            }
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use base::{ItemStack, Text};
+    use common::{
+        chat::{ChatBox, ChatPreference},
+        window::BackingWindow,
+        Game,
+    };
+    use ecs::Entity;
+    use protocol::ServerPlayPacket;
+
+    use crate::{commands, testing::TestClient, Server};
+
+    use super::*;
+
+    fn setup() -> (Game, Server, Entity, TestClient) {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+
+        let inventory = Inventory::player();
+        *inventory.item(Area::Hotbar, 0).unwrap() = Some(ItemStack::new(Item::Stone, 64));
+        *inventory.item(Area::Hotbar, 1).unwrap() = Some(ItemStack::new(Item::Dirt, 10));
+        *inventory.item(Area::Storage, 3).unwrap() = Some(ItemStack::new(Item::Stone, 16));
+        *inventory.item(Area::Helmet, 0).unwrap() = Some(ItemStack::new(Item::IronHelmet, 1));
+        let window = Window::new(BackingWindow::Player {
+            player: inventory.new_handle(),
+        });
+        let player = game
+            .ecs
+            .spawn((client.id, Name::new("Steve"), inventory, window));
+        (game, server, player, client)
+    }
+
+    fn run(game: &mut Game, server: &mut Server, command: &str) -> bool {
+        let console = game.ecs.spawn(());
+        commands::run(game, server, console, command)
+    }
+
+    fn items(game: &Game, player: Entity) -> Vec<ItemStack> {
+        game.ecs
+            .get::<Inventory>(player)
+            .unwrap()
+            .to_vec()
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    fn set_slots(client: &TestClient) -> Vec<(i16, Option<ItemStack>)> {
+        client
+            .sent_packets
+            .try_iter()
+            .filter_map(|packet| match packet {
+                ServerPlayPacket::SetSlot(packet) => Some((packet.slot, packet.slot_data)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clear_all_items() {
+        let (mut game, mut server, player, client) = setup();
+        client.sent_packets.drain();
+
+        assert!(run(&mut game, &mut server, "clear Steve"));
+        assert!(items(&game, player).is_empty());
+        assert_eq!(set_slots(&client).len(), 4);
+    }
+
+    #[test]
+    fn clear_item_up_to_count() {
+        let (mut game, mut server, player, client) = setup();
+        client.sent_packets.drain();
+
+        assert!(run(
+            &mut game,
+            &mut server,
+            "clear steve minecraft:stone 70"
+        ));
+        assert_eq!(
+            items(&game, player),
+            vec![
+                ItemStack::new(Item::IronHelmet, 1),
+                ItemStack::new(Item::Stone, 10),
+                ItemStack::new(Item::Dirt, 10),
+            ]
+        );
+        // Hotbar slot 0 and main inventory slot 3
+        assert_eq!(
+            set_slots(&client),
+            vec![(36, None), (12, Some(ItemStack::new(Item::Stone, 10)))]
+        );
+    }
+
+    #[test]
+    fn clear_reports_no_matching_items() {
+        let (mut game, mut server, player, client) = setup();
+        client.sent_packets.drain();
+
+        let console = game.ecs.spawn((ChatBox::new(ChatPreference::All),));
+        assert!(!commands::run(
+            &mut game,
+            &mut server,
+            console,
+            "clear Steve diamond"
+        ));
+        let messages: Vec<_> = game
+            .ecs
+            .get_mut::<ChatBox>(console)
+            .unwrap()
+            .drain()
+            .map(|message| message.text().clone())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![Text::from("No items were found on player Steve")]
+        );
+        assert_eq!(items(&game, player).len(), 4);
+        assert!(set_slots(&client).is_empty());
+    }
+}
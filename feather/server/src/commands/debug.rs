@@ -0,0 +1,22 @@
+//! `/debug dump`, which writes a snapshot of the game state to disk.
+
+use std::fs;
+
+use super::{CommandContext, CommandError};
+
+pub fn debug(ctx: &mut CommandContext, args: &[&str]) -> Result<(), CommandError> {
+    match args {
+        ["dump"] => dump(ctx),
+        _ => Err(CommandError::InvalidUsage),
+    }
+}
+
+fn dump(ctx: &mut CommandContext) -> Result<(), CommandError> {
+    let snapshot = ctx.game.snapshot();
+    let path = format!("debug-dump-{}.json", snapshot.tick_count);
+    fs::write(&path, snapshot.to_json())
+        .map_err(|e| CommandError::Failed(format!("Failed to write {}: {}", path, e)))?;
+
+    ctx.reply(format!("Wrote game state to {}", path));
+    Ok(())
+}
@@ -0,0 +1,207 @@
+//! `/effect`, which gives and clears status effects.
+
+use common::effects::{StatusEffect, StatusEffectInstance, StatusEffects};
+use ecs::Entity;
+use quill_common::components::Name;
+
+use super::{arguments::parse_player, CommandContext, CommandError};
+
+/// Duration of an effect if none is specified, in seconds.
+const DEFAULT_DURATION: u32 = 30;
+
+/// Maximum duration of an effect, in seconds.
+const MAX_DURATION: u32 = 1_000_000;
+
+pub fn effect(ctx: &mut CommandContext, args: &[&str]) -> Result<(), CommandError> {
+    match args {
+        ["give", player, effect, rest @ ..] => {
+            let (seconds, amplifier) = match rest {
+                [] => (DEFAULT_DURATION, 0),
+                [seconds] => (parse_duration(seconds)?, 0),
+                [seconds, amplifier] => (parse_duration(seconds)?, parse_amplifier(amplifier)?),
+                _ => return Err(CommandError::InvalidUsage),
+            };
+            let target = parse_player(ctx, player)?;
+            let effect = parse_effect(effect)?;
+            give(
+                ctx,
+                target,
+                StatusEffectInstance::new(effect, amplifier, seconds * 20),
+            )
+        }
+        ["clear", player] => {
+            let target = parse_player(ctx, player)?;
+            clear(ctx, target, None)
+        }
+        ["clear", player, effect] => {
+            let target = parse_player(ctx, player)?;
+            let effect = parse_effect(effect)?;
+            clear(ctx, target, Some(effect))
+        }
+        _ => Err(CommandError::InvalidUsage),
+    }
+}
+
+fn give(
+    ctx: &mut CommandContext,
+    target: Entity,
+    instance: StatusEffectInstance,
+) -> Result<(), CommandError> {
+    if ctx.game.ecs.get::<StatusEffects>(target).is_err() {
+        let _ = ctx.game.ecs.insert(target, StatusEffects::new());
+    }
+    let added = ctx
+        .game
+        .ecs
+        .get_mut::<StatusEffects>(target)
+        .map_or(false, |mut effects| effects.add(instance));
+    if !added {
+        return Err(CommandError::Failed(
+            "Unable to apply this effect (target is either immune to effects, or has something stronger)"
+                .into(),
+        ));
+    }
+
+    let name = target_name(ctx, target);
+    ctx.reply(format!(
+        "Applied effect {} to {}",
+        instance.effect.name(),
+        name
+    ));
+    Ok(())
+}
+
+fn clear(
+    ctx: &mut CommandContext,
+    target: Entity,
+    effect: Option<StatusEffect>,
+) -> Result<(), CommandError> {
+    let removed = match ctx.game.ecs.get_mut::<StatusEffects>(target) {
+        Ok(mut effects) => match effect {
+            Some(effect) => effects.remove(effect).is_some(),
+            None => !effects.clear().is_empty(),
+        },
+        Err(_) => false,
+    };
+    if !removed {
+        return Err(CommandError::Failed(
+            "Unable to remove effect (target doesn't have the requested effect)".into(),
+        ));
+    }
+
+    let name = target_name(ctx, target);
+    match effect {
+        Some(effect) => ctx.reply(format!("Removed effect {} from {}", effect.name(), name)),
+        None => ctx.reply(format!("Removed every effect from {}", name)),
+    }
+    Ok(())
+}
+
+fn parse_effect(arg: &str) -> Result<StatusEffect, CommandError> {
+    StatusEffect::from_name(arg)
+        .ok_or_else(|| CommandError::Failed(format!("Unknown effect: {}", arg)))
+}
+
+fn parse_duration(arg: &str) -> Result<u32, CommandError> {
+    match arg.parse::<u32>() {
+        Ok(seconds) if (1..=MAX_DURATION).contains(&seconds) => Ok(seconds),
+        _ => Err(CommandError::Failed(format!(
+            "Invalid duration: {} (must be between 1 and {} seconds)",
+            arg, MAX_DURATION
+        ))),
+    }
+}
+
+fn parse_amplifier(arg: &str) -> Result<u8, CommandError> {
+    arg.parse::<u8>().map_err(|_| {
+        CommandError::Failed(format!(
+            "Invalid amplifier: {} (must be between 0 and 255)",
+            arg
+        ))
+    })
+}
+
+fn target_name(ctx: &CommandContext, target: Entity) -> String {
+    ctx.game
+        .ecs
+        .get::<Name>(target)
+        .map(|name| name.to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use common::Game;
+
+    use crate::{commands, Server};
+
+    use super::*;
+
+    fn setup() -> (Game, Server, Entity) {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        let player = game.ecs.spawn((client.id, Name::new("Steve")));
+        (game, server, player)
+    }
+
+    fn run(game: &mut Game, server: &mut Server, command: &str) -> bool {
+        let console = game.ecs.spawn(());
+        commands::run(game, server, console, command)
+    }
+
+    fn effects(game: &Game, player: Entity) -> Vec<StatusEffectInstance> {
+        game.ecs
+            .get::<StatusEffects>(player)
+            .unwrap()
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn give_effect() {
+        let (mut game, mut server, player) = setup();
+        assert!(run(
+            &mut game,
+            &mut server,
+            "effect give Steve minecraft:regeneration 10 1"
+        ));
+        assert!(run(&mut game, &mut server, "effect give Steve speed"));
+        assert_eq!(
+            effects(&game, player),
+            vec![
+                StatusEffectInstance::new(StatusEffect::Regeneration, 1, 200),
+                StatusEffectInstance::new(StatusEffect::Speed, 0, 600),
+            ]
+        );
+
+        // Weaker effects don't override stronger ones
+        assert!(!run(
+            &mut game,
+            &mut server,
+            "effect give Steve regeneration 10 0"
+        ));
+        assert!(!run(&mut game, &mut server, "effect give Steve haste"));
+    }
+
+    #[test]
+    fn clear_effect() {
+        let (mut game, mut server, player) = setup();
+        assert!(run(&mut game, &mut server, "effect give Steve speed"));
+        assert!(run(&mut game, &mut server, "effect give Steve poison"));
+
+        assert!(run(&mut game, &mut server, "effect clear Steve speed"));
+        assert_eq!(
+            effects(&game, player),
+            vec![StatusEffectInstance::new(StatusEffect::Poison, 0, 600)]
+        );
+        assert!(!run(&mut game, &mut server, "effect clear Steve speed"));
+
+        assert!(run(&mut game, &mut server, "effect clear Steve"));
+        assert!(effects(&game, player).is_empty());
+        assert!(!run(&mut game, &mut server, "effect clear Steve"));
+    }
+}
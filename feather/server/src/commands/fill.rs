@@ -0,0 +1,262 @@
+//! `/fill`, which sets all blocks in a region.
+
+use base::{vec3, BlockId, BlockPosition, Item, ItemStack, CHUNK_HEIGHT};
+use quill_common::entity_init::EntityInit;
+
+use super::{
+    arguments::{parse_block, parse_block_position},
+    CommandContext, CommandError,
+};
+
+/// Maximum number of blocks a single `/fill` may cover.
+const MAX_FILL_VOLUME: u64 = 32768;
+
+/// How `/fill` treats the blocks already in the region.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum FillMode {
+    /// Replace all blocks, or only those matching the filter.
+    Replace(Option<BlockId>),
+    /// Only replace air.
+    Keep,
+    /// Replace all blocks, dropping the old blocks as items.
+    Destroy,
+}
+
+pub fn fill(ctx: &mut CommandContext, args: &[&str]) -> Result<(), CommandError> {
+    if args.len() < 7 {
+        return Err(CommandError::InvalidUsage);
+    }
+    let origin = ctx.origin();
+    let from = parse_block_position(&args[0..3], origin)?;
+    let to = parse_block_position(&args[3..6], origin)?;
+    let block = parse_block(args[6])?;
+    let mode = match &args[7..] {
+        [] | ["replace"] => FillMode::Replace(None),
+        ["replace", filter] => FillMode::Replace(Some(parse_block(filter)?)),
+        ["keep"] => FillMode::Keep,
+        ["destroy"] => FillMode::Destroy,
+        _ => return Err(CommandError::InvalidUsage),
+    };
+
+    let min = BlockPosition::new(from.x.min(to.x), from.y.min(to.y), from.z.min(to.z));
+    let max = BlockPosition::new(from.x.max(to.x), from.y.max(to.y), from.z.max(to.z));
+    match volume(min, max) {
+        Some(volume) if volume <= MAX_FILL_VOLUME => (),
+        Some(volume) => {
+            return Err(CommandError::Failed(format!(
+                "Too many blocks in the specified area (maximum {}, specified {})",
+                MAX_FILL_VOLUME, volume
+            )))
+        }
+        None => {
+            return Err(CommandError::Failed(format!(
+                "Too many blocks in the specified area (maximum {})",
+                MAX_FILL_VOLUME
+            )))
+        }
+    }
+    if min.y < 0 || max.y >= CHUNK_HEIGHT as i32 {
+        return Err(CommandError::Failed(
+            "Cannot place blocks outside of the world".into(),
+        ));
+    }
+
+    let mut changes = Vec::new();
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                let pos = BlockPosition::new(x, y, z);
+                let old = ctx
+                    .game
+                    .block(pos)
+                    .ok_or_else(|| CommandError::Failed("That position is not loaded".into()))?;
+                let replace = match mode {
+                    FillMode::Replace(Some(filter)) => old.kind() == filter.kind(),
+                    FillMode::Replace(None) | FillMode::Destroy => true,
+                    FillMode::Keep => old.is_air(),
+                };
+                if replace && old != block {
+                    changes.push((pos, old));
+                }
+            }
+        }
+    }
+
+    if mode == FillMode::Destroy {
+        for &(pos, old) in &changes {
+            drop_block(ctx, pos, old);
+        }
+    }
+    let count = ctx
+        .game
+        .set_blocks(changes.into_iter().map(|(pos, _)| (pos, block)));
+    if count == 0 {
+        return Err(CommandError::Failed("No blocks were filled".into()));
+    }
+
+    ctx.reply(format!("Successfully filled {} blocks", count));
+    Ok(())
+}
+
+/// Returns the number of blocks between `min` and `max` inclusive,
+/// or `None` if it doesn't fit in a `u64`.
+fn volume(min: BlockPosition, max: BlockPosition) -> Option<u64> {
+    let length = |min: i32, max: i32| (i64::from(max) - i64::from(min) + 1) as u64;
+    length(min.x, max.x)
+        .checked_mul(length(min.y, max.y))?
+        .checked_mul(length(min.z, max.z))
+}
+
+/// Drops a destroyed block and the items stored in it.
+fn drop_block(ctx: &mut CommandContext, pos: BlockPosition, block: BlockId) {
+    let mut items: Vec<ItemStack> = ctx
+        .game
+        .world
+        .block_entities()
+        .get(pos)
+        .and_then(|block_entity| block_entity.inventory())
+        .map(|inventory| inventory.to_vec().into_iter().flatten().collect())
+        .unwrap_or_default();
+    if !block.is_air() {
+        if let Some(item) = Item::from_name(block.kind().name()) {
+            items.push(ItemStack::new(item, 1));
+        }
+    }
+
+    let position = pos.position() + vec3(0.5, 0.5, 0.5);
+    for item in items {
+        let mut builder = ctx.game.create_entity_builder(position, EntityInit::Item);
+        builder.add(item);
+        ctx.game.spawn_entity(builder);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{position, Chunk, ChunkPosition, FacingCardinal, HalfTopBottom};
+    use common::Game;
+
+    use crate::{commands, Server};
+
+    use super::*;
+
+    fn setup() -> (Game, Server) {
+        let mut game = Game::new();
+        game.world
+            .chunk_map_mut()
+            .insert_chunk(Chunk::new(ChunkPosition::new(0, 0)));
+        (game, Server::for_testing())
+    }
+
+    fn run(game: &mut Game, server: &mut Server, command: &str) -> bool {
+        let console = game.ecs.spawn((position!(0.0, 64.0, 0.0),));
+        commands::run(game, server, console, command)
+    }
+
+    fn count(game: &Game, block: BlockId) -> usize {
+        let mut count = 0;
+        for x in 0..16 {
+            for y in 60..70 {
+                for z in 0..16 {
+                    if game.block(BlockPosition::new(x, y, z)) == Some(block) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn fill_replace() {
+        let (mut game, mut server) = setup();
+        assert!(run(&mut game, &mut server, "fill 0 64 0 2 65 3 stone"));
+        assert_eq!(count(&game, BlockId::stone()), 24);
+
+        // Replacing with a filter only affects matching blocks
+        game.set_block(BlockPosition::new(5, 64, 5), BlockId::dirt());
+        assert!(run(
+            &mut game,
+            &mut server,
+            "fill 0 64 0 5 64 5 minecraft:glass replace stone"
+        ));
+        assert_eq!(count(&game, BlockId::glass()), 12);
+        assert_eq!(count(&game, BlockId::stone()), 12);
+        assert_eq!(count(&game, BlockId::dirt()), 1);
+    }
+
+    #[test]
+    fn fill_block_state() {
+        let (mut game, mut server) = setup();
+        assert!(run(
+            &mut game,
+            &mut server,
+            "fill 0 64 0 1 64 0 oak_stairs[facing=east,half=top]"
+        ));
+        assert_eq!(
+            game.block(BlockPosition::new(1, 64, 0)),
+            Some(
+                BlockId::oak_stairs()
+                    .with_facing_cardinal(FacingCardinal::East)
+                    .with_half_top_bottom(HalfTopBottom::Top)
+            )
+        );
+        assert!(!run(
+            &mut game,
+            &mut server,
+            "fill 0 64 0 1 64 0 oak_stairs[facing=up]"
+        ));
+    }
+
+    #[test]
+    fn fill_keep() {
+        let (mut game, mut server) = setup();
+        game.set_block(BlockPosition::new(1, 64, 1), BlockId::dirt());
+        assert!(run(&mut game, &mut server, "fill ~ ~ ~ ~2 ~ ~2 stone keep"));
+        assert_eq!(count(&game, BlockId::stone()), 8);
+        assert_eq!(count(&game, BlockId::dirt()), 1);
+    }
+
+    #[test]
+    fn fill_destroy_drops_items() {
+        let (mut game, mut server) = setup();
+        assert!(run(&mut game, &mut server, "fill 0 64 0 1 64 1 stone"));
+        assert!(run(
+            &mut game,
+            &mut server,
+            "fill 0 64 0 1 64 1 dirt destroy"
+        ));
+        assert_eq!(count(&game, BlockId::dirt()), 4);
+
+        let dropped: Vec<ItemStack> = game
+            .ecs
+            .query::<&ItemStack>()
+            .iter()
+            .map(|(_, item)| item.clone())
+            .collect();
+        assert_eq!(dropped, vec![ItemStack::new(Item::Stone, 1); 4]);
+    }
+
+    #[test]
+    fn fill_rejects_oversized_regions() {
+        let (mut game, mut server) = setup();
+        // 33 * 33 * 33 > MAX_FILL_VOLUME
+        assert!(!run(&mut game, &mut server, "fill 0 0 0 32 32 32 stone"));
+        // The volume of this region overflows a u64
+        assert!(!run(
+            &mut game,
+            &mut server,
+            "fill -2147483648 0 -2147483648 2147483647 255 2147483647 stone"
+        ));
+        assert_eq!(
+            game.block(BlockPosition::new(0, 0, 0)),
+            Some(BlockId::air())
+        );
+    }
+
+    #[test]
+    fn filling_unchanged_blocks_fails() {
+        let (mut game, mut server) = setup();
+        assert!(!run(&mut game, &mut server, "fill 0 64 0 3 64 3 air"));
+    }
+}
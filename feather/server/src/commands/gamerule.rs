@@ -0,0 +1,216 @@
+//! `/gamerule`, which lists, queries, and changes game rules.
+
+use std::cell::Ref;
+
+use base::{GameRuleError, GameRuleValue, GameRules};
+
+use super::{CommandContext, CommandError};
+
+pub fn gamerule(ctx: &mut CommandContext, args: &[&str]) -> Result<(), CommandError> {
+    match args {
+        [] => {
+            let list = {
+                let rules = game_rules(ctx)?;
+                GameRules::NAMES
+                    .iter()
+                    .map(|name| format!("{} = {}", name, rules.get(name).unwrap()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            ctx.reply(format!("Game rules: {}", list));
+            Ok(())
+        }
+        [rule] => {
+            let value = game_rules(ctx)?
+                .get(rule)
+                .ok_or_else(|| unknown_rule(rule))?;
+            ctx.reply(format!("Gamerule {} is currently set to: {}", rule, value));
+            Ok(())
+        }
+        [rule, value] => {
+            let value = ctx
+                .game
+                .resources
+                .get_mut::<GameRules>()
+                .map_err(|_| no_game_rules())?
+                .set(rule, value)
+                .map_err(|e| match e {
+                    GameRuleError::UnknownRule(rule) => unknown_rule(&rule),
+                    GameRuleError::InvalidValue {
+                        rule,
+                        value,
+                        expected,
+                    } => CommandError::Failed(format!(
+                        "Invalid value {} for gamerule {}: expected {}",
+                        value, rule, expected
+                    )),
+                })?;
+            broadcast_change(ctx, rule, value);
+            ctx.reply(format!("Gamerule {} is now set to: {}", rule, value));
+            Ok(())
+        }
+        _ => Err(CommandError::InvalidUsage),
+    }
+}
+
+/// Notifies clients of changes to the rules they
+/// are aware of.
+fn broadcast_change(ctx: &mut CommandContext, rule: &str, value: GameRuleValue) {
+    let clients = ctx.server.clients.iter();
+    match (rule, value) {
+        ("reducedDebugInfo", GameRuleValue::Bool(reduced)) => {
+            clients.for_each(|client| client.send_reduced_debug_info(reduced))
+        }
+        ("doImmediateRespawn", GameRuleValue::Bool(immediate)) => {
+            clients.for_each(|client| client.send_immediate_respawn(immediate))
+        }
+        _ => {}
+    }
+}
+
+fn game_rules<'a>(ctx: &'a CommandContext) -> Result<Ref<'a, GameRules>, CommandError> {
+    ctx.game
+        .resources
+        .get::<GameRules>()
+        .map_err(|_| no_game_rules())
+}
+
+fn unknown_rule(rule: &str) -> CommandError {
+    CommandError::Failed(format!("Unknown gamerule: {}", rule))
+}
+
+fn no_game_rules() -> CommandError {
+    CommandError::Failed("Game rules are unavailable".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use base::Text;
+    use common::{
+        chat::{ChatBox, ChatPreference},
+        Game,
+    };
+    use ecs::Entity;
+    use protocol::ServerPlayPacket;
+
+    use crate::{commands, Server};
+
+    use super::*;
+
+    fn run(game: &mut Game, server: &mut Server, console: Entity, command: &str) -> bool {
+        commands::run(game, server, console, command)
+    }
+
+    fn replies(game: &Game, console: Entity) -> Vec<Text> {
+        game.ecs
+            .get_mut::<ChatBox>(console)
+            .unwrap()
+            .drain()
+            .map(|message| message.text().clone())
+            .collect()
+    }
+
+    fn setup() -> (Game, Server, Entity) {
+        let mut game = Game::new();
+        let console = game.ecs.spawn((ChatBox::new(ChatPreference::All),));
+        (game, Server::for_testing(), console)
+    }
+
+    #[test]
+    fn set_bool_rule() {
+        let (mut game, mut server, console) = setup();
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        client.sent_packets.drain();
+
+        assert!(run(
+            &mut game,
+            &mut server,
+            console,
+            "gamerule keepInventory true"
+        ));
+        assert!(game.resources.get::<GameRules>().unwrap().keep_inventory);
+        assert_eq!(
+            replies(&game, console),
+            vec![Text::from("Gamerule keepInventory is now set to: true")]
+        );
+        // Clients don't care about this rule
+        assert!(client.sent_packets.try_recv().is_err());
+
+        assert!(run(
+            &mut game,
+            &mut server,
+            console,
+            "gamerule reducedDebugInfo true"
+        ));
+        match client.sent_packets.try_recv().unwrap() {
+            ServerPlayPacket::EntityStatus(packet) => assert_eq!(packet.status, 22),
+            packet => panic!("unexpected packet {:?}", packet),
+        }
+    }
+
+    #[test]
+    fn set_int_rule() {
+        let (mut game, mut server, console) = setup();
+        assert!(run(
+            &mut game,
+            &mut server,
+            console,
+            "gamerule randomTickSpeed 10"
+        ));
+        assert_eq!(
+            game.resources.get::<GameRules>().unwrap().random_tick_speed,
+            10
+        );
+        replies(&game, console);
+
+        assert!(run(
+            &mut game,
+            &mut server,
+            console,
+            "gamerule randomTickSpeed"
+        ));
+        assert_eq!(
+            replies(&game, console),
+            vec![Text::from(
+                "Gamerule randomTickSpeed is currently set to: 10"
+            )]
+        );
+    }
+
+    #[test]
+    fn reject_bad_values() {
+        let (mut game, mut server, console) = setup();
+        assert!(!run(
+            &mut game,
+            &mut server,
+            console,
+            "gamerule keepInventory 1"
+        ));
+        assert!(!run(
+            &mut game,
+            &mut server,
+            console,
+            "gamerule randomTickSpeed fast"
+        ));
+        assert!(!run(
+            &mut game,
+            &mut server,
+            console,
+            "gamerule flying true"
+        ));
+        assert_eq!(
+            replies(&game, console),
+            vec![
+                Text::from("Invalid value 1 for gamerule keepInventory: expected a boolean"),
+                Text::from("Invalid value fast for gamerule randomTickSpeed: expected an integer"),
+                Text::from("Unknown gamerule: flying"),
+            ]
+        );
+        assert_eq!(
+            *game.resources.get::<GameRules>().unwrap(),
+            GameRules::default()
+        );
+    }
+}
@@ -0,0 +1,19 @@
+//! `/say`, which broadcasts a message to all players.
+
+use base::Text;
+use common::chat::ChatKind;
+
+use super::{CommandContext, CommandError};
+
+pub fn say(ctx: &mut CommandContext, args: &[&str]) -> Result<(), CommandError> {
+    if args.is_empty() {
+        return Err(CommandError::InvalidUsage);
+    }
+
+    let message = Text::translate_with(
+        "chat.type.announcement",
+        vec![ctx.sender_name(), args.join(" ")],
+    );
+    ctx.game.broadcast_chat(ChatKind::System, message);
+    Ok(())
+}
@@ -0,0 +1,111 @@
+//! `/setworldspawn`, which moves the world spawn point.
+
+use common::Level;
+
+use super::{arguments::parse_block_position, CommandContext, CommandError};
+
+pub fn setworldspawn(ctx: &mut CommandContext, args: &[&str]) -> Result<(), CommandError> {
+    let spawn = match args {
+        [] => ctx.origin().block(),
+        _ => parse_block_position(args, ctx.origin())?,
+    };
+
+    let saved = {
+        let mut level = ctx
+            .game
+            .resources
+            .get_mut::<Level>()
+            .map_err(|_| CommandError::Failed("The world has no level data".into()))?;
+        level.set_spawn(spawn);
+        level.save()
+    };
+    for client in ctx.server.clients.iter() {
+        client.send_spawn_position(spawn);
+    }
+    if let Err(e) = saved {
+        log::error!("Failed to save level.dat: {:?}", e);
+        return Err(CommandError::Failed(
+            "The spawn point was changed, but could not be saved".into(),
+        ));
+    }
+
+    ctx.reply(format!(
+        "Set the world spawn point to {}, {}, {}",
+        spawn.x, spawn.y, spawn.z
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, net::Ipv4Addr};
+
+    use base::{anvil::level::LevelData, BlockPosition};
+    use common::Game;
+    use protocol::ServerPlayPacket;
+    use tempfile::TempDir;
+
+    use crate::{commands, Server};
+
+    use super::*;
+
+    fn run(game: &mut Game, server: &mut Server, command: &str) -> bool {
+        let console = game.ecs.spawn(());
+        commands::run(game, server, console, command)
+    }
+
+    #[test]
+    fn set_world_spawn() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        client.sent_packets.drain();
+
+        assert!(run(&mut game, &mut server, "setworldspawn 10 70 -5"));
+        assert_eq!(
+            game.resources.get::<Level>().unwrap().spawn(),
+            BlockPosition::new(10, 70, -5)
+        );
+        match client.sent_packets.try_recv().unwrap() {
+            ServerPlayPacket::SpawnPosition(packet) => {
+                assert_eq!(packet.position, BlockPosition::new(10, 70, -5))
+            }
+            packet => panic!("unexpected packet {:?}", packet),
+        }
+    }
+
+    #[test]
+    fn world_spawn_is_saved() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("level.dat");
+        LevelData::default()
+            .save_to_file(&mut File::create(&path).unwrap())
+            .unwrap();
+
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        game.insert_resource(Level::load(&path).unwrap());
+        assert!(run(&mut game, &mut server, "setworldspawn 3 64 ~2"));
+
+        let saved = LevelData::load_from_file(&mut File::open(&path).unwrap()).unwrap();
+        assert_eq!((saved.spawn_x, saved.spawn_y, saved.spawn_z), (3, 64, 2));
+    }
+
+    #[test]
+    fn new_worlds_get_a_level_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("world").join("level.dat");
+
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        game.insert_resource(Level::new(&path));
+        assert_eq!(
+            game.resources.get::<Level>().unwrap().spawn(),
+            BlockPosition::new(0, 64, 0)
+        );
+        assert!(run(&mut game, &mut server, "setworldspawn 3 70 2"));
+
+        let saved = LevelData::load_from_file(&mut File::open(&path).unwrap()).unwrap();
+        assert_eq!((saved.spawn_x, saved.spawn_y, saved.spawn_z), (3, 70, 2));
+    }
+}
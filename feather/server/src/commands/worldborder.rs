@@ -0,0 +1,322 @@
+//! `/worldborder`, which queries and changes the world border.
+
+use common::{world_border::MAX_DIAMETER, Level, WorldBorder};
+
+use crate::Client;
+
+use super::{CommandContext, CommandError};
+
+pub fn worldborder(ctx: &mut CommandContext, args: &[&str]) -> Result<(), CommandError> {
+    match args {
+        ["get"] => {
+            let diameter = ctx
+                .game
+                .resources
+                .get::<WorldBorder>()
+                .map_err(|_| no_border())?
+                .diameter();
+            ctx.reply(format!(
+                "The world border is currently {:.1} blocks wide",
+                diameter
+            ));
+            Ok(())
+        }
+        ["set", size, time @ ..] => {
+            let size = parse_size(size)?;
+            let ticks = parse_time(time)?;
+            resize(ctx, size, ticks)
+        }
+        ["add", distance, time @ ..] => {
+            let distance = parse_number(distance)?;
+            let ticks = parse_time(time)?;
+            let size = ctx
+                .game
+                .resources
+                .get::<WorldBorder>()
+                .map_err(|_| no_border())?
+                .target_diameter()
+                + distance;
+            if !(1.0..=MAX_DIAMETER).contains(&size) {
+                return Err(invalid_size(size));
+            }
+            resize(ctx, size, ticks)
+        }
+        ["center", x, z] => {
+            let origin = ctx.origin();
+            let x = parse_coordinate(x, origin.x)?;
+            let z = parse_coordinate(z, origin.z)?;
+            update_border(
+                ctx,
+                |border| {
+                    border.center_x = x;
+                    border.center_z = z;
+                },
+                Client::send_world_border_center,
+            )?;
+            ctx.reply(format!(
+                "Set the center of the world border to {:.2}, {:.2}",
+                x, z
+            ));
+            Ok(())
+        }
+        ["damage", "amount", amount] => {
+            let amount = parse_distance(amount)?;
+            // Clients don't know about damage
+            update_border(ctx, |border| border.damage_per_block = amount, |_, _| {})?;
+            ctx.reply(format!(
+                "Set the world border damage to {:.2} per block each second",
+                amount
+            ));
+            Ok(())
+        }
+        ["damage", "buffer", distance] => {
+            let distance = parse_distance(distance)?;
+            update_border(ctx, |border| border.damage_buffer = distance, |_, _| {})?;
+            ctx.reply(format!(
+                "Set the world border damage buffer to {:.1} blocks",
+                distance
+            ));
+            Ok(())
+        }
+        ["warning", "distance", distance] => {
+            let distance = parse_warning(distance)?;
+            update_border(
+                ctx,
+                |border| border.warning_blocks = distance,
+                Client::send_world_border_warnings,
+            )?;
+            ctx.reply(format!(
+                "Set the world border warning distance to {} blocks",
+                distance
+            ));
+            Ok(())
+        }
+        ["warning", "time", seconds] => {
+            let seconds = parse_warning(seconds)?;
+            update_border(
+                ctx,
+                |border| border.warning_time = seconds,
+                Client::send_world_border_warnings,
+            )?;
+            ctx.reply(format!(
+                "Set the world border warning time to {} seconds",
+                seconds
+            ));
+            Ok(())
+        }
+        _ => Err(CommandError::InvalidUsage),
+    }
+}
+
+/// Moves the border to `size` over `ticks` ticks.
+fn resize(ctx: &mut CommandContext, size: f64, ticks: u64) -> Result<(), CommandError> {
+    let border = update_border(
+        ctx,
+        |border| border.lerp_diameter(size, ticks),
+        Client::send_world_border_size,
+    )?;
+    let message = if ticks == 0 {
+        format!("Set the world border to {:.1} blocks wide", size)
+    } else if border.diameter() < size {
+        format!(
+            "Growing the world border to {:.1} blocks wide over {} seconds",
+            size,
+            ticks / 20
+        )
+    } else {
+        format!(
+            "Shrinking the world border to {:.1} blocks wide over {} seconds",
+            size,
+            ticks / 20
+        )
+    };
+    ctx.reply(message);
+    Ok(())
+}
+
+/// Applies `change` to the border, stores it in the level,
+/// and sends it to clients with `broadcast`.
+fn update_border(
+    ctx: &mut CommandContext,
+    change: impl FnOnce(&mut WorldBorder),
+    broadcast: fn(&Client, &WorldBorder),
+) -> Result<WorldBorder, CommandError> {
+    let border = {
+        let mut border = ctx
+            .game
+            .resources
+            .get_mut::<WorldBorder>()
+            .map_err(|_| no_border())?;
+        change(&mut border);
+        border.clone()
+    };
+    let saved = match ctx.game.resources.get_mut::<Level>() {
+        Ok(mut level) => {
+            border.write_to_level(&mut level.data);
+            level.save()
+        }
+        Err(_) => Ok(()),
+    };
+    for client in ctx.server.clients.iter() {
+        broadcast(client, &border);
+    }
+    if let Err(e) = saved {
+        log::error!("Failed to save level.dat: {:?}", e);
+        return Err(CommandError::Failed(
+            "The world border was changed, but could not be saved".into(),
+        ));
+    }
+    Ok(border)
+}
+
+fn parse_number(arg: &str) -> Result<f64, CommandError> {
+    match arg.parse::<f64>() {
+        Ok(number) if number.is_finite() => Ok(number),
+        _ => Err(CommandError::Failed(format!("Invalid number: {}", arg))),
+    }
+}
+
+fn parse_size(arg: &str) -> Result<f64, CommandError> {
+    let size = parse_number(arg)?;
+    if (1.0..=MAX_DIAMETER).contains(&size) {
+        Ok(size)
+    } else {
+        Err(invalid_size(size))
+    }
+}
+
+fn parse_distance(arg: &str) -> Result<f64, CommandError> {
+    match parse_number(arg)? {
+        distance if distance >= 0. => Ok(distance),
+        _ => Err(CommandError::Failed(format!(
+            "Invalid distance: {} (must not be negative)",
+            arg
+        ))),
+    }
+}
+
+fn parse_warning(arg: &str) -> Result<u32, CommandError> {
+    arg.parse::<u32>().map_err(|_| {
+        CommandError::Failed(format!("Invalid value: {} (must be a whole number)", arg))
+    })
+}
+
+/// Parses the optional duration of a resize, in seconds,
+/// and converts it to ticks.
+fn parse_time(args: &[&str]) -> Result<u64, CommandError> {
+    let seconds = match args {
+        [] => return Ok(0),
+        [seconds] => seconds
+            .parse::<u64>()
+            .map_err(|_| CommandError::Failed(format!("Invalid duration: {} seconds", seconds)))?,
+        _ => return Err(CommandError::InvalidUsage),
+    };
+    // Clients are sent the duration in milliseconds as an i64
+    match seconds.checked_mul(1000) {
+        Some(millis) if millis <= i64::MAX as u64 => Ok(seconds * 20),
+        _ => Err(CommandError::Failed(format!(
+            "Invalid duration: {} seconds (too long)",
+            seconds
+        ))),
+    }
+}
+
+/// Parses a coordinate of the border center. Coordinates
+/// prefixed with `~` are relative to `origin`.
+fn parse_coordinate(arg: &str, origin: f64) -> Result<f64, CommandError> {
+    match arg.strip_prefix('~') {
+        Some("") => Ok(origin),
+        Some(offset) => Ok(origin + parse_number(offset)?),
+        None => parse_number(arg),
+    }
+}
+
+fn invalid_size(size: f64) -> CommandError {
+    CommandError::Failed(format!(
+        "Invalid world border size: {:.1} (must be between 1 and {} blocks)",
+        size, MAX_DIAMETER
+    ))
+}
+
+fn no_border() -> CommandError {
+    CommandError::Failed("The world has no border".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use common::Game;
+    use protocol::{packets::server::WorldBorder as WorldBorderPacket, ServerPlayPacket, VarLong};
+
+    use crate::{commands, Server};
+
+    use super::*;
+
+    fn run(game: &mut Game, server: &mut Server, command: &str) -> bool {
+        let console = game.ecs.spawn(());
+        commands::run(game, server, console, command)
+    }
+
+    #[test]
+    fn resize_over_time() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        client.sent_packets.drain();
+
+        assert!(run(&mut game, &mut server, "worldborder set 100"));
+        assert!(run(&mut game, &mut server, "worldborder set 50 10"));
+        {
+            let border = game.resources.get::<WorldBorder>().unwrap();
+            assert_eq!(border.diameter(), 100.);
+            assert_eq!(border.target_diameter(), 50.);
+            assert_eq!(border.remaining_ticks(), 200);
+        }
+        let level = game.resources.get::<Level>().unwrap();
+        assert_eq!(level.data.border_size_lerp_target, 50.);
+        assert_eq!(level.data.border_size_lerp_time, 10_000);
+
+        let packets: Vec<_> = client.sent_packets.try_iter().collect();
+        assert_eq!(packets.len(), 2);
+        match &packets[1] {
+            ServerPlayPacket::WorldBorder(WorldBorderPacket::LerpSize {
+                old_diameter,
+                new_diameter,
+                speed,
+            }) => assert_eq!(
+                (*old_diameter, *new_diameter, *speed),
+                (100., 50., VarLong(10_000))
+            ),
+            packet => panic!("unexpected packet {:?}", packet),
+        }
+    }
+
+    #[test]
+    fn recenter() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        client.sent_packets.drain();
+
+        assert!(run(&mut game, &mut server, "worldborder center 10 -20.5"));
+        {
+            let border = game.resources.get::<WorldBorder>().unwrap();
+            assert_eq!((border.center_x, border.center_z), (10., -20.5));
+        }
+        match client.sent_packets.try_recv().unwrap() {
+            ServerPlayPacket::WorldBorder(WorldBorderPacket::SetCenter { x, z }) => {
+                assert_eq!((x, z), (10., -20.5))
+            }
+            packet => panic!("unexpected packet {:?}", packet),
+        }
+
+        assert!(!run(&mut game, &mut server, "worldborder set 0"));
+        assert!(!run(&mut game, &mut server, "worldborder center x 0"));
+        assert!(!run(
+            &mut game,
+            &mut server,
+            "worldborder set 50 18446744073709551615"
+        ));
+    }
+}
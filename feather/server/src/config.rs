@@ -1,14 +1,28 @@
 //! Loads an `Options` from a TOML config.
 
-use std::{fs, net::Ipv4Addr, path::Path, str::FromStr};
+use std::{
+    fs, io,
+    net::{Ipv4Addr, SocketAddr},
+    num::NonZeroUsize,
+    path::Path,
+    str::FromStr,
+    thread,
+};
 
 use anyhow::Context;
-use base::Gamemode;
+use base::{Difficulty, Gamemode};
+use common::mob_spawning::SpawnSettings;
 use serde::{Deserialize, Deserializer};
+use tokio::runtime::{self, Runtime};
 
-use crate::{favicon::Favicon, Options};
+use crate::{
+    favicon::Favicon,
+    motd::{Motd, MotdSelection},
+    network_id_registry::EntityIdStrategy,
+    Options,
+};
 
-const DEFAULT_CONFIG: &str = include_str!("../config.toml");
+pub(crate) const DEFAULT_CONFIG: &str = include_str!("../config.toml");
 
 /// Loads the config, creating a default config if needed.
 pub fn load(path: &str) -> anyhow::Result<ConfigContainer> {
@@ -44,6 +58,8 @@ pub struct Config {
     pub log: Log,
     pub world: World,
     pub proxy: Proxy,
+    #[serde(default)]
+    pub metrics: Metrics,
 }
 
 impl Config {
@@ -52,7 +68,7 @@ impl Config {
             port: self.network.port,
             bind_address: self.network.address.to_string(),
             favicon: Favicon::load_default(),
-            motd: self.server.motd.clone(),
+            motd: Motd::new(self.server.motd.to_list(), self.server.motd_selection),
             online_mode: if self.proxy.proxy_mode != ProxyMode::None {
                 false
             } else {
@@ -63,15 +79,18 @@ impl Config {
             } else {
                 Some(self.network.compression_threshold as usize)
             },
+            compression_level: self.network.network_compression_level,
             view_distance: self.server.view_distance,
             max_players: self.server.max_players,
             default_gamemode: self.server.default_gamemode,
+            entity_id_strategy: self.server.entity_id_strategy,
             proxy_mode: match self.proxy.proxy_mode {
                 ProxyMode::None => None,
                 ProxyMode::Bungee => Some(crate::options::ProxyMode::Bungeecord),
                 ProxyMode::Velocity => Some(crate::options::ProxyMode::Velocity),
             },
             velocity_secret: self.proxy.velocity_secret.clone(),
+            enable_command_blocks: self.server.enable_command_blocks,
         }
     }
 }
@@ -81,15 +100,95 @@ pub struct Network {
     pub address: Ipv4Addr,
     pub port: u16,
     pub compression_threshold: i32,
+    /// zlib compression level of compressed packets.
+    #[serde(
+        default = "default_compression_level",
+        deserialize_with = "deserialize_compression_level"
+    )]
+    pub network_compression_level: u32,
+    /// Number of worker threads of the async runtime
+    /// running connections.
+    #[serde(
+        default = "default_network_threads",
+        deserialize_with = "deserialize_network_threads"
+    )]
+    pub network_threads: usize,
+}
+
+impl Network {
+    /// Builds the async runtime that runs connections.
+    pub fn build_runtime(&self) -> io::Result<Runtime> {
+        runtime::Builder::new_multi_thread()
+            .worker_threads(self.network_threads)
+            .thread_name("feather-network")
+            .enable_all()
+            .build()
+    }
+}
+
+/// zlib's default level, a balance between speed and size.
+fn default_compression_level() -> u32 {
+    6
+}
+
+fn default_network_threads() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ServerConfig {
     pub online_mode: bool,
-    pub motd: String,
+    pub motd: MotdConfig,
+    #[serde(default)]
+    pub motd_selection: MotdSelection,
     pub max_players: u32,
     pub default_gamemode: Gamemode,
     pub view_distance: u32,
+    #[serde(default)]
+    pub deterministic_ticking: bool,
+    #[serde(default)]
+    pub entity_id_strategy: EntityIdStrategy,
+    #[serde(default = "default_true")]
+    pub spawn_monsters: bool,
+    #[serde(default = "default_true")]
+    pub spawn_animals: bool,
+    #[serde(default)]
+    pub enable_command_blocks: bool,
+    #[serde(default = "default_true")]
+    pub pvp: bool,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+}
+
+impl ServerConfig {
+    pub fn spawn_settings(&self) -> SpawnSettings {
+        SpawnSettings {
+            spawn_monsters: self.spawn_monsters,
+            spawn_animals: self.spawn_animals,
+            ..Default::default()
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Either a single MOTD or a list of MOTDs to choose from.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum MotdConfig {
+    Single(String),
+    List(Vec<String>),
+}
+
+impl MotdConfig {
+    pub fn to_list(&self) -> Vec<String> {
+        match self {
+            MotdConfig::Single(motd) => vec![motd.clone()],
+            MotdConfig::List(motds) => motds.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -103,6 +202,12 @@ pub struct World {
     pub name: String,
     pub generator: String,
     pub seed: String,
+    /// zlib compression level of chunks saved to region files.
+    #[serde(
+        default = "default_compression_level",
+        deserialize_with = "deserialize_compression_level"
+    )]
+    pub region_compression_level: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -111,6 +216,14 @@ pub struct Proxy {
     pub velocity_secret: String,
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct Metrics {
+    /// Address to serve Prometheus metrics at.
+    /// Metrics are disabled if unset.
+    #[serde(default)]
+    pub address: Option<SocketAddr>,
+}
+
 #[derive(Debug, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum ProxyMode {
@@ -119,6 +232,28 @@ pub enum ProxyMode {
     Velocity,
 }
 
+fn deserialize_compression_level<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<u32, D::Error> {
+    match u32::deserialize(deserializer)? {
+        level if level <= 9 => Ok(level),
+        _ => Err(serde::de::Error::custom(
+            "invalid compression level: must be between 0 and 9",
+        )),
+    }
+}
+
+fn deserialize_network_threads<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<usize, D::Error> {
+    match usize::deserialize(deserializer)? {
+        0 => Err(serde::de::Error::custom(
+            "invalid network_threads: at least one thread is required",
+        )),
+        threads => Ok(threads),
+    }
+}
+
 fn deserialize_log_level<'de, D: Deserializer<'de>>(
     deserializer: D,
 ) -> Result<log::LevelFilter, D::Error> {
@@ -133,10 +268,90 @@ fn deserialize_log_level<'de, D: Deserializer<'de>>(
 
 #[cfg(test)]
 mod tests {
+    use std::sync::{Arc, Barrier};
+
     use super::*;
 
     #[test]
     fn default_config_is_valid() {
         let _config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
     }
+
+    #[test]
+    fn motd_list() {
+        let config = DEFAULT_CONFIG
+            .replace(
+                r#"motd = "A Feather server""#,
+                r#"motd = ["first", "second"]"#,
+            )
+            .replace(
+                r#"motd_selection = "random""#,
+                r#"motd_selection = "round_robin""#,
+            );
+        let config: Config = toml::from_str(&config).unwrap();
+        let motd = config.to_options().motd;
+        assert_eq!(motd.messages(), ["first", "second"]);
+        assert_eq!(motd.pick(), "first");
+        assert_eq!(motd.pick(), "second");
+    }
+
+    #[test]
+    fn network_threads() {
+        let config = DEFAULT_CONFIG.replace(
+            "compression_threshold = 256",
+            "compression_threshold = 256\nnetwork_threads = 2",
+        );
+        let config: Config = toml::from_str(&config).unwrap();
+        assert_eq!(config.network.network_threads, 2);
+
+        // Two tasks waiting on each other only finish
+        // if they run on separate worker threads.
+        let runtime = config.network.build_runtime().unwrap();
+        let barrier = Arc::new(Barrier::new(2));
+        runtime.block_on(async {
+            let tasks: Vec<_> = (0..2)
+                .map(|_| {
+                    let barrier = Arc::clone(&barrier);
+                    tokio::task::spawn(async move {
+                        barrier.wait();
+                        thread::current().name().map(str::to_owned)
+                    })
+                })
+                .collect();
+            for task in tasks {
+                assert_eq!(task.await.unwrap().as_deref(), Some("feather-network"));
+            }
+        });
+    }
+
+    #[test]
+    fn compression_levels() {
+        let config = DEFAULT_CONFIG
+            .replace(
+                "network_compression_level = 6",
+                "network_compression_level = 1",
+            )
+            .replace(
+                "region_compression_level = 6",
+                "region_compression_level = 9",
+            );
+        let config: Config = toml::from_str(&config).unwrap();
+        assert_eq!(config.to_options().compression_level, 1);
+        assert_eq!(config.world.region_compression_level, 9);
+
+        let config = DEFAULT_CONFIG.replace(
+            "network_compression_level = 6",
+            "network_compression_level = 10",
+        );
+        assert!(toml::from_str::<Config>(&config).is_err());
+    }
+
+    #[test]
+    fn zero_network_threads_is_invalid() {
+        let config = DEFAULT_CONFIG.replace(
+            "compression_threshold = 256",
+            "compression_threshold = 256\nnetwork_threads = 0",
+        );
+        assert!(toml::from_str::<Config>(&config).is_err());
+    }
 }
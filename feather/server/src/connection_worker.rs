@@ -1,12 +1,17 @@
-use std::{fmt::Debug, io, net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    fmt::Debug,
+    io,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
 
-use base::Text;
 use flume::{Receiver, Sender};
 use futures_lite::FutureExt;
 use io::ErrorKind;
+use parking_lot::{RwLock, RwLockReadGuard};
 use protocol::{
-    codec::CryptKey, packets::server::Disconnect, ClientPlayPacket, MinecraftCodec, Readable,
-    ServerPlayPacket, Writeable,
+    codec::CryptKey, ClientPlayPacket, MinecraftCodec, Readable, ServerPlayPacket, Writeable,
 };
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
@@ -18,9 +23,11 @@ use tokio::{
 };
 
 use crate::{
+    ban_list::BanList,
     initial_handler::{InitialHandling, NewPlayer},
     options::Options,
     player_count::PlayerCount,
+    DisconnectReason,
 };
 
 /// Tokio task which handles a connection and processes
@@ -36,17 +43,22 @@ pub struct Worker {
     writer: Writer,
     options: Arc<Options>,
     player_count: PlayerCount,
+    ban_list: Arc<RwLock<BanList>>,
     packets_to_send_tx: Sender<ServerPlayPacket>,
     received_packets_rx: Receiver<ClientPlayPacket>,
     new_players: Sender<NewPlayer>,
+    /// IP address of the client. Differs from the
+    /// address of the connection if a proxy is in use.
+    client_ip: IpAddr,
 }
 
 impl Worker {
     pub fn new(
         stream: TcpStream,
-        _addr: SocketAddr,
+        addr: SocketAddr,
         options: Arc<Options>,
         player_count: PlayerCount,
+        ban_list: Arc<RwLock<BanList>>,
         new_players: Sender<NewPlayer>,
     ) -> Self {
         let (reader, writer) = stream.into_split();
@@ -61,9 +73,11 @@ impl Worker {
             writer,
             options,
             player_count,
+            ban_list,
             packets_to_send_tx,
             received_packets_rx,
             new_players,
+            client_ip: addr.ip(),
         }
     }
 
@@ -86,9 +100,9 @@ impl Worker {
             InitialHandling::Disconnect => (),
             InitialHandling::Join(new_player) => {
                 if self.player_count.try_add_player().is_err() {
-                    self.write(ServerPlayPacket::Disconnect(Disconnect {
-                        reason: Text::from("The server is full!").to_string(),
-                    }))
+                    self.write(ServerPlayPacket::Disconnect(
+                        DisconnectReason::server_full().play_packet(),
+                    ))
                     .await
                     .ok();
                     return;
@@ -109,10 +123,23 @@ impl Worker {
         self.player_count.get()
     }
 
+    pub fn ban_list(&self) -> RwLockReadGuard<BanList> {
+        self.ban_list.read()
+    }
+
+    pub fn client_ip(&self) -> IpAddr {
+        self.client_ip
+    }
+
+    pub fn set_client_ip(&mut self, ip: IpAddr) {
+        self.client_ip = ip;
+    }
+
     #[allow(unused)]
-    pub fn enable_compression(&mut self, threshold: usize) {
+    pub fn enable_compression(&mut self, threshold: usize, level: u32) {
         self.reader.codec.enable_compression(threshold);
         self.writer.codec.enable_compression(threshold);
+        self.writer.codec.set_compression_level(level);
 
         log::debug!("Enabled compression");
     }
@@ -0,0 +1,188 @@
+//! Messages shown to players when they are disconnected.
+//!
+//! Reasons are sent as chat components using the vanilla
+//! translation keys, so clients show them in their own language.
+
+use base::{Color, Text};
+use protocol::packets::server::{Disconnect, DisconnectLogin};
+
+/// A ban that never expires, as stored in the ban lists.
+const PERMANENT_BAN: &str = "forever";
+
+/// The reason a client was disconnected, shown on their
+/// disconnect screen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisconnectReason(Text);
+
+impl DisconnectReason {
+    /// The server reached its player limit.
+    pub fn server_full() -> Self {
+        Self::translate("multiplayer.disconnect.server_full", Vec::<Text>::new())
+    }
+
+    /// The player isn't on the whitelist.
+    pub fn not_whitelisted() -> Self {
+        Self::translate("multiplayer.disconnect.not_whitelisted", Vec::<Text>::new())
+    }
+
+    /// The player or their IP address is banned.
+    ///
+    /// `expires` is the date the ban expires, as stored in
+    /// the ban lists. Permanent bans (`"forever"`) don't
+    /// mention an expiry date.
+    pub fn banned(reason: &str, expires: &str) -> Self {
+        let mut text = Text::translate_with(
+            "multiplayer.disconnect.banned.reason",
+            vec![reason.to_owned()],
+        );
+        if expires != PERMANENT_BAN {
+            text = text
+                + Text::translate_with(
+                    "multiplayer.disconnect.banned.expiration",
+                    vec![expires.to_owned()],
+                );
+        }
+        Self(text)
+    }
+
+    /// The player was kicked, optionally with a reason
+    /// given by an operator.
+    pub fn kicked(reason: Option<&str>) -> Self {
+        match reason {
+            Some(reason) => Self::custom(reason.to_owned()),
+            None => Self::translate("multiplayer.disconnect.kicked", Vec::<Text>::new()),
+        }
+    }
+
+    /// The server is shutting down.
+    pub fn shutdown() -> Self {
+        Self::translate("multiplayer.disconnect.server_shutdown", Vec::<Text>::new())
+    }
+
+    /// The player logged in again from another client.
+    pub fn duplicate_login() -> Self {
+        Self::translate("multiplayer.disconnect.duplicate_login", Vec::<Text>::new())
+    }
+
+    /// The client is older than the server, which runs `version`.
+    pub fn outdated_client(version: &str) -> Self {
+        Self::translate(
+            "multiplayer.disconnect.outdated_client",
+            vec![version.to_owned()],
+        )
+    }
+
+    /// The client is newer than the server, which runs `version`.
+    pub fn outdated_server(version: &str) -> Self {
+        Self::translate(
+            "multiplayer.disconnect.outdated_server",
+            vec![version.to_owned()],
+        )
+    }
+
+    /// A reason without a translation, e.g. for protocol violations.
+    pub fn custom(text: impl Into<Text>) -> Self {
+        Self(text.into())
+    }
+
+    fn translate<T: Into<Text>>(key: &'static str, with: Vec<T>) -> Self {
+        Self(Text::translate_with(key, with))
+    }
+
+    /// Shows the whole message in `color`.
+    pub fn with_color(self, color: Color) -> Self {
+        Self(self.0 * color)
+    }
+
+    /// Gets the message as a chat component.
+    pub fn text(&self) -> &Text {
+        &self.0
+    }
+
+    /// Creates the packet disconnecting a client in the Login state.
+    pub fn login_packet(&self) -> DisconnectLogin {
+        DisconnectLogin {
+            reason: self.0.to_string(),
+        }
+    }
+
+    /// Creates the packet disconnecting a client in the Play state.
+    pub fn play_packet(&self) -> Disconnect {
+        Disconnect {
+            reason: self.0.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(reason: DisconnectReason) -> String {
+        reason.play_packet().reason
+    }
+
+    #[test]
+    fn translated_reasons() {
+        assert_eq!(
+            json(DisconnectReason::server_full()),
+            r#"{"translate":"multiplayer.disconnect.server_full","with":[]}"#
+        );
+        assert_eq!(
+            json(DisconnectReason::not_whitelisted()),
+            r#"{"translate":"multiplayer.disconnect.not_whitelisted","with":[]}"#
+        );
+        assert_eq!(
+            json(DisconnectReason::shutdown()),
+            r#"{"translate":"multiplayer.disconnect.server_shutdown","with":[]}"#
+        );
+        assert_eq!(
+            json(DisconnectReason::duplicate_login()),
+            r#"{"translate":"multiplayer.disconnect.duplicate_login","with":[]}"#
+        );
+        assert_eq!(
+            json(DisconnectReason::kicked(None)),
+            r#"{"translate":"multiplayer.disconnect.kicked","with":[]}"#
+        );
+        assert_eq!(
+            json(DisconnectReason::outdated_client("1.16.5")),
+            r#"{"translate":"multiplayer.disconnect.outdated_client","with":["1.16.5"]}"#
+        );
+        assert_eq!(
+            json(DisconnectReason::outdated_server("1.16.5")),
+            r#"{"translate":"multiplayer.disconnect.outdated_server","with":["1.16.5"]}"#
+        );
+    }
+
+    #[test]
+    fn ban_reasons() {
+        assert_eq!(
+            json(DisconnectReason::banned("griefing", "forever")),
+            r#"{"translate":"multiplayer.disconnect.banned.reason","with":["griefing"]}"#
+        );
+        assert_eq!(
+            json(DisconnectReason::banned(
+                "griefing",
+                "2021-06-01 12:00:00 +0000"
+            )),
+            concat!(
+                r#"["",{"translate":"multiplayer.disconnect.banned.reason","with":["griefing"]},"#,
+                r#"{"translate":"multiplayer.disconnect.banned.expiration","with":["2021-06-01 12:00:00 +0000"]}]"#
+            )
+        );
+    }
+
+    #[test]
+    fn custom_reasons() {
+        assert_eq!(
+            json(DisconnectReason::kicked(Some("Be nice"))),
+            r#""Be nice""#
+        );
+        assert_eq!(
+            json(DisconnectReason::custom("Malformed Packet!").with_color(Color::Red)),
+            r#"{"text":"Malformed Packet!","color":"red"}"#
+        );
+        let login = DisconnectReason::server_full().login_packet();
+        assert_eq!(login.reason, json(DisconnectReason::server_full()));
+    }
+}
@@ -1,9 +1,12 @@
-use base::{EntityKind, Position};
+use base::{Area, EntityKind, Inventory, ItemStack, Position};
+use common::{effects::StatusEffectInstance, entities::player::HotbarSlot, physics::Velocity};
 use ecs::{EntityBuilder, EntityRef, SysResult};
+use parking_lot::Mutex;
+use protocol::packets::server::{EquipmentEntry, EquipmentSlot};
 use quill_common::entity_init::EntityInit;
 use uuid::Uuid;
 
-use crate::{Client, NetworkId};
+use crate::{network_id_registry::EntityIdAllocator, Client, NetworkId};
 
 /// Component that sends the spawn packet for an entity
 /// using its components.
@@ -21,19 +24,64 @@ impl SpawnPacketSender {
 #[derive(Copy, Clone, Debug)]
 pub struct PreviousPosition(pub Position);
 
-pub fn add_entity_components(builder: &mut EntityBuilder, init: &EntityInit) {
+/// Stores the equipment of a player as last sent
+/// to clients, in the order of [`EQUIPMENT_SLOTS`].
+/// Used to determine when to send equipment updates.
+#[derive(Debug, Default)]
+pub struct PreviousEquipment(pub [Option<ItemStack>; 6]);
+
+/// Stores the status effects of an entity as last sent
+/// to clients. Used to determine when to send effect updates.
+#[derive(Debug, Default)]
+pub struct PreviousStatusEffects(pub Vec<StatusEffectInstance>);
+
+pub const EQUIPMENT_SLOTS: [EquipmentSlot; 6] = [
+    EquipmentSlot::MainHand,
+    EquipmentSlot::OffHand,
+    EquipmentSlot::Boots,
+    EquipmentSlot::Leggings,
+    EquipmentSlot::Chestplate,
+    EquipmentSlot::Helmet,
+];
+
+/// Gets the item a player holds or wears in the given slot.
+pub fn equipment_item(
+    inventory: &Inventory,
+    hotbar_slot: HotbarSlot,
+    slot: &EquipmentSlot,
+) -> Option<ItemStack> {
+    let (area, index) = match slot {
+        EquipmentSlot::MainHand => (Area::Hotbar, hotbar_slot.get()),
+        EquipmentSlot::OffHand => (Area::Offhand, 0),
+        EquipmentSlot::Boots => (Area::Boots, 0),
+        EquipmentSlot::Leggings => (Area::Leggings, 0),
+        EquipmentSlot::Chestplate => (Area::Chestplate, 0),
+        EquipmentSlot::Helmet => (Area::Helmet, 0),
+    };
+    inventory.item(area, index).and_then(|item| item.clone())
+}
+
+pub fn add_entity_components(
+    builder: &mut EntityBuilder,
+    init: &EntityInit,
+    entity_ids: &Mutex<EntityIdAllocator>,
+) {
     if !builder.has::<NetworkId>() {
-        builder.add(NetworkId::new());
+        builder.add(entity_ids.lock().allocate());
     }
-    builder.add(PreviousPosition(*builder.get::<Position>().unwrap()));
+    builder
+        .add(PreviousPosition(*builder.get::<Position>().unwrap()))
+        .add(PreviousStatusEffects::default());
     add_spawn_packet(builder, init);
 }
 
 fn add_spawn_packet(builder: &mut EntityBuilder, init: &EntityInit) {
-    // TODO: object entities spawned with Spawn Entity
-    // (minecarts, items, ...)
+    // TODO: other object entities spawned with Spawn Entity
+    // (minecarts, ...)
     let spawn_packet = match init {
         EntityInit::Player => spawn_player,
+        EntityInit::Item => spawn_item,
+        EntityInit::Arrow | EntityInit::Snowball => spawn_projectile,
         _ => spawn_living_entity,
     };
     builder.add(SpawnPacketSender(spawn_packet));
@@ -45,9 +93,31 @@ fn spawn_player(entity: &EntityRef, client: &Client) -> SysResult {
     let pos = *entity.get::<Position>()?;
 
     client.send_player(network_id, uuid, pos);
+    send_equipment(entity, network_id, client);
     Ok(())
 }
 
+/// Sends the items a player holds and wears.
+fn send_equipment(entity: &EntityRef, network_id: NetworkId, client: &Client) {
+    let (inventory, hotbar_slot) = match (entity.get::<Inventory>(), entity.get::<HotbarSlot>()) {
+        (Ok(inventory), Ok(hotbar_slot)) => (inventory, *hotbar_slot),
+        _ => return,
+    };
+
+    let entries: Vec<EquipmentEntry> = EQUIPMENT_SLOTS
+        .iter()
+        .filter_map(|slot| {
+            equipment_item(&inventory, hotbar_slot, slot).map(|item| EquipmentEntry {
+                slot: slot.clone(),
+                item: Some(item),
+            })
+        })
+        .collect();
+    if !entries.is_empty() {
+        client.send_entity_equipment(network_id, entries);
+    }
+}
+
 fn spawn_living_entity(entity: &EntityRef, client: &Client) -> SysResult {
     let network_id = *entity.get::<NetworkId>()?;
     let uuid = *entity.get::<Uuid>()?;
@@ -57,3 +127,27 @@ fn spawn_living_entity(entity: &EntityRef, client: &Client) -> SysResult {
     client.send_living_entity(network_id, uuid, pos, kind);
     Ok(())
 }
+
+fn spawn_item(entity: &EntityRef, client: &Client) -> SysResult {
+    let network_id = *entity.get::<NetworkId>()?;
+    let uuid = *entity.get::<Uuid>()?;
+    let pos = *entity.get::<Position>()?;
+    let item = entity.get::<ItemStack>()?;
+
+    client.send_item_entity(network_id, uuid, pos, &item);
+    Ok(())
+}
+
+fn spawn_projectile(entity: &EntityRef, client: &Client) -> SysResult {
+    let network_id = *entity.get::<NetworkId>()?;
+    let uuid = *entity.get::<Uuid>()?;
+    let pos = *entity.get::<Position>()?;
+    let kind = *entity.get::<EntityKind>()?;
+    let velocity = entity
+        .get::<Velocity>()
+        .map(|velocity| velocity.0)
+        .unwrap_or_default();
+
+    client.send_projectile(network_id, uuid, pos, kind, velocity);
+    Ok(())
+}
@@ -1,6 +1,6 @@
 //! Initial handling of a connection.
 
-use crate::{connection_worker::Worker, favicon::Favicon};
+use crate::{connection_worker::Worker, favicon::Favicon, DisconnectReason};
 use anyhow::bail;
 use base::{ProfileProperty, Text};
 use flume::{Receiver, Sender};
@@ -11,9 +11,7 @@ use protocol::{
     codec::CryptKey,
     packets::{
         client::{HandshakeState, Ping},
-        server::{
-            DisconnectLogin, EncryptionRequest, LoginSuccess, Pong, Response, SetCompression,
-        },
+        server::{EncryptionRequest, LoginSuccess, Pong, Response, SetCompression},
     },
     ClientHandshakePacket, ClientLoginPacket, ClientPlayPacket, ClientStatusPacket,
     ServerLoginPacket, ServerPlayPacket, ServerStatusPacket,
@@ -22,12 +20,13 @@ use rand::rngs::OsRng;
 use rsa::{PaddingScheme, PublicKeyParts, RSAPrivateKey};
 use serde::{Deserialize, Serialize};
 use sha1::Sha1;
-use std::convert::TryInto;
+use std::{cmp::Ordering, convert::TryInto, net::IpAddr};
 use uuid::Uuid;
 
 use self::proxy::ProxyData;
 
 const SERVER_NAME: &str = "Feather 1.16.5";
+const MINECRAFT_VERSION: &str = "1.16.5";
 const PROTOCOL_VERSION: i32 = 754;
 
 mod proxy;
@@ -38,6 +37,8 @@ pub struct NewPlayer {
     pub uuid: Uuid,
     pub username: String,
     pub profile: Vec<ProfileProperty>,
+    /// IP address of the client.
+    pub ip: IpAddr,
 
     pub received_packets: Receiver<ClientPlayPacket>,
     pub packets_to_send: Sender<ServerPlayPacket>,
@@ -63,14 +64,13 @@ pub async fn handle(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
     match handshake.next_state {
         HandshakeState::Status => handle_status(worker).await,
         HandshakeState::Login => {
-            if handshake.protocol_version < PROTOCOL_VERSION {
+            if let Err(reason) = check_protocol_version(handshake.protocol_version) {
+                log::debug!(
+                    "Rejected client with protocol version {}",
+                    handshake.protocol_version
+                );
                 worker
-                    .write(ServerLoginPacket::DisconnectLogin(DisconnectLogin {
-                        reason: Text::from(
-                            "Invalid protocol! The server is running on version 1.16!",
-                        )
-                        .to_string(),
-                    }))
+                    .write(ServerLoginPacket::DisconnectLogin(reason.login_packet()))
                     .await
                     .ok();
                 return Ok(InitialHandling::Disconnect);
@@ -86,6 +86,16 @@ pub async fn handle(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
     }
 }
 
+/// Checks that a client speaks the same protocol version as the server.
+/// Otherwise, returns the reason telling the player which side is outdated.
+fn check_protocol_version(protocol_version: i32) -> Result<(), DisconnectReason> {
+    match protocol_version.cmp(&PROTOCOL_VERSION) {
+        Ordering::Less => Err(DisconnectReason::outdated_client(MINECRAFT_VERSION)),
+        Ordering::Greater => Err(DisconnectReason::outdated_server(MINECRAFT_VERSION)),
+        Ordering::Equal => Ok(()),
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct StatusResponse<'a> {
     version: Version,
@@ -119,7 +129,7 @@ async fn handle_status(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
             max: worker.options().max_players,
             online: worker.player_count(),
         },
-        description: Text::from(worker.options().motd.clone()),
+        description: Text::from(worker.options().motd.pick().to_owned()),
         favicon: worker
             .options()
             .favicon
@@ -163,6 +173,15 @@ async fn handle_login(
         proxy_data = Some(proxy::do_velocity_ip_forwarding(worker).await?);
     }
 
+    // Behind a proxy, the connection comes from the proxy's
+    // address, so use the forwarded client address instead.
+    if let Some(ip) = proxy_data
+        .as_ref()
+        .and_then(|proxy_data| proxy_data.client.parse().ok())
+    {
+        worker.set_client_ip(ip);
+    }
+
     if worker.options().online_mode {
         enable_encryption(worker, login_start.name).await
     } else {
@@ -294,6 +313,19 @@ async fn finish_login(
     worker: &mut Worker,
     response: AuthResponse,
 ) -> anyhow::Result<InitialHandling> {
+    // Turn banned players away before they take up a player slot.
+    let ban = worker
+        .ban_list()
+        .disconnect_reason(response.id, &response.name, worker.client_ip());
+    if let Some(reason) = ban {
+        log::info!("Rejected {}: banned ({})", response.name, reason.text());
+        worker
+            .write(ServerLoginPacket::DisconnectLogin(reason.login_packet()))
+            .await
+            .ok();
+        return Ok(InitialHandling::Disconnect);
+    }
+
     enable_compression(worker).await?;
 
     let success = LoginSuccess {
@@ -308,6 +340,7 @@ async fn finish_login(
         username: response.name,
         uuid: response.id,
         profile: response.properties,
+        ip: worker.client_ip(),
         received_packets: worker.received_packets(),
         packets_to_send: worker.packets_to_send(),
     };
@@ -321,7 +354,30 @@ async fn enable_compression(worker: &mut Worker) -> anyhow::Result<()> {
             threshold: threshold as i32,
         });
         worker.write(&packet).await?;
-        worker.enable_compression(threshold);
+        let level = worker.options().compression_level;
+        worker.enable_compression(threshold, level);
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protocol_version_mismatch() {
+        assert_eq!(check_protocol_version(PROTOCOL_VERSION), Ok(()));
+
+        let older = check_protocol_version(PROTOCOL_VERSION - 1).unwrap_err();
+        assert_eq!(
+            older.login_packet().reason,
+            r#"{"translate":"multiplayer.disconnect.outdated_client","with":["1.16.5"]}"#
+        );
+
+        let newer = check_protocol_version(PROTOCOL_VERSION + 1).unwrap_err();
+        assert_eq!(
+            newer.login_packet().reason,
+            r#"{"translate":"multiplayer.disconnect.outdated_server","with":["1.16.5"]}"#
+        );
+    }
+}
@@ -2,30 +2,43 @@
 
 use std::{sync::Arc, time::Instant};
 
-use base::Position;
+use parking_lot::{Mutex, RwLock, RwLockReadGuard};
+
+use ban_list::BanList;
+use base::{Position, Text};
 use chunk_subscriptions::ChunkSubscriptions;
-use common::Game;
-use ecs::SystemExecutor;
+use common::{Game, Window};
+use ecs::{Entity, SysResult, SystemExecutor};
 use flume::Receiver;
 use initial_handler::NewPlayer;
 use listener::Listener;
+use op_list::OpList;
 
+pub mod ban_list;
 mod chunk_subscriptions;
 pub mod client;
+mod commands;
 pub mod config;
 mod connection_worker;
+pub mod disconnect;
 mod entities;
 pub mod favicon;
 mod initial_handler;
 mod listener;
+pub mod metrics;
+pub mod motd;
 mod network_id_registry;
+pub mod op_list;
 mod options;
 mod packet_handlers;
 mod player_count;
 mod systems;
+#[cfg(test)]
+mod testing;
 
 pub use client::{Client, ClientId, Clients};
-pub use network_id_registry::NetworkId;
+pub use disconnect::DisconnectReason;
+pub use network_id_registry::{EntityIdAllocator, EntityIdStrategy, NetworkId};
 pub use options::Options;
 use player_count::PlayerCount;
 use systems::view::WaitingChunks;
@@ -48,6 +61,15 @@ pub struct Server {
     last_keepalive_time: Instant,
 
     player_count: PlayerCount,
+
+    /// Shared with the connection workers, which
+    /// turn away banned players when they log in.
+    ban_list: Arc<RwLock<BanList>>,
+    op_list: OpList,
+
+    /// Shared with the entity spawn callback,
+    /// which allocates IDs for new entities.
+    entity_ids: Arc<Mutex<EntityIdAllocator>>,
 }
 
 impl Server {
@@ -58,8 +80,16 @@ impl Server {
         let options = Arc::new(options);
         let player_count = PlayerCount::new(options.max_players);
 
+        let ban_list = Arc::new(RwLock::new(BanList::load(".")?));
+
         let (new_players_tx, new_players) = flume::bounded(4);
-        Listener::start(Arc::clone(&options), player_count.clone(), new_players_tx).await?;
+        Listener::start(
+            Arc::clone(&options),
+            player_count.clone(),
+            Arc::clone(&ban_list),
+            new_players_tx,
+        )
+        .await?;
 
         log::info!(
             "Server is listening on {}:{}",
@@ -67,7 +97,19 @@ impl Server {
             options.port
         );
 
-        Ok(Self {
+        let mut server = Self::new(options, player_count, ban_list, new_players);
+        server.op_list = OpList::load(".")?;
+        Ok(server)
+    }
+
+    fn new(
+        options: Arc<Options>,
+        player_count: PlayerCount,
+        ban_list: Arc<RwLock<BanList>>,
+        new_players: Receiver<NewPlayer>,
+    ) -> Self {
+        let entity_ids = EntityIdAllocator::new(options.entity_id_strategy);
+        Self {
             options,
             clients: Clients::new(),
             new_players,
@@ -75,20 +117,50 @@ impl Server {
             chunk_subscriptions: ChunkSubscriptions::default(),
             last_keepalive_time: Instant::now(),
             player_count,
-        })
+            ban_list,
+            op_list: OpList::new(),
+            entity_ids: Arc::new(Mutex::new(entity_ids)),
+        }
     }
 
     /// Links this server with a `Game` so that players connecting
     /// to the server become part of this `Game`.
     pub fn link_with_game(self, game: &mut Game, systems: &mut SystemExecutor<Game>) {
+        let entity_ids = Arc::clone(&self.entity_ids);
         systems::register(self, game, systems);
-        game.add_entity_spawn_callback(entities::add_entity_components);
+        game.add_entity_spawn_callback(move |builder, init| {
+            entities::add_entity_components(builder, init, &entity_ids)
+        });
     }
 
     /// Gets the number of online players.
     pub fn player_count(&self) -> u32 {
         self.player_count.get()
     }
+
+    /// Gets the list of banned players and IP addresses.
+    pub fn ban_list(&self) -> RwLockReadGuard<BanList> {
+        self.ban_list.read()
+    }
+
+    /// Gets the list of server operators.
+    pub fn op_list(&self) -> &OpList {
+        &self.op_list
+    }
+
+    /// Opens `window` for a player, e.g. a chest or a plugin's menu,
+    /// replacing the player's `Window` component.
+    ///
+    /// Clicks are applied to the new window until the player closes it.
+    pub fn open_window(
+        &self,
+        game: &mut Game,
+        player: Entity,
+        window: Window,
+        title: impl Into<Text>,
+    ) -> SysResult {
+        packet_handlers::inventory::open_window(game, self, player, window, title)
+    }
 }
 
 /// Low-level functions, mostly used internally.
@@ -99,7 +171,7 @@ impl Server {
         let mut clients = Vec::new();
         for player in self.new_players.clone().try_iter() {
             if let Some(old_client) = self.clients.iter().find(|x| x.uuid() == player.uuid) {
-                old_client.disconnect("Logged in from another location!");
+                old_client.disconnect(DisconnectReason::duplicate_login());
             }
             let id = self.create_client(player);
             clients.push(id);
@@ -115,9 +187,31 @@ impl Server {
         }
     }
 
+    /// Disconnects all clients which are banned
+    /// according to the ban list.
+    pub fn disconnect_banned_clients(&self) {
+        let ban_list = self.ban_list.read();
+        for client in self.clients.iter() {
+            if client.is_disconnected() {
+                continue;
+            }
+            if let Some(reason) =
+                ban_list.disconnect_reason(client.uuid(), client.username(), client.ip())
+            {
+                client.disconnect(reason);
+            }
+        }
+    }
+
     /// Allocates a `NetworkId` for an entity.
     pub fn create_network_id(&mut self) -> NetworkId {
-        NetworkId::new()
+        self.entity_ids.lock().allocate()
+    }
+
+    /// Frees the `NetworkId` of a despawned entity
+    /// so that it can be reused.
+    pub fn free_network_id(&mut self, id: NetworkId) {
+        self.entity_ids.lock().free(id);
     }
 
     fn create_client(&mut self, player: NewPlayer) -> ClientId {
@@ -2,10 +2,11 @@ use std::{net::SocketAddr, sync::Arc};
 
 use anyhow::Context;
 use flume::Sender;
+use parking_lot::RwLock;
 use tokio::net::{TcpListener, TcpStream};
 
 use crate::{
-    connection_worker::Worker, initial_handler::NewPlayer, options::Options,
+    ban_list::BanList, connection_worker::Worker, initial_handler::NewPlayer, options::Options,
     player_count::PlayerCount,
 };
 
@@ -14,6 +15,7 @@ pub struct Listener {
     listener: TcpListener,
     options: Arc<Options>,
     player_count: PlayerCount,
+    ban_list: Arc<RwLock<BanList>>,
     new_players: Sender<NewPlayer>,
 }
 
@@ -21,6 +23,7 @@ impl Listener {
     pub async fn start(
         options: Arc<Options>,
         player_count: PlayerCount,
+        ban_list: Arc<RwLock<BanList>>,
         new_players: Sender<NewPlayer>,
     ) -> anyhow::Result<()> {
         let listener = TcpListener::bind(format!("{}:{}", options.bind_address, options.port))
@@ -31,6 +34,7 @@ impl Listener {
             listener,
             options,
             player_count,
+            ban_list,
             new_players,
         };
         tokio::task::spawn(async move {
@@ -54,6 +58,7 @@ impl Listener {
             addr,
             Arc::clone(&self.options),
             self.player_count.clone(),
+            Arc::clone(&self.ban_list),
             self.new_players.clone(),
         );
         worker.start();
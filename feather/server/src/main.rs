@@ -1,10 +1,17 @@
-use std::{cell::RefCell, rc::Rc, sync::Arc};
+use std::{cell::RefCell, path::Path, rc::Rc, sync::Arc, time::Instant};
 
 use anyhow::Context;
-use base::anvil::level::SuperflatGeneratorOptions;
-use common::{Game, TickLoop, World};
+use base::{
+    anvil::level::{LevelLoadError, SuperflatGeneratorOptions},
+    GameRules,
+};
+use common::{mob_spawning::MobSpawner, Game, Level, TickLoop, TickMetrics, World, WorldBorder};
 use ecs::SystemExecutor;
-use feather_server::{config::Config, Server};
+use feather_server::{
+    config::Config,
+    metrics::{CountingAllocator, MetricsExporter},
+    Server,
+};
 use plugin_host::PluginManager;
 use worldgen::{ComposableGenerator, SuperflatWorldGenerator, WorldGenerator};
 
@@ -13,8 +20,10 @@ mod logging;
 const PLUGINS_DIRECTORY: &str = "plugins";
 const CONFIG_PATH: &str = "config.toml";
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn main() -> anyhow::Result<()> {
     let feather_server::config::ConfigContainer {
         config,
         was_config_created,
@@ -25,11 +34,27 @@ async fn main() -> anyhow::Result<()> {
     }
     log::info!("Loaded config");
 
+    let runtime = config
+        .network
+        .build_runtime()
+        .context("failed to start the network runtime")?;
+    // Let the game loop spawn tasks onto the runtime.
+    // The runtime keeps running connections until `main` returns.
+    let _guard = runtime.enter();
+
     log::info!("Creating server");
     let options = config.to_options();
-    let server = Server::bind(options).await?;
-
-    let game = init_game(server, &config)?;
+    let server = runtime.block_on(Server::bind(options))?;
+
+    let mut game = init_game(server, &config)?;
+    match config.metrics.address {
+        Some(address) => {
+            let exporter = runtime.block_on(MetricsExporter::bind(address))?;
+            log::info!("Serving metrics at http://{}/metrics", exporter.address());
+            game.insert_resource(exporter);
+        }
+        None => CountingAllocator::disable(),
+    }
 
     run(game);
 
@@ -38,7 +63,12 @@ async fn main() -> anyhow::Result<()> {
 
 fn init_game(server: Server, config: &Config) -> anyhow::Result<Game> {
     let mut game = Game::new();
+    game.deterministic_ticking = config.server.deterministic_ticking;
+    game.pvp = config.server.pvp;
+    game.insert_resource(config.server.difficulty);
     init_systems(&mut game, server);
+    game.insert_resource(MobSpawner::new(config.server.spawn_settings()));
+    init_level(&mut game, config)?;
     init_world_source(&mut game, config);
     init_plugin_manager(&mut game)?;
     Ok(game)
@@ -58,6 +88,34 @@ fn init_systems(game: &mut Game, server: Server) {
     game.system_executor = Rc::new(RefCell::new(systems));
 }
 
+fn init_level(game: &mut Game, config: &Config) -> anyhow::Result<()> {
+    let path = Path::new(&config.world.name).join("level.dat");
+    if !path.exists() {
+        // New worlds use the default spawn, rules and border
+        // until their level.dat is first saved
+        game.insert_resource(Level::new(path));
+        return Ok(());
+    }
+    let level = match Level::load(&path) {
+        Ok(level) => level,
+        Err(e @ LevelLoadError::Nbt(_)) | Err(e @ LevelLoadError::Corrupt(_)) => {
+            // Use defaults, replacing the damaged file when the level is saved
+            log::warn!("{} is damaged ({}); using defaults", path.display(), e);
+            game.insert_resource(GameRules::default());
+            game.insert_resource(WorldBorder::default());
+            game.insert_resource(Level::new(path));
+            return Ok(());
+        }
+        // Unreadable files and newer levels could be damaged by
+        // running with defaults, so refuse to start instead.
+        Err(e) => return Err(e).with_context(|| format!("failed to load {}", path.display())),
+    };
+    game.insert_resource(level.data.game_rules());
+    game.insert_resource(WorldBorder::from_level(&level.data));
+    game.insert_resource(level);
+    Ok(())
+}
+
 fn init_world_source(game: &mut Game, config: &Config) {
     // Load chunks from the world save first,
     // and fall back to generating a superflat
@@ -72,7 +130,11 @@ fn init_world_source(game: &mut Game, config: &Config) {
         )),
         _ => Arc::new(ComposableGenerator::default_with_seed(seed)),
     };
-    game.world = World::with_gen_and_path(generator, config.world.name.clone());
+    game.world = World::with_gen_and_path(
+        generator,
+        config.world.name.clone(),
+        config.world.region_compression_level,
+    );
 }
 
 fn init_plugin_manager(game: &mut Game) -> anyhow::Result<()> {
@@ -97,9 +159,13 @@ fn run(game: Game) {
 
 fn create_tick_loop(mut game: Game) -> TickLoop {
     TickLoop::new(move || {
+        let start = Instant::now();
         let systems = Rc::clone(&game.system_executor);
         systems.borrow_mut().run(&mut game);
         game.tick_count += 1;
+        if let Ok(mut metrics) = game.resources.get_mut::<TickMetrics>() {
+            metrics.record(start.elapsed());
+        }
 
         false
     })
@@ -0,0 +1,288 @@
+//! Exports server metrics in the Prometheus text format.
+//!
+//! Metrics are rendered once per second by a system
+//! and served over HTTP by a Tokio task.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt::Write,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use common::{Game, TickMetrics};
+use ecs::{SysResult, SystemExecutor};
+use parking_lot::Mutex;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::Server;
+
+/// Metrics are rendered once per this many ticks.
+const RENDER_INTERVAL: u64 = 20;
+
+/// Requests with a longer head are answered without reading the rest.
+const MAX_REQUEST_LENGTH: usize = 8192;
+
+static HEAP_BYTES: AtomicUsize = AtomicUsize::new(0);
+static COUNTING_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Global allocator counting the bytes allocated
+/// on the heap, reported as `feather_heap_bytes`.
+///
+/// Heap usage is only known if this is set
+/// as the `#[global_allocator]`. Counting starts
+/// enabled, so that allocations made before metrics
+/// are configured are included.
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    /// Stops counting allocations, for servers that don't
+    /// export metrics. Counting can't be enabled again.
+    pub fn disable() {
+        COUNTING_ENABLED.store(false, Ordering::Relaxed);
+    }
+
+    fn is_enabled() -> bool {
+        COUNTING_ENABLED.load(Ordering::Relaxed)
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() && Self::is_enabled() {
+            HEAP_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() && Self::is_enabled() {
+            HEAP_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        if Self::is_enabled() {
+            HEAP_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() && Self::is_enabled() {
+            if new_size > layout.size() {
+                HEAP_BYTES.fetch_add(new_size - layout.size(), Ordering::Relaxed);
+            } else {
+                HEAP_BYTES.fetch_sub(layout.size() - new_size, Ordering::Relaxed);
+            }
+        }
+        new_ptr
+    }
+}
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.group::<Server>().add_system(render_metrics);
+}
+
+/// Resource serving metrics over HTTP.
+pub struct MetricsExporter {
+    address: SocketAddr,
+    rendered: Arc<Mutex<String>>,
+}
+
+impl MetricsExporter {
+    /// Starts serving metrics at `address`.
+    ///
+    /// Must be called within the context of a Tokio runtime.
+    pub async fn bind(address: SocketAddr) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(address).await?;
+        let address = listener.local_addr()?;
+        let rendered = Arc::new(Mutex::new(String::new()));
+
+        let served = Arc::clone(&rendered);
+        tokio::task::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        let body = served.lock().clone();
+                        tokio::task::spawn(async move {
+                            if let Err(e) = respond(stream, body).await {
+                                log::debug!("Failed to serve metrics: {:?}", e);
+                            }
+                        });
+                    }
+                    Err(e) => log::warn!("Failed to accept metrics connection: {:?}", e),
+                }
+            }
+        });
+
+        Ok(Self { address, rendered })
+    }
+
+    /// Gets the address metrics are served at.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    fn update(&self, rendered: String) {
+        *self.rendered.lock() = rendered;
+    }
+}
+
+/// Answers any HTTP request with the rendered metrics.
+async fn respond(mut stream: TcpStream, body: String) -> anyhow::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n")
+        && request.len() < MAX_REQUEST_LENGTH
+    {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+
+    let response = format!(
+        "HTTP/1.1 200 OK\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\
+         \r\n\
+         {}",
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn render_metrics(game: &mut Game, server: &mut Server) -> SysResult {
+    if game.tick_count % RENDER_INTERVAL != 0 {
+        return Ok(());
+    }
+    if let Ok(exporter) = game.resources.get::<MetricsExporter>() {
+        exporter.update(render(game, server)?);
+    }
+    Ok(())
+}
+
+/// Renders the current metrics in the Prometheus text format.
+fn render(game: &Game, server: &Server) -> anyhow::Result<String> {
+    let (tps, tick_time) = {
+        let metrics = game.resources.get::<TickMetrics>()?;
+        (metrics.tps(), metrics.mean_tick_time())
+    };
+
+    let mut out = String::new();
+    gauge(
+        &mut out,
+        "feather_tps",
+        "Ticks per second over recent ticks.",
+        tps,
+    );
+    gauge(
+        &mut out,
+        "feather_tick_time_seconds",
+        "Mean time spent running recent ticks.",
+        tick_time.as_secs_f64(),
+    );
+    gauge(
+        &mut out,
+        "feather_players",
+        "Number of online players.",
+        server.clients.iter().count(),
+    );
+    gauge(
+        &mut out,
+        "feather_loaded_chunks",
+        "Number of loaded chunks.",
+        game.world.chunk_map().len(),
+    );
+    gauge(
+        &mut out,
+        "feather_entities",
+        "Number of entities.",
+        game.ecs.query::<()>().iter().count(),
+    );
+    gauge(
+        &mut out,
+        "feather_heap_bytes",
+        "Bytes allocated on the heap.",
+        HEAP_BYTES.load(Ordering::Relaxed),
+    );
+    Ok(out)
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::Ipv4Addr, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn scrape_metrics() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let response = runtime.block_on(async {
+            let mut game = Game::new();
+            let mut server = Server::for_testing();
+            let exporter = MetricsExporter::bind((Ipv4Addr::LOCALHOST, 0).into())
+                .await
+                .unwrap();
+            let address = exporter.address();
+            game.insert_resource(exporter);
+
+            server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+            game.ecs.spawn(());
+            game.ecs.spawn(());
+            game.resources
+                .get_mut::<TickMetrics>()
+                .unwrap()
+                .record(Duration::from_millis(10));
+            render_metrics(&mut game, &mut server).unwrap();
+
+            let mut stream = TcpStream::connect(address).await.unwrap();
+            stream
+                .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+                .await
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        });
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        for line in &[
+            "# TYPE feather_tps gauge",
+            "feather_tps 20",
+            "feather_tick_time_seconds 0.01",
+            "feather_players 1",
+            "feather_loaded_chunks 0",
+            "feather_entities 2",
+            "# TYPE feather_heap_bytes gauge",
+        ] {
+            assert!(body.lines().any(|l| l == *line), "missing {:?}", line);
+        }
+    }
+}
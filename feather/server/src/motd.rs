@@ -0,0 +1,107 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+/// How the MOTD is chosen when several are configured.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MotdSelection {
+    /// Pick a random MOTD on each ping.
+    Random,
+    /// Cycle through the MOTDs in order.
+    RoundRobin,
+}
+
+impl Default for MotdSelection {
+    fn default() -> Self {
+        MotdSelection::Random
+    }
+}
+
+/// The message of the day shown in the server list.
+///
+/// Can be cloned to create a new handle; clones
+/// share their round-robin position.
+#[derive(Debug, Clone)]
+pub struct Motd {
+    messages: Vec<String>,
+    selection: MotdSelection,
+    next: Arc<AtomicUsize>,
+}
+
+impl Motd {
+    /// Creates an MOTD which picks from `messages`
+    /// according to `selection`.
+    ///
+    /// An empty list is treated as a single empty MOTD.
+    pub fn new(messages: Vec<String>, selection: MotdSelection) -> Self {
+        let messages = if messages.is_empty() {
+            vec![String::new()]
+        } else {
+            messages
+        };
+        Self {
+            messages,
+            selection,
+            next: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Creates an MOTD that always displays `message`.
+    pub fn single(message: impl Into<String>) -> Self {
+        Self::new(vec![message.into()], MotdSelection::default())
+    }
+
+    pub fn messages(&self) -> &[String] {
+        &self.messages
+    }
+
+    /// Chooses the MOTD to send in response to a status ping.
+    pub fn pick(&self) -> &str {
+        match self.selection {
+            MotdSelection::Random => self
+                .messages
+                .choose(&mut rand::thread_rng())
+                .expect("MOTD list is never empty"),
+            MotdSelection::RoundRobin => {
+                let index = self.next.fetch_add(1, Ordering::Relaxed);
+                &self.messages[index % self.messages.len()]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages() -> Vec<String> {
+        vec!["first".to_owned(), "second".to_owned(), "third".to_owned()]
+    }
+
+    #[test]
+    fn round_robin_advances() {
+        let motd = Motd::new(messages(), MotdSelection::RoundRobin);
+        let picked: Vec<&str> = (0..4).map(|_| motd.pick()).collect();
+        assert_eq!(picked, vec!["first", "second", "third", "first"]);
+    }
+
+    #[test]
+    fn random_stays_in_list() {
+        let motd = Motd::new(messages(), MotdSelection::Random);
+        for _ in 0..100 {
+            assert!(motd.messages().iter().any(|m| m == motd.pick()));
+        }
+    }
+
+    #[test]
+    fn single_motd() {
+        let motd = Motd::single("A Feather server");
+        assert_eq!(motd.pick(), "A Feather server");
+        assert_eq!(motd.pick(), "A Feather server");
+    }
+}
@@ -1,17 +1,157 @@
-use std::sync::atomic::{AtomicI32, Ordering};
+use std::collections::VecDeque;
+
+use ahash::AHashSet;
+use serde::Deserialize;
 
 /// An entity's ID used by the protocol
 /// in `entity_id` fields.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct NetworkId(pub i32);
 
-impl NetworkId {
-    /// Creates a new, unique network ID.
-    pub(crate) fn new() -> Self {
-        static NEXT: AtomicI32 = AtomicI32::new(0);
-        // In theory, this can overflow if the server
-        // creates 4 billion entities. The hope is that
-        // old entities will have died out at that point.
-        Self(NEXT.fetch_add(1, Ordering::SeqCst))
+/// Determines how an [`EntityIdAllocator`] chooses IDs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityIdStrategy {
+    /// Allocate IDs in increasing order, like vanilla.
+    /// IDs of despawned entities are only reused
+    /// once all IDs have been handed out.
+    Monotonic,
+    /// Reuse IDs of despawned entities before
+    /// allocating new ones.
+    Recycle,
+}
+
+impl Default for EntityIdStrategy {
+    fn default() -> Self {
+        EntityIdStrategy::Monotonic
+    }
+}
+
+/// Allocates unique [`NetworkId`]s.
+///
+/// An ID is never handed out while it is still in use:
+/// freed IDs go to a free list and are only reused
+/// after the entity owning them was despawned.
+#[derive(Debug)]
+pub struct EntityIdAllocator {
+    strategy: EntityIdStrategy,
+    /// The next never-used ID, or `None` if
+    /// all IDs have been allocated at least once.
+    next: Option<i32>,
+    free: VecDeque<i32>,
+    in_use: AHashSet<i32>,
+}
+
+impl EntityIdAllocator {
+    pub fn new(strategy: EntityIdStrategy) -> Self {
+        Self {
+            strategy,
+            next: Some(0),
+            free: VecDeque::new(),
+            in_use: AHashSet::new(),
+        }
+    }
+
+    /// Allocates a new, unique ID.
+    ///
+    /// # Panics
+    /// Panics if every possible ID is in use.
+    pub fn allocate(&mut self) -> NetworkId {
+        let id = match self.strategy {
+            EntityIdStrategy::Monotonic => self.next_unused().or_else(|| self.free.pop_front()),
+            EntityIdStrategy::Recycle => self.free.pop_front().or_else(|| self.next_unused()),
+        }
+        .expect("ran out of entity IDs");
+        self.in_use.insert(id);
+        NetworkId(id)
+    }
+
+    /// Frees an ID so that it can be reused.
+    ///
+    /// Returns `false` and does nothing if the ID is not in use.
+    pub fn free(&mut self, id: NetworkId) -> bool {
+        if self.in_use.remove(&id.0) {
+            self.free.push_back(id.0);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns whether the given ID is currently allocated.
+    pub fn is_in_use(&self, id: NetworkId) -> bool {
+        self.in_use.contains(&id.0)
+    }
+
+    fn next_unused(&mut self) -> Option<i32> {
+        let id = self.next?;
+        self.next = id.checked_add(1);
+        Some(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Spawns and despawns entities in an interleaved pattern,
+    /// asserting that no two live entities share an ID.
+    fn churn(allocator: &mut EntityIdAllocator) {
+        let mut live = Vec::new();
+        for round in 0..100 {
+            for _ in 0..50 {
+                let id = allocator.allocate();
+                assert!(!live.contains(&id), "{:?} allocated twice", id);
+                live.push(id);
+            }
+            // Despawn every other entity
+            let (freed, kept): (Vec<_>, Vec<_>) = live
+                .into_iter()
+                .enumerate()
+                .partition(|(i, _)| (i + round) % 2 == 0);
+            for (_, id) in freed {
+                assert!(allocator.free(id));
+                assert!(!allocator.free(id), "double free must be ignored");
+            }
+            live = kept.into_iter().map(|(_, id)| id).collect();
+        }
+        for id in live {
+            assert!(allocator.is_in_use(id));
+        }
+    }
+
+    #[test]
+    fn monotonic_no_live_collisions() {
+        churn(&mut EntityIdAllocator::new(EntityIdStrategy::Monotonic));
+    }
+
+    #[test]
+    fn recycle_no_live_collisions() {
+        churn(&mut EntityIdAllocator::new(EntityIdStrategy::Recycle));
+    }
+
+    #[test]
+    fn recycle_reuses_only_after_free() {
+        let mut allocator = EntityIdAllocator::new(EntityIdStrategy::Recycle);
+        let a = allocator.allocate();
+        let b = allocator.allocate();
+        assert_ne!(a, b);
+        assert_eq!(allocator.allocate(), NetworkId(2));
+
+        allocator.free(a);
+        assert_eq!(allocator.allocate(), a);
+        assert_eq!(allocator.allocate(), NetworkId(3));
+    }
+
+    #[test]
+    fn monotonic_reuses_once_exhausted() {
+        let mut allocator = EntityIdAllocator::new(EntityIdStrategy::Monotonic);
+        let a = allocator.allocate();
+        allocator.free(a);
+        assert_eq!(allocator.allocate(), NetworkId(1));
+
+        allocator.next = Some(i32::MAX);
+        assert_eq!(allocator.allocate(), NetworkId(i32::MAX));
+        assert_eq!(allocator.allocate(), a);
     }
 }
@@ -0,0 +1,73 @@
+//! The list of server operators, loaded from
+//! the vanilla `ops.json` file.
+
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const OPS_FILE: &str = "ops.json";
+
+/// A server operator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Operator {
+    pub uuid: Uuid,
+    pub name: String,
+    #[serde(default = "default_level")]
+    pub level: u8,
+    #[serde(default)]
+    pub bypasses_player_limit: bool,
+}
+
+fn default_level() -> u8 {
+    4
+}
+
+/// Stores the players who are allowed to
+/// run privileged commands.
+#[derive(Debug, Default)]
+pub struct OpList {
+    ops: Vec<Operator>,
+}
+
+impl OpList {
+    /// Creates an empty op list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the op list from the given directory.
+    /// A missing file is treated as an empty list.
+    pub fn load(directory: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = directory.as_ref().join(OPS_FILE);
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+        let contents = fs::read_to_string(&path)?;
+        let ops = serde_json::from_str(&contents)
+            .with_context(|| format!("malformed {}", path.display()))?;
+        Ok(Self { ops })
+    }
+
+    /// Adds an operator. Not persisted.
+    pub fn add(&mut self, operator: Operator) {
+        self.ops.retain(|op| op.uuid != operator.uuid);
+        self.ops.push(operator);
+    }
+
+    /// Returns whether the player with the given UUID is an operator.
+    pub fn is_op(&self, uuid: Uuid) -> bool {
+        self.ops.iter().any(|op| op.uuid == uuid)
+    }
+
+    /// Gets the permission level of the player with
+    /// the given UUID, which is 0 for non-operators.
+    pub fn level(&self, uuid: Uuid) -> u8 {
+        self.ops
+            .iter()
+            .find(|op| op.uuid == uuid)
+            .map_or(0, |op| op.level)
+    }
+}
@@ -1,6 +1,6 @@
 use base::Gamemode;
 
-use crate::favicon::Favicon;
+use crate::{favicon::Favicon, motd::Motd, network_id_registry::EntityIdStrategy};
 
 /// Options for building a [`Server`](crate::Server).
 #[derive(Debug, Clone)]
@@ -13,7 +13,7 @@ pub struct Options {
     /// The server favicon.
     pub favicon: Option<Favicon>,
     /// The server MOTD.
-    pub motd: String,
+    pub motd: Motd,
 
     /// Whether the server should authenticate players.
     pub online_mode: bool,
@@ -28,6 +28,9 @@ pub struct Options {
     /// The default gamemode for new players.
     pub default_gamemode: Gamemode,
 
+    /// How entity network IDs are allocated.
+    pub entity_id_strategy: EntityIdStrategy,
+
     /// Proxy IP forwarding mode
     pub proxy_mode: Option<ProxyMode>,
     // HMAC key used with Velocity IP forwarding.
@@ -35,6 +38,11 @@ pub struct Options {
 
     /// Packet size threshold at which to compress data
     pub compression_threshold: Option<usize>,
+    /// zlib compression level of compressed packets, from 0 to 9
+    pub compression_level: u32,
+
+    /// Whether command blocks run their commands.
+    pub enable_command_blocks: bool,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
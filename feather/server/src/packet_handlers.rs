@@ -1,5 +1,5 @@
 use base::{Position, Text};
-use common::{chat::ChatKind, Game};
+use common::{chat::ChatKind, death, Game};
 use ecs::{Entity, EntityRef, SysResult};
 use interaction::{
     handle_held_item_change, handle_interact_entity, handle_player_block_placement,
@@ -14,12 +14,14 @@ use protocol::{
 };
 use quill_common::components::Name;
 
-use crate::{NetworkId, Server};
+use crate::{ClientId, NetworkId, Server};
 
+mod command_block;
 mod entity_action;
 mod interaction;
 pub mod inventory;
 mod movement;
+mod sign;
 
 /// Handles a packet received from a client.
 pub fn handle_packet(
@@ -45,15 +47,23 @@ pub fn handle_packet(
 
         ClientPlayPacket::Animation(packet) => handle_animation(server, player, packet),
 
-        ClientPlayPacket::ChatMessage(packet) => handle_chat_message(game, player, packet),
+        ClientPlayPacket::ChatMessage(packet) => match packet.message.strip_prefix('/') {
+            Some(command) => crate::commands::execute(game, server, player_id, command),
+            None => handle_chat_message(game, player, packet),
+        },
 
-        ClientPlayPacket::PlayerDigging(packet) => handle_player_digging(game, packet, player_id),
+        ClientPlayPacket::PlayerDigging(packet) => {
+            handle_player_digging(game, server, packet, player_id)
+        }
 
         ClientPlayPacket::CreativeInventoryAction(packet) => {
             inventory::handle_creative_inventory_action(player, packet)
         }
         ClientPlayPacket::ClickWindow(packet) => {
-            inventory::handle_click_window(server, player, packet)
+            inventory::handle_click_window(game, server, player_id, packet)
+        }
+        ClientPlayPacket::CloseWindow(packet) => {
+            inventory::handle_close_window(game, server, player_id, packet)
         }
 
         ClientPlayPacket::PlayerBlockPlacement(packet) => {
@@ -71,18 +81,30 @@ pub fn handle_packet(
             movement::handle_player_abilities(game, player_id, packet)
         }
 
+        ClientPlayPacket::UpdateCommandBlock(packet) => {
+            command_block::handle_update_command_block(game, server, player_id, packet)
+        }
+        ClientPlayPacket::UpdateCommandBlockMinecart(packet) => {
+            command_block::handle_update_command_block_minecart(game, server, player_id, packet)
+        }
+        ClientPlayPacket::UpdateSign(packet) => {
+            sign::handle_update_sign(game, server, player_id, packet)
+        }
+
         ClientPlayPacket::EntityAction(packet) => {
             entity_action::handle_entity_action(game, player_id, packet)
         }
 
+        ClientPlayPacket::ClientStatus(packet) => {
+            handle_client_status(game, server, player_id, packet)
+        }
+
         ClientPlayPacket::TeleportConfirm(_)
         | ClientPlayPacket::QueryBlockNbt(_)
         | ClientPlayPacket::SetDifficulty(_)
-        | ClientPlayPacket::ClientStatus(_)
         | ClientPlayPacket::TabComplete(_)
         | ClientPlayPacket::WindowConfirmation(_)
         | ClientPlayPacket::ClickWindowButton(_)
-        | ClientPlayPacket::CloseWindow(_)
         | ClientPlayPacket::PluginMessage(_)
         | ClientPlayPacket::EditBook(_)
         | ClientPlayPacket::QueryEntityNbt(_)
@@ -101,11 +123,8 @@ pub fn handle_packet(
         | ClientPlayPacket::AdvancementTab(_)
         | ClientPlayPacket::SelectTrade(_)
         | ClientPlayPacket::SetBeaconEffect(_)
-        | ClientPlayPacket::UpdateCommandBlock(_)
-        | ClientPlayPacket::UpdateCommandBlockMinecart(_)
         | ClientPlayPacket::UpdateJigsawBlock(_)
         | ClientPlayPacket::UpdateStructureBlock(_)
-        | ClientPlayPacket::UpdateSign(_)
         | ClientPlayPacket::Spectate(_)
         | ClientPlayPacket::UseItem(_) => Ok(()),
     }
@@ -137,6 +156,23 @@ fn handle_chat_message(game: &Game, player: EntityRef, packet: client::ChatMessa
     Ok(())
 }
 
+/// Respawns dead players that request it.
+fn handle_client_status(
+    game: &mut Game,
+    server: &mut Server,
+    player: Entity,
+    packet: client::ClientStatus,
+) -> SysResult {
+    if !matches!(packet, client::ClientStatus::PerformRespawn) || !death::respawn(game, player)? {
+        return Ok(());
+    }
+    let client_id = *game.ecs.get::<ClientId>(player)?;
+    if let Some(client) = server.clients.get(client_id) {
+        client.update_own_position(*game.ecs.get::<Position>(player)?);
+    }
+    Ok(())
+}
+
 fn handle_client_settings(
     server: &mut Server,
     player: EntityRef,
@@ -0,0 +1,237 @@
+use base::{BlockId, Gamemode, Text};
+use blocks::FacingCubic;
+use common::{
+    block_entity::BlockEntity,
+    chat::{ChatKind, ChatMessage},
+    Game,
+};
+use ecs::{Entity, SysResult};
+use protocol::packets::client::{UpdateCommandBlock, UpdateCommandBlockMinecart};
+
+use crate::{commands, ClientId, Server};
+
+/// Maximum length of a command stored in a command block.
+const MAX_COMMAND_LENGTH: usize = 32500;
+
+const FLAG_TRACK_OUTPUT: u8 = 0x01;
+const FLAG_CONDITIONAL: u8 = 0x02;
+const FLAG_AUTO: u8 = 0x04;
+
+/// The operator permission level needed to edit command blocks.
+const EDIT_PERMISSION_LEVEL: u8 = 2;
+
+/// Handles a player editing a command block. Only operators
+/// in creative mode may edit command blocks.
+pub fn handle_update_command_block(
+    game: &mut Game,
+    server: &mut Server,
+    player: Entity,
+    packet: UpdateCommandBlock,
+) -> SysResult {
+    if !check_can_edit(game, server, player)? {
+        return Ok(());
+    }
+
+    let command = packet.command.trim();
+    if command.len() > MAX_COMMAND_LENGTH {
+        return reply(game, player, "Command is too long");
+    }
+    if let Err(e) = commands::validate_command_block_command(command.trim_start_matches('/')) {
+        return reply(game, player, e.to_string());
+    }
+
+    let old_block = match game.block(packet.position) {
+        Some(block) => block,
+        None => return Ok(()),
+    };
+    let mut command_block = match game.world.block_entities_mut().remove(packet.position) {
+        Some(BlockEntity::CommandBlock(command_block)) => command_block,
+        Some(other) => {
+            game.world
+                .block_entities_mut()
+                .insert(packet.position, other);
+            return Ok(());
+        }
+        None => return Ok(()),
+    };
+
+    let block = match packet.mode.0 {
+        0 => BlockId::chain_command_block(),
+        1 => BlockId::repeating_command_block(),
+        _ => BlockId::command_block(),
+    };
+    let block = block
+        .with_facing_cubic(old_block.facing_cubic().unwrap_or(FacingCubic::North))
+        .with_conditional(packet.flags & FLAG_CONDITIONAL != 0);
+
+    command_block.command = command.to_owned();
+    command_block.track_output = packet.flags & FLAG_TRACK_OUTPUT != 0;
+    command_block.auto = packet.flags & FLAG_AUTO != 0;
+    if !command_block.track_output {
+        command_block.last_output.clear();
+    }
+    // Changing the mode resets the block entity, so
+    // the updated command block is inserted afterwards.
+    game.set_block(packet.position, block);
+    let data = BlockEntity::CommandBlock(command_block.clone()).to_data(packet.position);
+    game.world
+        .block_entities_mut()
+        .insert(packet.position, BlockEntity::CommandBlock(command_block));
+
+    server.broadcast_nearby_with(packet.position.position(), |client| {
+        client.send_block_entity_data(&data)
+    });
+    reply(game, player, format!("Command set: {}", command))
+}
+
+/// Handles a player editing a command block minecart. Checked
+/// like command blocks, but the command isn't stored since
+/// command block minecarts don't run commands yet.
+pub fn handle_update_command_block_minecart(
+    game: &mut Game,
+    server: &mut Server,
+    player: Entity,
+    _packet: UpdateCommandBlockMinecart,
+) -> SysResult {
+    check_can_edit(game, server, player)?;
+    Ok(())
+}
+
+/// Returns whether `player` may edit command blocks: they need
+/// to be enabled, and the player must be an operator of at least
+/// level 2 in creative mode. Otherwise, tells the player why not.
+fn check_can_edit(game: &mut Game, server: &Server, player: Entity) -> SysResult<bool> {
+    if !server.options.enable_command_blocks {
+        reply(
+            game,
+            player,
+            "Command blocks are not enabled on this server",
+        )?;
+        return Ok(false);
+    }
+    let client_id = *game.ecs.get::<ClientId>(player)?;
+    let level = server
+        .clients
+        .get(client_id)
+        .map_or(0, |client| server.op_list.level(client.uuid()));
+    if level < EDIT_PERMISSION_LEVEL || *game.ecs.get::<Gamemode>(player)? != Gamemode::Creative {
+        reply(game, player, "You must be an operator in creative mode")?;
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+fn reply(game: &mut Game, player: Entity, message: impl Into<Text>) -> SysResult {
+    game.send_message(player, ChatMessage::new(ChatKind::System, message.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::Ipv4Addr, sync::Arc};
+
+    use base::{position, BlockPosition, Chunk, ChunkPosition};
+    use common::{block_entity::CommandBlock, chat::ChatPreference, ChatBox};
+    use protocol::VarInt;
+
+    use super::*;
+    use crate::op_list::Operator;
+
+    const POS: BlockPosition = BlockPosition { x: 3, y: 64, z: 3 };
+
+    fn setup(enable_command_blocks: bool) -> (Game, Server, Entity) {
+        setup_with_level(enable_command_blocks, 4)
+    }
+
+    fn setup_with_level(enable_command_blocks: bool, level: u8) -> (Game, Server, Entity) {
+        let mut game = Game::new();
+        game.world
+            .chunk_map_mut()
+            .insert_chunk(Chunk::new(ChunkPosition::new(0, 0)));
+        game.set_block(POS, BlockId::command_block());
+
+        let mut server = Server::for_testing();
+        Arc::make_mut(&mut server.options).enable_command_blocks = enable_command_blocks;
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        server.op_list.add(Operator {
+            uuid: server.clients.get(client.id).unwrap().uuid(),
+            name: "Steve".to_owned(),
+            level,
+            bypasses_player_limit: false,
+        });
+
+        let player = game.ecs.spawn((
+            client.id,
+            Gamemode::Creative,
+            position!(0.0, 64.0, 0.0),
+            ChatBox::new(ChatPreference::All),
+        ));
+        (game, server, player)
+    }
+
+    fn update(command: &str, mode: i32, flags: u8) -> UpdateCommandBlock {
+        UpdateCommandBlock {
+            position: POS,
+            command: command.to_owned(),
+            mode: VarInt(mode),
+            flags,
+        }
+    }
+
+    fn command_block(game: &Game) -> CommandBlock {
+        match game.world.block_entities().get(POS) {
+            Some(BlockEntity::CommandBlock(command_block)) => command_block.clone(),
+            other => panic!("expected a command block, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn command_is_stored() {
+        let (mut game, mut server, player) = setup(true);
+        let packet = update("say hi", 1, FLAG_AUTO | FLAG_TRACK_OUTPUT);
+        handle_update_command_block(&mut game, &mut server, player, packet).unwrap();
+
+        let command_block = command_block(&game);
+        assert_eq!(command_block.command, "say hi");
+        assert!(command_block.auto);
+        assert!(command_block.track_output);
+        assert_eq!(
+            game.block(POS).unwrap().kind(),
+            blocks::BlockKind::RepeatingCommandBlock
+        );
+    }
+
+    #[test]
+    fn invalid_commands_are_rejected() {
+        let (mut game, mut server, player) = setup(true);
+        for command in &["ban Steve", "nonexistent"] {
+            let packet = update(command, 2, 0);
+            handle_update_command_block(&mut game, &mut server, player, packet).unwrap();
+            assert_eq!(command_block(&game).command, "");
+        }
+    }
+
+    #[test]
+    fn command_blocks_cannot_be_edited_when_disabled() {
+        let (mut game, mut server, player) = setup(false);
+        let packet = update("say hi", 2, 0);
+        handle_update_command_block(&mut game, &mut server, player, packet).unwrap();
+        assert_eq!(command_block(&game).command, "");
+    }
+
+    #[test]
+    fn low_level_operators_cannot_edit() {
+        let (mut game, mut server, player) = setup_with_level(true, 1);
+        let packet = update("say hi", 2, 0);
+        handle_update_command_block(&mut game, &mut server, player, packet).unwrap();
+        assert_eq!(command_block(&game).command, "");
+    }
+
+    #[test]
+    fn operators_must_be_in_creative_mode() {
+        let (mut game, mut server, player) = setup(true);
+        game.ecs.insert(player, Gamemode::Survival).unwrap();
+        let packet = update("say hi", 2, 0);
+        handle_update_command_block(&mut game, &mut server, player, packet).unwrap();
+        assert_eq!(command_block(&game).command, "");
+    }
+}
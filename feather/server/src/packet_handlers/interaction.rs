@@ -1,18 +1,34 @@
-use crate::{ClientId, NetworkId, Server};
+use crate::{ClientId, DisconnectReason, NetworkId, Server};
+use anyhow::bail;
+use base::{Area, BlockPosition, EntityMetadata, Gamemode, Inventory, ItemStack, Position};
+use common::combat;
+use common::entities::armor_stand::ArmorStandEquipment;
+use common::entities::item_frame::ItemFrameContents;
 use common::entities::player::HotbarSlot;
 use common::interactable::InteractableRegistry;
-use common::Game;
+use common::mining::{self, Digging};
+use common::{Game, Window};
 use ecs::{Entity, EntityRef, SysResult};
 use libcraft_core::{BlockFace as LibcraftBlockFace, Hand};
 use libcraft_core::{InteractionType, Vec3f};
-use protocol::packets::client::{
-    BlockFace, HeldItemChange, InteractEntity, InteractEntityKind, PlayerBlockPlacement,
-    PlayerDigging, PlayerDiggingStatus,
+use protocol::packets::{
+    client::{
+        BlockFace, HeldItemChange, InteractEntity, InteractEntityKind, PlayerBlockPlacement,
+        PlayerDigging, PlayerDiggingStatus,
+    },
+    server::{EquipmentEntry, EquipmentSlot},
 };
 use quill_common::{
+    entity_init::EntityInit,
     events::{BlockInteractEvent, BlockPlacementEvent, InteractEntityEvent},
     EntityId,
 };
+
+/// Entity metadata index of the item in an item frame.
+const META_INDEX_ITEM_FRAME_ITEM: u8 = 7;
+/// Entity metadata index of the rotation of an item frame.
+const META_INDEX_ITEM_FRAME_ROTATION: u8 = 8;
+
 /// Handles the player block placement packet. Currently just removes the block client side for the player.
 pub fn handle_player_block_placement(
     game: &mut Game,
@@ -28,7 +44,7 @@ pub fn handle_player_block_placement(
 
             let client = _server.clients.get(*client_id).unwrap();
 
-            client.disconnect("Malformed Packet!");
+            client.disconnect(DisconnectReason::custom("Malformed Packet!"));
 
             anyhow::bail!(
                 "Player sent a malformed `PlayerBlockPlacement` packet. {:?}",
@@ -61,7 +77,9 @@ pub fn handle_player_block_placement(
 
                 let client = _server.clients.get(*client_id).unwrap();
 
-                client.disconnect("Attempted to interact with an unloaded block!");
+                client.disconnect(DisconnectReason::custom(
+                    "Attempted to interact with an unloaded block!",
+                ));
 
                 anyhow::bail!(
                     "Player attempted to interact with an unloaded block. {:?}",
@@ -110,20 +128,89 @@ pub fn handle_player_block_placement(
 /// * Shooting arrows.
 /// * Eating.
 /// * Swapping items between the main and off hand.
-pub fn handle_player_digging(game: &mut Game, packet: PlayerDigging, _player: Entity) -> SysResult {
+///
+/// Blocks break when digging starts if the player is in creative
+/// mode or the block breaks instantly. Otherwise, they break once
+/// the player finishes digging after enough time has passed.
+pub fn handle_player_digging(
+    game: &mut Game,
+    server: &mut Server,
+    packet: PlayerDigging,
+    player: Entity,
+) -> SysResult {
     log::trace!("Got player digging with status {:?}", packet.status);
     match packet.status {
-        PlayerDiggingStatus::StartDigging | PlayerDiggingStatus::CancelDigging => {
-            game.break_block(packet.position);
-            Ok(())
+        PlayerDiggingStatus::StartDigging => {
+            let creative = game
+                .ecs
+                .get::<Gamemode>(player)
+                .map_or(false, |gamemode| *gamemode == Gamemode::Creative);
+            if creative {
+                game.break_block(packet.position);
+                return Ok(());
+            }
+            match dig_time(game, player, packet.position)? {
+                Some(0) => {
+                    game.break_block(packet.position);
+                }
+                Some(_) => {
+                    let digging = Digging {
+                        position: packet.position,
+                        start_tick: game.tick_count,
+                    };
+                    game.ecs.insert(player, digging)?;
+                }
+                None => (),
+            }
+        }
+        PlayerDiggingStatus::CancelDigging => {
+            let _ = game.ecs.remove::<Digging>(player);
         }
-        _ => Ok(()),
+        PlayerDiggingStatus::FinishDigging => {
+            let digging = game.ecs.remove::<Digging>(player).ok();
+            let done = match (digging, dig_time(game, player, packet.position)?) {
+                (Some(digging), Some(dig_time)) => {
+                    digging.position == packet.position
+                        && digging.is_done(game.tick_count, dig_time)
+                }
+                _ => false,
+            };
+            if done {
+                game.break_block(packet.position);
+            } else {
+                // The client already removed the block
+                resend_block(game, server, player, packet.position)?;
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Sends the actual block at `pos` to a player
+/// whose attempt to break it was rejected.
+fn resend_block(game: &Game, server: &Server, player: Entity, pos: BlockPosition) -> SysResult {
+    let client_id = *game.ecs.get::<ClientId>(player)?;
+    if let (Some(client), Some(block)) = (server.clients.get(client_id), game.block(pos)) {
+        client.send_block_change(pos, block);
     }
+    Ok(())
+}
+
+/// Gets the number of ticks the player needs to break
+/// the block at `pos` with their held item.
+fn dig_time(game: &Game, player: Entity, pos: BlockPosition) -> SysResult<Option<u32>> {
+    let block = match game.block(pos) {
+        Some(block) => block,
+        None => return Ok(None),
+    };
+    let tool = with_held_item(game, player, Hand::Main, |held| held.clone())?.flatten();
+    Ok(mining::dig_time(block.kind(), tool.as_ref()))
 }
 
 pub fn handle_interact_entity(
     game: &mut Game,
-    _server: &mut Server,
+    server: &mut Server,
     packet: InteractEntity,
     player: Entity,
 ) -> SysResult {
@@ -140,9 +227,11 @@ pub fn handle_interact_entity(
             None => {
                 let client_id = game.ecs.get::<ClientId>(player).unwrap();
 
-                let client = _server.clients.get(*client_id).unwrap();
+                let client = server.clients.get(*client_id).unwrap();
 
-                client.disconnect("Interacted with an invalid entity!");
+                client.disconnect(DisconnectReason::custom(
+                    "Interacted with an invalid entity!",
+                ));
 
                 anyhow::bail!("Player attempted to interact with an invalid entity.")
             }
@@ -151,35 +240,39 @@ pub fn handle_interact_entity(
     };
 
     let event = match packet.kind {
-        InteractEntityKind::Attack => InteractEntityEvent {
-            target: EntityId(target.id() as u64),
-            ty: InteractionType::Attack,
-            target_pos: None,
-            hand: None,
-            sneaking: packet.sneaking,
-        },
-        InteractEntityKind::Interact => InteractEntityEvent {
-            target: EntityId(target.id() as u64),
-            ty: InteractionType::Interact,
-            target_pos: None,
-            hand: None,
-            sneaking: packet.sneaking,
-        },
+        InteractEntityKind::Attack => {
+            attack_item_frame(game, server, player, target)?;
+            attack_entity(game, server, player, target)?;
+            InteractEntityEvent {
+                target: EntityId(target.id() as u64),
+                ty: InteractionType::Attack,
+                target_pos: None,
+                hand: None,
+                sneaking: packet.sneaking,
+            }
+        }
+        InteractEntityKind::Interact { hand } => {
+            let hand = hand_from_id(hand)?;
+            use_item_frame(game, server, player, target, hand.clone())?;
+            InteractEntityEvent {
+                target: EntityId(target.id() as u64),
+                ty: InteractionType::Interact,
+                target_pos: None,
+                hand: Some(hand),
+                sneaking: packet.sneaking,
+            }
+        }
         InteractEntityKind::InteractAt {
             target_x,
             target_y,
             target_z,
             hand,
         } => {
-            let hand = match hand {
-                0 => Hand::Main,
-                1 => Hand::Offhand,
-                _ => unreachable!(),
-            };
-
+            let hand = hand_from_id(hand)?;
+            use_armor_stand(game, server, player, target, hand.clone(), target_y)?;
             InteractEntityEvent {
                 target: EntityId(target.id() as u64),
-                ty: InteractionType::Attack,
+                ty: InteractionType::Interact,
                 target_pos: Some(Vec3f::new(
                     target_x as f32,
                     target_y as f32,
@@ -196,6 +289,162 @@ pub fn handle_interact_entity(
     Ok(())
 }
 
+fn hand_from_id(id: i32) -> SysResult<Hand> {
+    match id {
+        0 => Ok(Hand::Main),
+        1 => Ok(Hand::Offhand),
+        _ => bail!("invalid hand {}", id),
+    }
+}
+
+/// Runs `f` on the item a player holds in the given hand.
+///
+/// Returns `None` if the player has no such slot.
+fn with_held_item<T>(
+    game: &Game,
+    player: Entity,
+    hand: Hand,
+    f: impl FnOnce(&mut Option<ItemStack>) -> T,
+) -> SysResult<Option<T>> {
+    let inventory = game.ecs.get::<Inventory>(player)?;
+    let mut held = match hand {
+        Hand::Main => inventory.item(Area::Hotbar, game.ecs.get::<HotbarSlot>(player)?.get()),
+        Hand::Offhand => inventory.item(Area::Offhand, 0),
+    };
+    Ok(held.as_deref_mut().map(f))
+}
+
+/// Places an item into an item frame, or rotates its item.
+fn use_item_frame(
+    game: &mut Game,
+    server: &Server,
+    player: Entity,
+    frame: Entity,
+    hand: Hand,
+) -> SysResult {
+    let mut contents = match game.ecs.get_mut::<ItemFrameContents>(frame) {
+        Ok(contents) => contents,
+        Err(_) => return Ok(()),
+    };
+    let consume = *game.ecs.get::<Gamemode>(player)? != Gamemode::Creative;
+    let changed = with_held_item(game, player, hand, |held| contents.interact(held, consume))?;
+    drop(contents);
+
+    if changed == Some(true) {
+        send_item_frame(game, server, frame)?;
+        resend_window(game, server, player)?;
+    }
+    Ok(())
+}
+
+/// Knocks the item out of an item frame. The item
+/// is dropped unless the player is in creative mode.
+fn attack_item_frame(game: &mut Game, server: &Server, player: Entity, frame: Entity) -> SysResult {
+    let item = match game.ecs.get_mut::<ItemFrameContents>(frame) {
+        Ok(mut contents) => contents.take_item(),
+        Err(_) => return Ok(()),
+    };
+    let item = match item {
+        Some(item) => item,
+        None => return Ok(()),
+    };
+    send_item_frame(game, server, frame)?;
+
+    if *game.ecs.get::<Gamemode>(player)? != Gamemode::Creative {
+        let position = *game.ecs.get::<Position>(frame)?;
+        let mut builder = game.create_entity_builder(position, EntityInit::Item);
+        builder.add(item);
+        game.spawn_entity(builder);
+    }
+    Ok(())
+}
+
+/// Hits an entity with the player's held item and
+/// sends the resulting knockback.
+fn attack_entity(game: &mut Game, server: &Server, player: Entity, target: Entity) -> SysResult {
+    let attack = match combat::attack(game, player, target)? {
+        Some(attack) => attack,
+        None => return Ok(()),
+    };
+
+    // Players move themselves, so their own client
+    // needs to be told about the knockback too.
+    let network_id = *game.ecs.get::<NetworkId>(target)?;
+    for client in server.clients.iter() {
+        if client.network_id() == network_id || client.is_entity_loaded(network_id) {
+            client.send_entity_velocity(network_id, attack.velocity);
+        }
+    }
+    Ok(())
+}
+
+fn send_item_frame(game: &Game, server: &Server, frame: Entity) -> SysResult {
+    let contents = game.ecs.get::<ItemFrameContents>(frame)?;
+    let position = *game.ecs.get::<Position>(frame)?;
+    let network_id = *game.ecs.get::<NetworkId>(frame)?;
+
+    let metadata = EntityMetadata::new()
+        .with(META_INDEX_ITEM_FRAME_ITEM, contents.item.clone())
+        .with(META_INDEX_ITEM_FRAME_ROTATION, contents.rotation as i32);
+    server.broadcast_nearby_with(position, |client| {
+        client.send_entity_metadata(network_id, metadata.clone())
+    });
+    Ok(())
+}
+
+/// Puts armor onto an armor stand or takes it off.
+fn use_armor_stand(
+    game: &mut Game,
+    server: &Server,
+    player: Entity,
+    stand: Entity,
+    hand: Hand,
+    target_y: f64,
+) -> SysResult {
+    let mut equipment = match game.ecs.get_mut::<ArmorStandEquipment>(stand) {
+        Ok(equipment) => equipment,
+        Err(_) => return Ok(()),
+    };
+    let changed = with_held_item(game, player, hand, |held| {
+        equipment.interact(target_y, held)
+    })?
+    .flatten();
+    let (area, item) = match changed {
+        Some(area) => (area, equipment.slot_mut(area).unwrap().clone()),
+        None => return Ok(()),
+    };
+    drop(equipment);
+
+    let slot = match area {
+        Area::Helmet => EquipmentSlot::Helmet,
+        Area::Chestplate => EquipmentSlot::Chestplate,
+        Area::Leggings => EquipmentSlot::Leggings,
+        _ => EquipmentSlot::Boots,
+    };
+    let position = *game.ecs.get::<Position>(stand)?;
+    let network_id = *game.ecs.get::<NetworkId>(stand)?;
+    server.broadcast_nearby_with(position, |client| {
+        client.send_entity_equipment(
+            network_id,
+            vec![EquipmentEntry {
+                slot: slot.clone(),
+                item: item.clone(),
+            }],
+        )
+    });
+
+    resend_window(game, server, player)
+}
+
+/// Sends a player's window after its held item changed.
+fn resend_window(game: &Game, server: &Server, player: Entity) -> SysResult {
+    let client_id = *game.ecs.get::<ClientId>(player)?;
+    if let Some(client) = server.clients.get(client_id) {
+        client.send_window_items(&*game.ecs.get::<Window>(player)?);
+    }
+    Ok(())
+}
+
 pub fn handle_held_item_change(player: EntityRef, packet: HeldItemChange) -> SysResult {
     let new_id = packet.slot as usize;
     let mut slot = player.get_mut::<HotbarSlot>()?;
@@ -208,10 +457,17 @@ pub fn handle_held_item_change(player: EntityRef, packet: HeldItemChange) -> Sys
 
 #[cfg(test)]
 mod tests {
-    use common::Game;
-    use protocol::packets::client::HeldItemChange;
+    use std::net::Ipv4Addr;
+
+    use base::{position, BlockId, Chunk, ChunkPosition, Item, Vec3d};
+    use common::{
+        combat::AttackCooldown, damage::Health, physics::Velocity, window::BackingWindow, Game,
+    };
+    use protocol::{packets::client::HeldItemChange, ServerPlayPacket};
+    use quill_common::components::OnGround;
 
     use super::*;
+    use crate::testing::TestClient;
 
     #[test]
     fn held_item_change() {
@@ -228,4 +484,305 @@ mod tests {
             HotbarSlot::new(8)
         );
     }
+
+    #[test]
+    fn place_and_remove_item_in_item_frame() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let (player, inventory, _client) = spawn_player(&mut game, &mut server);
+        *inventory.item(Area::Hotbar, 0).unwrap() = Some(ItemStack::new(Item::Diamond, 5));
+
+        let mut builder =
+            game.create_entity_builder(position!(2.0, 65.0, 0.0), EntityInit::ItemFrame);
+        builder.add(NetworkId(100));
+        common::entities::item_frame::build_default(&mut builder);
+        let frame = game.spawn_entity(builder);
+
+        let packet = InteractEntity {
+            entity_id: 100,
+            kind: InteractEntityKind::Interact { hand: 0 },
+            sneaking: false,
+        };
+        handle_interact_entity(&mut game, &mut server, packet, player).unwrap();
+
+        assert_eq!(
+            game.ecs.get::<ItemFrameContents>(frame).unwrap().item,
+            Some(ItemStack::new(Item::Diamond, 1))
+        );
+        assert_eq!(
+            inventory.item(Area::Hotbar, 0).unwrap().clone(),
+            Some(ItemStack::new(Item::Diamond, 4))
+        );
+        let event = game.ecs.get::<InteractEntityEvent>(player).unwrap();
+        assert!(matches!(event.ty, InteractionType::Interact));
+        assert!(matches!(event.hand, Some(Hand::Main)));
+        drop(event);
+
+        let packet = InteractEntity {
+            entity_id: 100,
+            kind: InteractEntityKind::Attack,
+            sneaking: false,
+        };
+        handle_interact_entity(&mut game, &mut server, packet, player).unwrap();
+
+        assert_eq!(
+            *game.ecs.get::<ItemFrameContents>(frame).unwrap(),
+            ItemFrameContents::default()
+        );
+        let dropped: Vec<ItemStack> = game
+            .ecs
+            .query::<&ItemStack>()
+            .iter()
+            .map(|(_, item)| item.clone())
+            .collect();
+        assert_eq!(dropped, vec![ItemStack::new(Item::Diamond, 1)]);
+    }
+
+    #[test]
+    fn equip_armor_stand() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let (player, inventory, _client) = spawn_player(&mut game, &mut server);
+        *inventory.item(Area::Hotbar, 0).unwrap() = Some(ItemStack::new(Item::IronHelmet, 1));
+
+        let mut builder =
+            game.create_entity_builder(position!(2.0, 65.0, 0.0), EntityInit::ArmorStand);
+        builder.add(NetworkId(100));
+        common::entities::armor_stand::build_default(&mut builder);
+        let stand = game.spawn_entity(builder);
+
+        let packet = InteractEntity {
+            entity_id: 100,
+            kind: InteractEntityKind::InteractAt {
+                target_x: 0.0,
+                target_y: 1.0,
+                target_z: 0.0,
+                hand: 0,
+            },
+            sneaking: false,
+        };
+        handle_interact_entity(&mut game, &mut server, packet.clone(), player).unwrap();
+        assert_eq!(
+            game.ecs.get::<ArmorStandEquipment>(stand).unwrap().helmet,
+            Some(ItemStack::new(Item::IronHelmet, 1))
+        );
+        assert!(inventory.item(Area::Hotbar, 0).unwrap().is_none());
+
+        // With an empty hand, clicking the head takes the helmet back
+        let mut packet = packet;
+        if let InteractEntityKind::InteractAt { target_y, .. } = &mut packet.kind {
+            *target_y = 1.8;
+        }
+        handle_interact_entity(&mut game, &mut server, packet, player).unwrap();
+        assert_eq!(
+            *game.ecs.get::<ArmorStandEquipment>(stand).unwrap(),
+            ArmorStandEquipment::default()
+        );
+        assert_eq!(
+            inventory.item(Area::Hotbar, 0).unwrap().clone(),
+            Some(ItemStack::new(Item::IronHelmet, 1))
+        );
+    }
+
+    /// Sends a digging packet for `position` from `player`.
+    fn dig(
+        game: &mut Game,
+        server: &mut Server,
+        player: Entity,
+        status: PlayerDiggingStatus,
+        position: BlockPosition,
+    ) {
+        let packet = PlayerDigging {
+            status,
+            position,
+            face: BlockFace::Top,
+        };
+        handle_player_digging(game, server, packet, player).unwrap();
+    }
+
+    fn digging_setup() -> (Game, Server, Entity, TestClient) {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        game.world
+            .chunk_map_mut()
+            .insert_chunk(Chunk::new(ChunkPosition::new(0, 0)));
+        let (player, _, client) = spawn_player(&mut game, &mut server);
+        (game, server, player, client)
+    }
+
+    #[test]
+    fn digging_takes_time() {
+        let (mut game, mut server, player, _client) = digging_setup();
+        let stone = BlockPosition::new(1, 64, 1);
+        let torch = BlockPosition::new(2, 64, 1);
+        game.set_block(stone, BlockId::stone());
+        game.set_block(torch, BlockId::torch());
+
+        // Torches break instantly
+        dig(
+            &mut game,
+            &mut server,
+            player,
+            PlayerDiggingStatus::StartDigging,
+            torch,
+        );
+        assert_eq!(game.block(torch), Some(BlockId::air()));
+
+        // Stone takes around 150 ticks to break by hand
+        dig(
+            &mut game,
+            &mut server,
+            player,
+            PlayerDiggingStatus::StartDigging,
+            stone,
+        );
+        assert_eq!(game.block(stone), Some(BlockId::stone()));
+        game.tick_count += 10;
+        dig(
+            &mut game,
+            &mut server,
+            player,
+            PlayerDiggingStatus::FinishDigging,
+            stone,
+        );
+        assert_eq!(game.block(stone), Some(BlockId::stone()));
+
+        dig(
+            &mut game,
+            &mut server,
+            player,
+            PlayerDiggingStatus::StartDigging,
+            stone,
+        );
+        game.tick_count += 150;
+        dig(
+            &mut game,
+            &mut server,
+            player,
+            PlayerDiggingStatus::FinishDigging,
+            stone,
+        );
+        assert_eq!(game.block(stone), Some(BlockId::air()));
+    }
+
+    #[test]
+    fn rejected_digging_resends_the_block() {
+        let (mut game, mut server, player, client) = digging_setup();
+        let stone = BlockPosition::new(1, 64, 1);
+        game.set_block(stone, BlockId::stone());
+
+        // Finishing too early
+        dig(
+            &mut game,
+            &mut server,
+            player,
+            PlayerDiggingStatus::StartDigging,
+            stone,
+        );
+        game.tick_count += 10;
+        client.sent_packets.drain();
+        dig(
+            &mut game,
+            &mut server,
+            player,
+            PlayerDiggingStatus::FinishDigging,
+            stone,
+        );
+        // Finishing without starting
+        dig(
+            &mut game,
+            &mut server,
+            player,
+            PlayerDiggingStatus::FinishDigging,
+            stone,
+        );
+
+        let packets: Vec<_> = client.sent_packets.drain().collect();
+        assert_eq!(packets.len(), 2);
+        for packet in packets {
+            match packet {
+                ServerPlayPacket::BlockChange(packet) => {
+                    assert_eq!(packet.position, stone);
+                    assert_eq!(packet.block, BlockId::stone());
+                }
+                packet => panic!("unexpected packet {:?}", packet),
+            }
+        }
+    }
+
+    #[test]
+    fn creative_players_break_blocks_instantly() {
+        let (mut game, mut server, player, _client) = digging_setup();
+        game.ecs.insert(player, Gamemode::Creative).unwrap();
+        let pos = BlockPosition::new(1, 64, 1);
+        game.set_block(pos, BlockId::stone());
+
+        dig(
+            &mut game,
+            &mut server,
+            player,
+            PlayerDiggingStatus::StartDigging,
+            pos,
+        );
+        assert_eq!(game.block(pos), Some(BlockId::air()));
+    }
+
+    #[test]
+    fn attack_player() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let (player, inventory, _client) = spawn_player(&mut game, &mut server);
+        *inventory.item(Area::Hotbar, 0).unwrap() = Some(ItemStack::new(Item::IronSword, 1));
+        game.ecs.insert(player, AttackCooldown::default()).unwrap();
+        game.tick_count = 100;
+
+        let alex = server.connect_test_client("Alex", Ipv4Addr::LOCALHOST.into());
+        let network_id = server.clients.get(alex.id).unwrap().network_id();
+        let target = game.ecs.spawn((
+            alex.id,
+            network_id,
+            Gamemode::Survival,
+            position!(0.0, 65.0, 2.0),
+            Health(20.),
+            OnGround(true),
+            Velocity(Vec3d::zero()),
+        ));
+        alex.sent_packets.drain();
+
+        let packet = InteractEntity {
+            entity_id: network_id.0,
+            kind: InteractEntityKind::Attack,
+            sneaking: false,
+        };
+        handle_interact_entity(&mut game, &mut server, packet, player).unwrap();
+
+        assert_eq!(*game.ecs.get::<Health>(target).unwrap(), Health(14.));
+        match alex.sent_packets.try_recv().unwrap() {
+            ServerPlayPacket::EntityVelocity(packet) => {
+                assert_eq!(packet.entity_id, network_id.0);
+                assert_eq!(
+                    (packet.velocity_x, packet.velocity_y, packet.velocity_z),
+                    (0, 3200, 3200)
+                );
+            }
+            packet => panic!("unexpected packet {:?}", packet),
+        }
+    }
+
+    fn spawn_player(game: &mut Game, server: &mut Server) -> (Entity, Inventory, TestClient) {
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        let inventory = Inventory::player();
+        let window = Window::new(BackingWindow::Player {
+            player: inventory.new_handle(),
+        });
+        let player = game.ecs.spawn((
+            client.id,
+            inventory.new_handle(),
+            window,
+            HotbarSlot::new(0),
+            Gamemode::Survival,
+            position!(0.0, 65.0, 0.0),
+        ));
+        (player, inventory, client)
+    }
 }
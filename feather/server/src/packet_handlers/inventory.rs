@@ -1,11 +1,15 @@
-use anyhow::bail;
-use base::Gamemode;
-use common::{window::BackingWindow, Window};
-use ecs::{EntityRef, SysResult};
-use protocol::packets::client::{ClickWindow, CreativeInventoryAction};
+use anyhow::{anyhow, bail, Context};
+use base::{Gamemode, Inventory, ItemStack, Position, Text};
+use common::{events::DropItemEvent, window::BackingWindow, Game, Window};
+use ecs::{Entity, EntityRef, SysResult};
+use protocol::packets::client::{ClickWindow, CloseWindow, CreativeInventoryAction};
+use quill_common::entity_init::EntityInit;
 
 use crate::{ClientId, Server};
 
+/// The slot sent by the client when clicking outside the window.
+const OUTSIDE_WINDOW_SLOT: i16 = -999;
+
 pub fn handle_creative_inventory_action(
     player: EntityRef,
     packet: CreativeInventoryAction,
@@ -29,18 +33,39 @@ pub fn handle_creative_inventory_action(
 }
 
 pub fn handle_click_window(
+    game: &mut Game,
     server: &mut Server,
-    player: EntityRef,
+    player_id: Entity,
     packet: ClickWindow,
 ) -> SysResult {
-    let result = _handle_click_window(&player, &packet);
+    let client_id = *game.ecs.get::<ClientId>(player_id)?;
+    let client = server.clients.get(client_id).unwrap();
+    // Ignore clicks in a window that was closed in the meantime
+    if packet.window_id != client.open_window_id() {
+        return Ok(());
+    }
 
-    let client = server.clients.get(*player.get::<ClientId>()?).unwrap();
-    client.confirm_window_action(
-        packet.window_id,
-        packet.action_number as i16,
-        result.is_ok(),
-    );
+    let action_number = packet.action_number as i16;
+    let is_newer = game
+        .ecs
+        .get_mut::<Window>(player_id)?
+        .check_action_number(action_number);
+
+    // Replayed clicks are rejected. The client is resynchronized
+    // with the full window contents below either way.
+    let result = if !is_newer {
+        Err(anyhow!(
+            "window action number {} was already used",
+            action_number
+        ))
+    } else if packet.slot == OUTSIDE_WINDOW_SLOT && packet.mode == 0 {
+        drop_cursor_item(game, player_id, packet.button)
+    } else {
+        _handle_click_window(&game.ecs.entity(player_id)?, &packet)
+    };
+
+    let player = game.ecs.entity(player_id)?;
+    client.confirm_window_action(packet.window_id, action_number, result.is_ok());
 
     let window = player.get::<Window>()?;
 
@@ -76,10 +101,103 @@ fn _handle_click_window(player: &EntityRef, packet: &ClickWindow) -> SysResult {
     Ok(())
 }
 
+/// Drops the items held by the cursor after the player clicked
+/// outside the window. A left click drops the whole stack,
+/// a right click a single item.
+fn drop_cursor_item(game: &mut Game, player_id: Entity, button: i8) -> SysResult {
+    let amount = match button {
+        0 => u32::MAX,
+        1 => 1,
+        _ => bail!("unrecgonized click"),
+    };
+    let item = game
+        .ecs
+        .get_mut::<Window>(player_id)?
+        .take_cursor_item(amount);
+    match item {
+        Some(item) => drop_item(game, player_id, item),
+        None => Ok(()),
+    }
+}
+
+/// Spawns an item entity for items dropped by a player.
+fn drop_item(game: &mut Game, player_id: Entity, item: ItemStack) -> SysResult {
+    // Items are dropped from the player's eyes
+    let mut position = *game.ecs.get::<Position>(player_id)?;
+    position.y += 1.3;
+
+    let mut builder = game.create_entity_builder(position, EntityInit::Item);
+    builder.add(item.clone());
+    let entity = game.spawn_entity(builder);
+
+    game.ecs
+        .insert_entity_event(player_id, DropItemEvent { item, entity })?;
+    Ok(())
+}
+
+/// Opens `window` for a player. See [`Server::open_window`].
+pub(crate) fn open_window(
+    game: &mut Game,
+    server: &Server,
+    player_id: Entity,
+    window: Window,
+    title: impl Into<Text>,
+) -> SysResult {
+    let client_id = *game.ecs.get::<ClientId>(player_id)?;
+    let client = server
+        .clients
+        .get(client_id)
+        .context("player is disconnected")?;
+
+    // The previous window is closed without the client noticing
+    return_cursor_item(game, player_id)?;
+
+    client.open_window(&window, title)?;
+    *game.ecs.get_mut::<Window>(player_id)? = window;
+    Ok(())
+}
+
+pub fn handle_close_window(
+    game: &mut Game,
+    server: &mut Server,
+    player_id: Entity,
+    packet: CloseWindow,
+) -> SysResult {
+    let client_id = *game.ecs.get::<ClientId>(player_id)?;
+    let client = server.clients.get(client_id).unwrap();
+    if packet.window_id != client.open_window_id() {
+        return Ok(());
+    }
+
+    return_cursor_item(game, player_id)?;
+
+    let inventory = game.ecs.get::<Inventory>(player_id)?.new_handle();
+    *game.ecs.get_mut::<Window>(player_id)? =
+        Window::new(BackingWindow::Player { player: inventory });
+    client.close_window();
+    Ok(())
+}
+
+/// Moves the cursor item back into the player's inventory,
+/// dropping whatever doesn't fit.
+fn return_cursor_item(game: &mut Game, player_id: Entity) -> SysResult {
+    let leftover = game
+        .ecs
+        .get_mut::<Window>(player_id)?
+        .return_cursor_item()?;
+    match leftover {
+        Some(item) => drop_item(game, player_id, item),
+        None => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use base::{Inventory, Item, ItemStack};
+    use std::net::Ipv4Addr;
+
+    use base::{position, Area, Item};
     use common::Game;
+    use protocol::ServerPlayPacket;
 
     use super::*;
 
@@ -154,6 +272,244 @@ mod tests {
         );
     }
 
+    #[test]
+    fn click_outside_window_drops_one_item() {
+        let (mut game, mut server, player) = player_holding(ItemStack::new(Item::Diamond, 10));
+
+        handle_click_window(&mut game, &mut server, player, click_outside(1)).unwrap();
+
+        let window = game.ecs.get::<Window>(player).unwrap();
+        assert_eq!(window.cursor_item(), Some(ItemStack::new(Item::Diamond, 9)));
+        drop(window);
+        assert_eq!(dropped_items(&game), vec![ItemStack::new(Item::Diamond, 1)]);
+
+        let event = game.ecs.get::<DropItemEvent>(player).unwrap();
+        assert_eq!(event.item, ItemStack::new(Item::Diamond, 1));
+        assert_eq!(
+            game.ecs.get::<Position>(event.entity).unwrap().y,
+            65.0 + 1.3
+        );
+    }
+
+    #[test]
+    fn click_outside_window_drops_whole_stack() {
+        let (mut game, mut server, player) = player_holding(ItemStack::new(Item::Diamond, 10));
+
+        handle_click_window(&mut game, &mut server, player, click_outside(0)).unwrap();
+
+        assert_eq!(game.ecs.get::<Window>(player).unwrap().cursor_item(), None);
+        assert_eq!(
+            dropped_items(&game),
+            vec![ItemStack::new(Item::Diamond, 10)]
+        );
+    }
+
+    #[test]
+    fn click_outside_window_with_empty_cursor() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        let player = game
+            .ecs
+            .spawn((client.id, player_window(), position!(0.0, 65.0, 0.0)));
+
+        handle_click_window(&mut game, &mut server, player, click_outside(0)).unwrap();
+
+        assert!(dropped_items(&game).is_empty());
+        assert!(game.ecs.get::<DropItemEvent>(player).is_err());
+    }
+
+    #[test]
+    fn stale_action_number_is_rejected() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        let window = player_window();
+        window
+            .set_item(9, Some(ItemStack::new(Item::Diamond, 64)))
+            .unwrap();
+        let player = game
+            .ecs
+            .spawn((client.id, window, position!(0.0, 65.0, 0.0)));
+
+        // Pick up the diamonds and put them down in another slot
+        handle_click_window(&mut game, &mut server, player, left_click(9, 1)).unwrap();
+        handle_click_window(&mut game, &mut server, player, left_click(10, 2)).unwrap();
+        client.sent_packets.drain();
+
+        // Replaying the first click must not pick up the diamonds again
+        handle_click_window(&mut game, &mut server, player, left_click(10, 1)).unwrap_err();
+        let window = game.ecs.get::<Window>(player).unwrap();
+        assert_eq!(window.cursor_item(), None);
+        assert_eq!(
+            window.item(10).unwrap().clone(),
+            Some(ItemStack::new(Item::Diamond, 64))
+        );
+
+        let packets: Vec<ServerPlayPacket> = client.sent_packets.drain().collect();
+        assert!(packets.iter().any(|packet| matches!(
+            packet,
+            ServerPlayPacket::WindowConfirmation(confirmation)
+                if confirmation.action_number == 1 && !confirmation.is_accepted
+        )));
+        assert!(packets
+            .iter()
+            .any(|packet| matches!(packet, ServerPlayPacket::WindowItems(_))));
+    }
+
+    #[test]
+    fn skipped_action_numbers_are_accepted() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        let window = player_window();
+        window
+            .set_item(9, Some(ItemStack::new(Item::Diamond, 64)))
+            .unwrap();
+        let player = game
+            .ecs
+            .spawn((client.id, window, position!(0.0, 65.0, 0.0)));
+
+        handle_click_window(&mut game, &mut server, player, left_click(9, 1)).unwrap();
+        client.sent_packets.drain();
+
+        // Action numbers 2 to 4 were skipped
+        handle_click_window(&mut game, &mut server, player, left_click(10, 5)).unwrap();
+        let window = game.ecs.get::<Window>(player).unwrap();
+        assert_eq!(window.cursor_item(), None);
+        assert_eq!(
+            window.item(10).unwrap().clone(),
+            Some(ItemStack::new(Item::Diamond, 64))
+        );
+
+        let packets: Vec<ServerPlayPacket> = client.sent_packets.drain().collect();
+        assert!(packets.iter().any(|packet| matches!(
+            packet,
+            ServerPlayPacket::WindowConfirmation(confirmation)
+                if confirmation.action_number == 5 && confirmation.is_accepted
+        )));
+        assert!(packets
+            .iter()
+            .any(|packet| matches!(packet, ServerPlayPacket::WindowItems(_))));
+    }
+
+    #[test]
+    fn open_generic_9x3_window() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        let inventory = Inventory::player();
+        let player = game.ecs.spawn((
+            client.id,
+            player_window_for(&inventory),
+            inventory.new_handle(),
+            position!(0.0, 65.0, 0.0),
+        ));
+        client.sent_packets.drain();
+
+        let chest = Inventory::chest();
+        *chest.item(Area::Storage, 0).unwrap() = Some(ItemStack::new(Item::Diamond, 3));
+        let window = Window::new(BackingWindow::Generic9x3 {
+            block: chest.new_handle(),
+            player: inventory.new_handle(),
+        });
+        server
+            .open_window(&mut game, player, window, "Virtual Chest")
+            .unwrap();
+
+        let packets: Vec<ServerPlayPacket> = client.sent_packets.drain().collect();
+        let open_packet = packets
+            .iter()
+            .find_map(|packet| match packet {
+                ServerPlayPacket::OpenWindow(packet) => Some(packet),
+                _ => None,
+            })
+            .expect("no Open Window packet sent");
+        assert_eq!(open_packet.window_id, 1);
+        assert_eq!(open_packet.window_kind, 2);
+        assert!(open_packet.window_title.contains("Virtual Chest"));
+        assert!(packets.iter().any(|packet| matches!(
+            packet,
+            ServerPlayPacket::WindowItems(items) if items.window_id == 1
+        )));
+
+        // Clicks in the player's own inventory are stale and ignored
+        handle_click_window(&mut game, &mut server, player, left_click(0, 1)).unwrap();
+        assert!(chest.item(Area::Storage, 0).unwrap().is_some());
+
+        // Clicks in the new window apply to the chest
+        let mut click = left_click(0, 1);
+        click.window_id = 1;
+        handle_click_window(&mut game, &mut server, player, click).unwrap();
+        assert!(chest.item(Area::Storage, 0).unwrap().is_none());
+        assert_eq!(
+            game.ecs.get::<Window>(player).unwrap().cursor_item(),
+            Some(ItemStack::new(Item::Diamond, 3))
+        );
+
+        // Closing the window returns the cursor item to the player
+        handle_close_window(&mut game, &mut server, player, CloseWindow { window_id: 1 }).unwrap();
+        assert_eq!(
+            inventory.item(Area::Hotbar, 0).unwrap().clone(),
+            Some(ItemStack::new(Item::Diamond, 3))
+        );
+        let window = game.ecs.get::<Window>(player).unwrap();
+        assert_eq!(window.cursor_item(), None);
+        assert!(matches!(window.inner(), BackingWindow::Player { .. }));
+        assert_eq!(server.clients.get(client.id).unwrap().open_window_id(), 0);
+    }
+
+    /// Spawns a player whose cursor holds `item`.
+    fn player_holding(item: ItemStack) -> (Game, Server, Entity) {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+
+        let mut window = player_window();
+        window.set_item(9, Some(item)).unwrap();
+        window.left_click(9).unwrap();
+        let player = game
+            .ecs
+            .spawn((client.id, window, position!(0.0, 65.0, 0.0)));
+        (game, server, player)
+    }
+
+    fn left_click(slot: i16, action_number: u16) -> ClickWindow {
+        ClickWindow {
+            window_id: 0,
+            slot,
+            button: 0,
+            action_number,
+            mode: 0,
+            clicked_item: None,
+        }
+    }
+
+    fn click_outside(button: i8) -> ClickWindow {
+        ClickWindow {
+            window_id: 0,
+            slot: OUTSIDE_WINDOW_SLOT,
+            button,
+            action_number: 1,
+            mode: 0,
+            clicked_item: None,
+        }
+    }
+
+    fn dropped_items(game: &Game) -> Vec<ItemStack> {
+        game.ecs
+            .query::<&ItemStack>()
+            .iter()
+            .map(|(_, item)| item.clone())
+            .collect()
+    }
+
+    fn player_window_for(inventory: &Inventory) -> Window {
+        Window::new(BackingWindow::Player {
+            player: inventory.new_handle(),
+        })
+    }
+
     fn player_window() -> Window {
         Window::new(BackingWindow::Player {
             player: Inventory::player(),
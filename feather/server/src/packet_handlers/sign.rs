@@ -0,0 +1,171 @@
+use base::{Position, Text};
+use common::{block_entity::BlockEntity, Game};
+use ecs::{Entity, SysResult};
+use protocol::packets::client::UpdateSign;
+
+use crate::Server;
+
+/// Maximum number of characters kept per sign line.
+const MAX_LINE_LENGTH: usize = 384;
+
+/// Players can only edit signs within this distance.
+const MAX_EDIT_DISTANCE: f64 = 8.;
+
+/// Handles the text a player entered after placing a sign.
+///
+/// The lines are sanitized, stored in the sign's block entity
+/// and sent to all players who can see the sign.
+pub fn handle_update_sign(
+    game: &mut Game,
+    server: &mut Server,
+    player: Entity,
+    packet: UpdateSign,
+) -> SysResult {
+    let player_pos = *game.ecs.get::<Position>(player)?;
+    let sign_pos = packet.position.position();
+    if player_pos.distance_squared_to(sign_pos) > MAX_EDIT_DISTANCE * MAX_EDIT_DISTANCE {
+        log::debug!(
+            "Ignoring sign update at {:?}: too far from the player",
+            packet.position
+        );
+        return Ok(());
+    }
+
+    let sign = match game.world.block_entities_mut().get_mut(packet.position) {
+        Some(BlockEntity::Sign(sign)) => sign,
+        _ => {
+            log::debug!("Ignoring sign update at {:?}: no sign", packet.position);
+            return Ok(());
+        }
+    };
+    let lines = [packet.line_1, packet.line_2, packet.line_3, packet.line_4];
+    for (line, new_line) in sign.lines.iter_mut().zip(lines.iter()) {
+        *line = Text::from(sanitize_line(new_line));
+    }
+
+    let data = BlockEntity::Sign(sign.clone()).to_data(packet.position);
+    server.broadcast_nearby_with(sign_pos, |client| client.send_block_entity_data(&data));
+    Ok(())
+}
+
+/// Removes formatting codes and control characters from
+/// a sign line and truncates it to [`MAX_LINE_LENGTH`].
+fn sanitize_line(line: &str) -> String {
+    let mut sanitized = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            // Skip the formatting code
+            chars.next();
+        } else if !c.is_control() {
+            sanitized.push(c);
+        }
+    }
+    sanitized.chars().take(MAX_LINE_LENGTH).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use base::{position, BlockId, BlockPosition, Chunk, ChunkPosition};
+    use common::block_entity::Sign;
+    use protocol::ServerPlayPacket;
+
+    use super::*;
+    use crate::testing::TestClient;
+
+    const SIGN_POS: BlockPosition = BlockPosition { x: 2, y: 64, z: 3 };
+
+    fn setup() -> (Game, Server, Entity, TestClient) {
+        let mut game = Game::new();
+        game.world
+            .chunk_map_mut()
+            .insert_chunk(Chunk::new(ChunkPosition::new(0, 0)));
+        game.set_block(SIGN_POS, BlockId::oak_sign());
+
+        let mut server = Server::for_testing();
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        server
+            .chunk_subscriptions
+            .subscribe(ChunkPosition::new(0, 0), client.id);
+        client.sent_packets.drain();
+
+        let player = game.ecs.spawn((client.id, position!(1.0, 64.0, 1.0)));
+        (game, server, player, client)
+    }
+
+    fn update_sign(lines: [&str; 4]) -> UpdateSign {
+        UpdateSign {
+            position: SIGN_POS,
+            line_1: lines[0].to_owned(),
+            line_2: lines[1].to_owned(),
+            line_3: lines[2].to_owned(),
+            line_4: lines[3].to_owned(),
+        }
+    }
+
+    fn sign(game: &Game) -> Sign {
+        match game.world.block_entities().get(SIGN_POS) {
+            Some(BlockEntity::Sign(sign)) => sign.clone(),
+            other => panic!("expected a sign, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sign_text_is_stored_and_broadcast() {
+        let (mut game, mut server, player, client) = setup();
+        let packet = update_sign(["Hello", "", "world", "!"]);
+        handle_update_sign(&mut game, &mut server, player, packet).unwrap();
+
+        let expected = [
+            Text::from("Hello"),
+            Text::empty(),
+            Text::from("world"),
+            Text::from("!"),
+        ];
+        assert_eq!(sign(&game).lines, expected);
+
+        let packets: Vec<_> = client.sent_packets.drain().collect();
+        assert_eq!(packets.len(), 1);
+        match &packets[0] {
+            ServerPlayPacket::BlockEntityData(packet) => {
+                assert_eq!(packet.position, SIGN_POS);
+                assert_eq!(packet.action, 9);
+                assert_eq!(
+                    packet.data.0.get("Text1"),
+                    Some(&nbt::Value::String("\"Hello\"".to_owned()))
+                );
+            }
+            packet => panic!("unexpected packet {:?}", packet),
+        }
+    }
+
+    #[test]
+    fn invalid_sign_text_is_sanitized() {
+        let (mut game, mut server, player, _client) = setup();
+        let long_line = "a".repeat(1000);
+        let packet = update_sign(["§cRed", "tab\there", &long_line, "§"]);
+        handle_update_sign(&mut game, &mut server, player, packet).unwrap();
+
+        let lines = sign(&game).lines;
+        assert_eq!(lines[0], Text::from("Red"));
+        assert_eq!(lines[1], Text::from("tabhere"));
+        assert_eq!(lines[2], Text::from("a".repeat(MAX_LINE_LENGTH)));
+        assert_eq!(lines[3], Text::empty());
+    }
+
+    #[test]
+    fn distant_or_missing_signs_are_not_edited() {
+        let (mut game, mut server, player, client) = setup();
+        *game.ecs.get_mut::<Position>(player).unwrap() = position!(100.0, 64.0, 100.0);
+        handle_update_sign(&mut game, &mut server, player, update_sign(["x"; 4])).unwrap();
+        assert_eq!(sign(&game), Sign::default());
+
+        *game.ecs.get_mut::<Position>(player).unwrap() = position!(1.0, 64.0, 1.0);
+        let mut packet = update_sign(["x"; 4]);
+        packet.position = BlockPosition::new(5, 64, 5);
+        handle_update_sign(&mut game, &mut server, player, packet).unwrap();
+        assert!(client.sent_packets.try_recv().is_err());
+    }
+}
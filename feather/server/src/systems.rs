@@ -2,8 +2,10 @@
 
 mod block;
 mod chat;
+mod command_block;
 mod entity;
 mod particle;
+mod player_death;
 mod player_join;
 mod player_leave;
 mod plugin_message;
@@ -30,12 +32,15 @@ pub fn register(server: Server, game: &mut Game, systems: &mut SystemExecutor<Ga
     view::register(game, systems);
     crate::chunk_subscriptions::register(systems);
     player_leave::register(systems);
+    player_death::register(systems);
     tablist::register(systems);
     block::register(systems);
+    command_block::register(systems);
     entity::register(game, systems);
     chat::register(game, systems);
     particle::register(systems);
     plugin_message::register(systems);
+    crate::metrics::register(systems);
 
     systems.group::<Server>().add_system(tick_clients);
 }
@@ -14,7 +14,10 @@
 //! the above three options to achieve ideal performance.
 
 use ahash::AHashMap;
-use base::{chunk::SECTION_VOLUME, position, ChunkPosition, CHUNK_WIDTH};
+use base::{
+    chunk::{SECTION_HEIGHT, SECTION_VOLUME},
+    position, BlockId, BlockPosition, ChunkPosition, CHUNK_WIDTH,
+};
 use common::{events::BlockChangeEvent, Game};
 use ecs::{SysResult, SystemExecutor};
 
@@ -27,7 +30,14 @@ pub fn register(systems: &mut SystemExecutor<Game>) {
 }
 
 fn broadcast_block_changes(game: &mut Game, server: &mut Server) -> SysResult {
-    for (_, event) in game.ecs.query::<&BlockChangeEvent>().iter() {
+    let mut events: Vec<_> = game
+        .ecs
+        .query::<&BlockChangeEvent>()
+        .iter()
+        .map(|(entity, event)| (entity, event.clone()))
+        .collect();
+    game.sort_for_tick(&mut events);
+    for (_, event) in &events {
         broadcast_block_change(event, game, server);
     }
     Ok(())
@@ -55,6 +65,11 @@ fn broadcast_block_change_chunk_overwrite(
         sections.entry(chunk).or_default().push(section + 1); // + 1 to account for the void air chunk
     }
 
+    let mut sections: Vec<_> = sections.into_iter().collect();
+    if game.deterministic_ticking {
+        sections.sort_by_key(|(chunk, _)| (chunk.x, chunk.z));
+    }
+
     for (chunk_pos, sections) in sections {
         let chunk = game.world.chunk_map().chunk_handle_at(chunk_pos);
         if let Some(chunk) = chunk {
@@ -71,12 +86,82 @@ fn broadcast_block_change_chunk_overwrite(
 }
 
 fn broadcast_block_change_simple(event: &BlockChangeEvent, game: &Game, server: &mut Server) {
+    let mut sections: AHashMap<(ChunkPosition, usize), Vec<(BlockPosition, BlockId)>> =
+        AHashMap::new();
     for pos in event.iter_changed_blocks() {
-        let new_block = game.block(pos);
-        if let Some(new_block) = new_block {
-            server.broadcast_nearby_with(pos.position(), |client| {
-                client.send_block_change(pos, new_block)
+        if let Some(new_block) = game.block(pos) {
+            sections
+                .entry((pos.chunk(), pos.y as usize / SECTION_HEIGHT))
+                .or_default()
+                .push((pos, new_block));
+        }
+    }
+
+    let mut sections: Vec<_> = sections.into_iter().collect();
+    if game.deterministic_ticking {
+        sections.sort_by_key(|((chunk, section), _)| (chunk.x, chunk.z, *section));
+    }
+
+    for ((chunk, section), blocks) in sections {
+        let position = blocks[0].0.position();
+        if let [(pos, new_block)] = blocks[..] {
+            server
+                .broadcast_nearby_with(position, |client| client.send_block_change(pos, new_block));
+        } else {
+            server.broadcast_nearby_with(position, |client| {
+                client.send_multi_block_change(chunk, section, &blocks)
             });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use base::Chunk;
+    use protocol::ServerPlayPacket;
+
+    use super::*;
+
+    #[test]
+    fn small_bulk_changes_are_batched_per_section() {
+        let mut game = Game::new();
+        game.deterministic_ticking = true;
+        game.world
+            .chunk_map_mut()
+            .insert_chunk(Chunk::new(ChunkPosition::new(0, 0)));
+        let mut server = Server::for_testing();
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        server
+            .chunk_subscriptions
+            .subscribe(ChunkPosition::new(0, 0), client.id);
+        client.sent_packets.drain();
+
+        // Two blocks in section 4, one in section 5
+        game.set_blocks(vec![
+            (BlockPosition::new(1, 64, 2), BlockId::stone()),
+            (BlockPosition::new(3, 65, 4), BlockId::stone()),
+            (BlockPosition::new(1, 80, 2), BlockId::stone()),
+        ]);
+        broadcast_block_changes(&mut game, &mut server).unwrap();
+
+        let packets: Vec<_> = client.sent_packets.drain().collect();
+        assert_eq!(packets.len(), 2);
+        match &packets[0] {
+            ServerPlayPacket::MultiBlockChange(packet) => {
+                assert_eq!(packet.chunk_section_coordinate, 4);
+                let stone = i64::from(BlockId::stone().vanilla_id()) << 12;
+                let records: Vec<i64> = packet.records.0.iter().map(|record| record.0).collect();
+                assert_eq!(records, vec![stone | 0x120, stone | 0x341]);
+            }
+            packet => panic!("unexpected packet {:?}", packet),
+        }
+        match &packets[1] {
+            ServerPlayPacket::BlockChange(packet) => {
+                assert_eq!(packet.position, BlockPosition::new(1, 80, 2));
+            }
+            packet => panic!("unexpected packet {:?}", packet),
+        }
+    }
+}
@@ -0,0 +1,397 @@
+//! Runs the commands stored in command blocks.
+//!
+//! Impulse command blocks run once when activated,
+//! repeating command blocks run every tick while active,
+//! and chain command blocks run after the command block
+//! pointing into them. Command blocks only run if
+//! [`Options::enable_command_blocks`](crate::Options::enable_command_blocks) is set.
+//!
+//! Redstone isn't simulated: a command block is powered
+//! while a power source (a redstone block, an active lever,
+//! button or pressure plate, or a lit redstone torch) is
+//! directly next to it. Plugins may also set
+//! [`CommandBlock::powered`] themselves.
+
+use base::{BlockPosition, Text};
+use blocks::{BlockId, BlockKind, FacingCubic, SimplifiedBlockKind};
+use common::{
+    block_entity::{BlockEntity, CommandBlock},
+    chat::{ChatBox, ChatPreference},
+    events::BlockChangeEvent,
+    Game,
+};
+use ecs::{SysResult, SystemExecutor};
+
+use crate::{commands, commands::CommandBlockSender, Server};
+
+/// Maximum number of chain command blocks run after
+/// a single impulse or repeating command block.
+const MAX_CHAIN_LENGTH: usize = 65536;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .add_system(update_command_block_power)
+        .group::<Server>()
+        .add_system(run_command_blocks);
+}
+
+/// Updates whether command blocks next to
+/// changed blocks are powered.
+fn update_command_block_power(game: &mut Game) -> SysResult {
+    let mut positions = Vec::new();
+    for (_, event) in game.ecs.query::<&BlockChangeEvent>().iter() {
+        for pos in event.iter_changed_blocks() {
+            positions.push(pos);
+            positions.extend(neighbors(pos));
+        }
+    }
+    positions.sort_unstable();
+    positions.dedup();
+
+    for pos in positions {
+        if !matches!(
+            game.world.block_entities().get(pos),
+            Some(BlockEntity::CommandBlock(_))
+        ) {
+            continue;
+        }
+        let powered = neighbors(pos)
+            .iter()
+            .any(|&neighbor| game.block(neighbor).map_or(false, is_power_source));
+        if let Some(BlockEntity::CommandBlock(command_block)) =
+            game.world.block_entities_mut().get_mut(pos)
+        {
+            command_block.powered = powered;
+        }
+    }
+
+    Ok(())
+}
+
+fn is_power_source(block: BlockId) -> bool {
+    match block.simplified_kind() {
+        SimplifiedBlockKind::RedstoneBlock => true,
+        SimplifiedBlockKind::RedstoneTorch | SimplifiedBlockKind::RedstoneWallTorch => {
+            block.lit() == Some(true)
+        }
+        SimplifiedBlockKind::Lever
+        | SimplifiedBlockKind::StoneButton
+        | SimplifiedBlockKind::WoodenButton
+        | SimplifiedBlockKind::StonePressurePlate
+        | SimplifiedBlockKind::WoodenPressurePlate => block.powered() == Some(true),
+        SimplifiedBlockKind::LightWeightedPressurePlate
+        | SimplifiedBlockKind::HeavyWeightedPressurePlate => block.power().unwrap_or(0) > 0,
+        _ => false,
+    }
+}
+
+fn neighbors(pos: BlockPosition) -> [BlockPosition; 6] {
+    [
+        offset(pos, FacingCubic::North),
+        offset(pos, FacingCubic::East),
+        offset(pos, FacingCubic::South),
+        offset(pos, FacingCubic::West),
+        offset(pos, FacingCubic::Up),
+        offset(pos, FacingCubic::Down),
+    ]
+}
+
+fn run_command_blocks(game: &mut Game, server: &mut Server) -> SysResult {
+    if !server.options.enable_command_blocks {
+        return Ok(());
+    }
+
+    let mut to_run = Vec::new();
+    for (pos, block_entity) in game.world.block_entities().iter() {
+        let command_block = match block_entity {
+            BlockEntity::CommandBlock(command_block) => command_block,
+            _ => continue,
+        };
+        match game.block(pos).map(|block| block.kind()) {
+            Some(BlockKind::CommandBlock) => {
+                if command_block.is_active() != command_block.triggered {
+                    to_run.push(pos);
+                }
+            }
+            Some(BlockKind::RepeatingCommandBlock) => {
+                if command_block.is_active() {
+                    to_run.push(pos);
+                }
+            }
+            _ => (),
+        }
+    }
+    if game.deterministic_ticking {
+        to_run.sort_unstable();
+    }
+
+    for pos in to_run {
+        if let Some(BlockEntity::CommandBlock(command_block)) =
+            game.world.block_entities_mut().get_mut(pos)
+        {
+            // Impulse command blocks are re-armed once deactivated.
+            command_block.triggered = command_block.is_active();
+            if !command_block.is_active() {
+                continue;
+            }
+        }
+        run_chain(game, server, pos)?;
+    }
+
+    Ok(())
+}
+
+/// Runs the command block at `start` followed by
+/// the chain command blocks it points into.
+fn run_chain(game: &mut Game, server: &mut Server, start: BlockPosition) -> SysResult {
+    let mut pos = start;
+    let mut success = execute(game, server, pos)?;
+
+    for _ in 0..MAX_CHAIN_LENGTH {
+        let facing = match game.block(pos).and_then(|block| block.facing_cubic()) {
+            Some(facing) => facing,
+            None => break,
+        };
+        pos = offset(pos, facing);
+
+        let block = match game.block(pos) {
+            Some(block) if block.kind() == BlockKind::ChainCommandBlock => block,
+            _ => break,
+        };
+        let command_block = match game.world.block_entities_mut().get_mut(pos) {
+            Some(BlockEntity::CommandBlock(command_block)) => command_block,
+            _ => break,
+        };
+        if !command_block.is_active() {
+            break;
+        }
+
+        // Conditional command blocks only run if
+        // the previous command block succeeded.
+        command_block.condition_met = success || block.conditional() != Some(true);
+        success = if command_block.condition_met {
+            execute(game, server, pos)?
+        } else {
+            false
+        };
+    }
+
+    Ok(())
+}
+
+/// Runs the command of the command block at `pos`,
+/// returning whether it succeeded.
+fn execute(game: &mut Game, server: &mut Server, pos: BlockPosition) -> anyhow::Result<bool> {
+    let command = match game.world.block_entities().get(pos) {
+        Some(BlockEntity::CommandBlock(command_block)) => command_block.command.clone(),
+        _ => return Ok(false),
+    };
+    let command = command.trim().trim_start_matches('/');
+
+    let mut success = false;
+    let mut output = None;
+    if !command.is_empty() {
+        let sender = game.ecs.spawn((
+            CommandBlockSender { position: pos },
+            pos.position(),
+            ChatBox::new(ChatPreference::All),
+        ));
+        success = commands::run(game, server, sender, command);
+        output = game
+            .ecs
+            .get_mut::<ChatBox>(sender)?
+            .drain()
+            .last()
+            .map(|message| message.text().clone());
+        game.ecs.despawn(sender)?;
+    }
+
+    let tick = game.tick_count as i64;
+    if let Some(BlockEntity::CommandBlock(command_block)) =
+        game.world.block_entities_mut().get_mut(pos)
+    {
+        update_after_execution(command_block, success, output, tick);
+    }
+    Ok(success)
+}
+
+fn update_after_execution(
+    command_block: &mut CommandBlock,
+    success: bool,
+    output: Option<Text>,
+    tick: i64,
+) {
+    command_block.success_count = success as i32;
+    command_block.last_execution = tick;
+    if command_block.track_output {
+        command_block.last_output = output.map(|text| text.to_string()).unwrap_or_default();
+    }
+}
+
+fn offset(pos: BlockPosition, facing: FacingCubic) -> BlockPosition {
+    let (x, y, z) = match facing {
+        FacingCubic::North => (0, 0, -1),
+        FacingCubic::East => (1, 0, 0),
+        FacingCubic::South => (0, 0, 1),
+        FacingCubic::West => (-1, 0, 0),
+        FacingCubic::Up => (0, 1, 0),
+        FacingCubic::Down => (0, -1, 0),
+    };
+    BlockPosition::new(pos.x + x, pos.y + y, pos.z + z)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use base::{BlockId, Chunk, ChunkPosition};
+
+    use super::*;
+
+    const POS: BlockPosition = BlockPosition { x: 1, y: 64, z: 1 };
+
+    fn setup(enable_command_blocks: bool) -> (Game, Server) {
+        let mut game = Game::new();
+        game.world
+            .chunk_map_mut()
+            .insert_chunk(Chunk::new(ChunkPosition::new(0, 0)));
+        game.set_block(POS, BlockId::command_block());
+        set_command_block(&mut game, POS, "say hello");
+
+        let mut server = Server::for_testing();
+        Arc::make_mut(&mut server.options).enable_command_blocks = enable_command_blocks;
+        (game, server)
+    }
+
+    fn set_command_block(game: &mut Game, pos: BlockPosition, command: &str) {
+        let command_block = command_block(game, pos);
+        command_block.command = command.to_owned();
+        command_block.track_output = true;
+    }
+
+    fn command_block(game: &mut Game, pos: BlockPosition) -> &mut CommandBlock {
+        match game.world.block_entities_mut().get_mut(pos) {
+            Some(BlockEntity::CommandBlock(command_block)) => command_block,
+            other => panic!("expected a command block, got {:?}", other),
+        }
+    }
+
+    /// Runs a tick and returns the messages received by `listener`.
+    fn tick(game: &mut Game, server: &mut Server, listener: ecs::Entity) -> Vec<Text> {
+        run_command_blocks(game, server).unwrap();
+        game.ecs
+            .get_mut::<ChatBox>(listener)
+            .unwrap()
+            .drain()
+            .map(|message| message.text().clone())
+            .collect()
+    }
+
+    fn announcement(message: &str) -> Text {
+        Text::translate_with(
+            "chat.type.announcement",
+            vec!["@".to_owned(), message.to_owned()],
+        )
+    }
+
+    #[test]
+    fn impulse_command_block_runs_once_when_powered() {
+        let (mut game, mut server) = setup(true);
+        let listener = game.ecs.spawn((ChatBox::new(ChatPreference::All),));
+
+        let messages = tick(&mut game, &mut server, listener);
+        assert!(messages.is_empty());
+
+        command_block(&mut game, POS).powered = true;
+        let messages = tick(&mut game, &mut server, listener);
+        assert_eq!(messages, vec![announcement("hello")]);
+        assert_eq!(command_block(&mut game, POS).success_count, 1);
+        assert_eq!(
+            command_block(&mut game, POS).last_output,
+            announcement("hello").to_string()
+        );
+
+        // Stays powered: doesn't run again
+        let messages = tick(&mut game, &mut server, listener);
+        assert!(messages.is_empty());
+
+        // Power cycle
+        command_block(&mut game, POS).powered = false;
+        tick(&mut game, &mut server, listener);
+        command_block(&mut game, POS).powered = true;
+        let messages = tick(&mut game, &mut server, listener);
+        assert_eq!(messages, vec![announcement("hello")]);
+    }
+
+    #[test]
+    fn adjacent_power_sources_power_command_blocks() {
+        let (mut game, mut server) = setup(true);
+        let listener = game.ecs.spawn((ChatBox::new(ChatPreference::All),));
+        let above = BlockPosition::new(POS.x, POS.y + 1, POS.z);
+
+        game.set_block(above, BlockId::redstone_block());
+        update_command_block_power(&mut game).unwrap();
+        assert!(command_block(&mut game, POS).powered);
+        let messages = tick(&mut game, &mut server, listener);
+        assert_eq!(messages, vec![announcement("hello")]);
+
+        game.set_block(above, BlockId::lever().with_powered(false));
+        update_command_block_power(&mut game).unwrap();
+        assert!(!command_block(&mut game, POS).powered);
+        tick(&mut game, &mut server, listener);
+
+        game.set_block(above, BlockId::lever().with_powered(true));
+        update_command_block_power(&mut game).unwrap();
+        assert!(command_block(&mut game, POS).powered);
+        let messages = tick(&mut game, &mut server, listener);
+        assert_eq!(messages, vec![announcement("hello")]);
+    }
+
+    #[test]
+    fn chain_command_blocks_follow_facing() {
+        let (mut game, mut server) = setup(true);
+        let listener = game.ecs.spawn((ChatBox::new(ChatPreference::All),));
+        let chain_pos = BlockPosition::new(POS.x + 1, POS.y, POS.z);
+        game.set_block(
+            POS,
+            BlockId::repeating_command_block().with_facing_cubic(FacingCubic::East),
+        );
+        set_command_block(&mut game, POS, "say first");
+        game.set_block(chain_pos, BlockId::chain_command_block());
+        set_command_block(&mut game, chain_pos, "say second");
+        command_block(&mut game, chain_pos).auto = true;
+        command_block(&mut game, POS).auto = true;
+
+        let messages = tick(&mut game, &mut server, listener);
+        assert_eq!(
+            messages,
+            vec![announcement("first"), announcement("second")]
+        );
+        // Repeating command blocks run every tick
+        let messages = tick(&mut game, &mut server, listener);
+        assert_eq!(messages.len(), 2);
+    }
+
+    #[test]
+    fn command_blocks_are_inert_when_disabled() {
+        let (mut game, mut server) = setup(false);
+        let listener = game.ecs.spawn((ChatBox::new(ChatPreference::All),));
+        command_block(&mut game, POS).powered = true;
+
+        let messages = tick(&mut game, &mut server, listener);
+        assert!(messages.is_empty());
+        assert_eq!(command_block(&mut game, POS).success_count, 0);
+    }
+
+    #[test]
+    fn command_blocks_cannot_run_admin_commands() {
+        let (mut game, mut server) = setup(true);
+        let listener = game.ecs.spawn((ChatBox::new(ChatPreference::All),));
+        set_command_block(&mut game, POS, "ban-ip 10.0.0.1");
+        command_block(&mut game, POS).powered = true;
+
+        tick(&mut game, &mut server, listener);
+        assert!(server.ban_list().ips().is_empty());
+        assert_eq!(command_block(&mut game, POS).success_count, 0);
+    }
+}
@@ -8,10 +8,14 @@ use quill_common::components::OnGround;
 
 use crate::{entities::PreviousPosition, NetworkId, Server};
 
+mod equipment;
 mod spawn_packet;
+mod status_effects;
 
 pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
     spawn_packet::register(game, systems);
+    equipment::register(systems);
+    status_effects::register(systems);
     systems.group::<Server>().add_system(send_entity_movement);
 }
 
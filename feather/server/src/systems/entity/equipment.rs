@@ -0,0 +1,153 @@
+//! Sends the items held and worn by players to
+//! the clients tracking them.
+
+use base::Inventory;
+use common::{entities::player::HotbarSlot, Game};
+use ecs::{SysResult, SystemExecutor};
+use protocol::packets::server::EquipmentEntry;
+
+use crate::{
+    entities::{equipment_item, PreviousEquipment, EQUIPMENT_SLOTS},
+    NetworkId, Server,
+};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.group::<Server>().add_system(send_equipment_changes);
+}
+
+/// System to send equipment updates when a player
+/// switches their held item or changes their armor.
+fn send_equipment_changes(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (inventory, &hotbar_slot, &network_id, previous)) in game
+        .ecs
+        .query::<(&Inventory, &HotbarSlot, &NetworkId, &mut PreviousEquipment)>()
+        .iter()
+    {
+        let mut changes = Vec::new();
+        for (slot, previous_item) in EQUIPMENT_SLOTS.iter().zip(previous.0.iter_mut()) {
+            let item = equipment_item(inventory, hotbar_slot, slot);
+            if item != *previous_item {
+                *previous_item = item.clone();
+                changes.push(EquipmentEntry {
+                    slot: slot.clone(),
+                    item,
+                });
+            }
+        }
+
+        if !changes.is_empty() {
+            for client in server.clients.iter() {
+                if client.network_id() != network_id && client.is_entity_loaded(network_id) {
+                    client.send_entity_equipment(network_id, changes.clone());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use base::{Area, Item, ItemStack, Position};
+    use ecs::Entity;
+    use protocol::{packets::server::EquipmentSlot, ServerPlayPacket};
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::testing::TestClient;
+
+    #[test]
+    fn held_item_and_armor_changes_are_broadcast() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let steve = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        let observer = server.connect_test_client("Alex", Ipv4Addr::LOCALHOST.into());
+
+        let inventory = Inventory::player();
+        let player = spawn_player(&mut game, &server, &steve, &inventory);
+        let network_id = *game.ecs.get::<NetworkId>(player).unwrap();
+        server.clients.get(observer.id).unwrap().send_player(
+            network_id,
+            Uuid::from_u128(1),
+            Position::default(),
+        );
+        observer.sent_packets.drain();
+
+        *inventory.item(Area::Hotbar, 0).unwrap() = Some(ItemStack::new(Item::IronSword, 1));
+        *inventory.item(Area::Helmet, 0).unwrap() = Some(ItemStack::new(Item::IronHelmet, 1));
+        send_equipment_changes(&mut game, &mut server).unwrap();
+
+        let entries = equipment_packets(&observer, network_id);
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(entries[0].slot, EquipmentSlot::MainHand));
+        assert_eq!(entries[0].item, Some(ItemStack::new(Item::IronSword, 1)));
+        assert!(matches!(entries[1].slot, EquipmentSlot::Helmet));
+        assert_eq!(entries[1].item, Some(ItemStack::new(Item::IronHelmet, 1)));
+        // A player doesn't track themselves
+        assert!(equipment_packets(&steve, network_id).is_empty());
+
+        // Nothing changed
+        send_equipment_changes(&mut game, &mut server).unwrap();
+        assert!(equipment_packets(&observer, network_id).is_empty());
+
+        // Switching to an empty hotbar slot
+        game.ecs
+            .get_mut::<HotbarSlot>(player)
+            .unwrap()
+            .set(1)
+            .unwrap();
+        send_equipment_changes(&mut game, &mut server).unwrap();
+        let entries = equipment_packets(&observer, network_id);
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(entries[0].slot, EquipmentSlot::MainHand));
+        assert_eq!(entries[0].item, None);
+    }
+
+    #[test]
+    fn untracked_players_are_skipped() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let steve = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        let observer = server.connect_test_client("Alex", Ipv4Addr::LOCALHOST.into());
+
+        let inventory = Inventory::player();
+        let player = spawn_player(&mut game, &server, &steve, &inventory);
+        let network_id = *game.ecs.get::<NetworkId>(player).unwrap();
+        observer.sent_packets.drain();
+
+        *inventory.item(Area::Boots, 0).unwrap() = Some(ItemStack::new(Item::IronBoots, 1));
+        send_equipment_changes(&mut game, &mut server).unwrap();
+        assert!(equipment_packets(&observer, network_id).is_empty());
+    }
+
+    fn spawn_player(
+        game: &mut Game,
+        server: &Server,
+        client: &TestClient,
+        inventory: &Inventory,
+    ) -> Entity {
+        let network_id = server.clients.get(client.id).unwrap().network_id();
+        game.ecs.spawn((
+            inventory.new_handle(),
+            HotbarSlot::default(),
+            network_id,
+            PreviousEquipment::default(),
+        ))
+    }
+
+    fn equipment_packets(client: &TestClient, network_id: NetworkId) -> Vec<EquipmentEntry> {
+        client
+            .sent_packets
+            .drain()
+            .filter_map(|packet| match packet {
+                ServerPlayPacket::EntityEquipment(packet) if packet.entity_id == network_id.0 => {
+                    Some(packet.entries)
+                }
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+}
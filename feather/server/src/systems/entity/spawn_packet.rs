@@ -75,6 +75,7 @@ fn send_entities_when_created(game: &mut Game, server: &mut Server) -> SysResult
 }
 
 /// System to unload an entity on clients when it is removed.
+/// Also frees the entity's network ID.
 fn unload_entities_when_removed(game: &mut Game, server: &mut Server) -> SysResult {
     for (_, (_event, &position, &network_id)) in game
         .ecs
@@ -82,6 +83,7 @@ fn unload_entities_when_removed(game: &mut Game, server: &mut Server) -> SysResu
         .iter()
     {
         server.broadcast_nearby_with(position, |client| client.unload_entity(network_id));
+        server.free_network_id(network_id);
     }
 
     Ok(())
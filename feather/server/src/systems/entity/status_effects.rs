@@ -0,0 +1,114 @@
+//! Sends status effect changes to the affected
+//! player and the clients tracking the entity.
+
+use common::{effects::StatusEffects, Game};
+use ecs::{SysResult, SystemExecutor};
+
+use crate::{entities::PreviousStatusEffects, NetworkId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(send_status_effect_changes);
+}
+
+/// System to send effect packets when an effect
+/// is added, changed, or removed.
+fn send_status_effect_changes(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (effects, &network_id, previous)) in game
+        .ecs
+        .query::<(&StatusEffects, &NetworkId, &mut PreviousStatusEffects)>()
+        .iter()
+    {
+        // Durations only decrease on their own, so a longer
+        // duration means the effect was given again.
+        let added: Vec<_> = effects
+            .iter()
+            .filter(|instance| {
+                !previous.0.iter().any(|old| {
+                    old.effect == instance.effect
+                        && old.amplifier == instance.amplifier
+                        && old.duration >= instance.duration
+                })
+            })
+            .collect();
+        let removed: Vec<_> = previous
+            .0
+            .iter()
+            .filter(|old| effects.get(old.effect).is_none())
+            .map(|old| old.effect)
+            .collect();
+        if !added.is_empty() || !removed.is_empty() {
+            for client in server.clients.iter() {
+                if client.network_id() != network_id && !client.is_entity_loaded(network_id) {
+                    continue;
+                }
+                for &effect in &removed {
+                    client.send_remove_entity_effect(network_id, effect);
+                }
+                for instance in &added {
+                    client.send_entity_effect(network_id, instance);
+                }
+            }
+        }
+        previous.0 = effects.iter().copied().collect();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use common::effects::{StatusEffect, StatusEffectInstance};
+    use protocol::ServerPlayPacket;
+
+    use super::*;
+
+    #[test]
+    fn effect_changes_are_sent() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let steve = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        let network_id = server.clients.get(steve.id).unwrap().network_id();
+        let entity = game.ecs.spawn((
+            network_id,
+            StatusEffects::new(),
+            PreviousStatusEffects::default(),
+        ));
+        steve.sent_packets.drain();
+
+        game.ecs
+            .get_mut::<StatusEffects>(entity)
+            .unwrap()
+            .add(StatusEffectInstance::new(StatusEffect::Speed, 1, 200));
+        send_status_effect_changes(&mut game, &mut server).unwrap();
+        match steve.sent_packets.try_recv().unwrap() {
+            ServerPlayPacket::EntityEffect(packet) => {
+                assert_eq!(packet.effect_id, 1);
+                assert_eq!(packet.amplifier, 1);
+                assert_eq!(packet.duration, 200);
+            }
+            packet => panic!("unexpected packet {:?}", packet),
+        }
+
+        // Ticking down doesn't resend the effect
+        {
+            let mut effects = game.ecs.get_mut::<StatusEffects>(entity).unwrap();
+            effects.remove(StatusEffect::Speed);
+            effects.add(StatusEffectInstance::new(StatusEffect::Speed, 1, 150));
+        }
+        send_status_effect_changes(&mut game, &mut server).unwrap();
+        assert!(steve.sent_packets.try_recv().is_err());
+
+        game.ecs
+            .get_mut::<StatusEffects>(entity)
+            .unwrap()
+            .remove(StatusEffect::Speed);
+        send_status_effect_changes(&mut game, &mut server).unwrap();
+        match steve.sent_packets.try_recv().unwrap() {
+            ServerPlayPacket::RemoveEntityEffect(packet) => assert_eq!(packet.effect_id, 1),
+            packet => panic!("unexpected packet {:?}", packet),
+        }
+    }
+}
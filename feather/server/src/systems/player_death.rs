@@ -0,0 +1,76 @@
+//! Updates the inventories of players who died.
+
+use common::{events::PlayerDeathEvent, Game, Window};
+use ecs::{SysResult, SystemExecutor};
+
+use crate::{ClientId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(send_dropped_inventories);
+}
+
+/// Empties the window of players who dropped
+/// their inventory on death.
+fn send_dropped_inventories(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (event, &client_id, window)) in game
+        .ecs
+        .query::<(&PlayerDeathEvent, &ClientId, &Window)>()
+        .iter()
+    {
+        if event.keep_inventory {
+            continue;
+        }
+        if let Some(client) = server.clients.get(client_id) {
+            client.send_window_items(window);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use base::Inventory;
+    use common::window::BackingWindow;
+    use protocol::ServerPlayPacket;
+
+    use super::*;
+
+    #[test]
+    fn dropped_inventory_is_sent() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        let steve = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        let window = Window::new(BackingWindow::Player {
+            player: Inventory::player(),
+        });
+        let player = game.ecs.spawn((
+            steve.id,
+            window,
+            PlayerDeathEvent {
+                keep_inventory: true,
+            },
+        ));
+        steve.sent_packets.drain();
+
+        send_dropped_inventories(&mut game, &mut server).unwrap();
+        assert!(steve.sent_packets.try_recv().is_err());
+
+        game.ecs
+            .insert(
+                player,
+                PlayerDeathEvent {
+                    keep_inventory: false,
+                },
+            )
+            .unwrap();
+        send_dropped_inventories(&mut game, &mut server).unwrap();
+        assert!(matches!(
+            steve.sent_packets.try_recv().unwrap(),
+            ServerPlayPacket::WindowItems(_)
+        ));
+    }
+}
@@ -1,15 +1,15 @@
-use base::{Inventory, Position, Text};
+use base::{Difficulty, GameRules, Inventory, Text};
 use common::{
     chat::{ChatKind, ChatPreference},
     entities::player::HotbarSlot,
     view::View,
     window::BackingWindow,
-    ChatBox, Game, Window,
+    ChatBox, Game, Level, Window, WorldBorder,
 };
 use ecs::{SysResult, SystemExecutor};
 use quill_common::{components::Name, entity_init::EntityInit};
 
-use crate::{ClientId, Server};
+use crate::{entities::PreviousEquipment, ClientId, Server};
 
 pub fn register(systems: &mut SystemExecutor<Game>) {
     systems.group::<Server>().add_system(poll_new_players);
@@ -26,10 +26,21 @@ fn poll_new_players(game: &mut Game, server: &mut Server) -> SysResult {
 
 fn accept_new_player(game: &mut Game, server: &mut Server, client_id: ClientId) -> SysResult {
     let client = server.clients.get(client_id).unwrap();
-    client.send_join_game(server.options.default_gamemode);
+    client.send_join_game(
+        server.options.default_gamemode,
+        &*game.resources.get::<GameRules>()?,
+    );
     client.send_brand();
+    client.send_difficulty(*game.resources.get::<Difficulty>()?);
 
-    let mut builder = game.create_entity_builder(Position::default(), EntityInit::Player);
+    let (spawn, spawn_position) = {
+        let level = game.resources.get::<Level>()?;
+        (level.spawn(), level.spawn_position())
+    };
+    client.send_spawn_position(spawn);
+    client.send_world_border(&*game.resources.get::<WorldBorder>()?);
+
+    let mut builder = game.create_entity_builder(spawn_position, EntityInit::Player);
 
     let inventory = Inventory::player();
     let window = Window::new(BackingWindow::Player {
@@ -42,7 +53,7 @@ fn accept_new_player(game: &mut Game, server: &mut Server, client_id: ClientId)
         .add(client.network_id())
         .add(client_id)
         .add(View::new(
-            Position::default().chunk(),
+            spawn_position.chunk(),
             server.options.view_distance,
         ))
         .add(server.options.default_gamemode)
@@ -52,7 +63,8 @@ fn accept_new_player(game: &mut Game, server: &mut Server, client_id: ClientId)
         .add(ChatBox::new(ChatPreference::All))
         .add(inventory)
         .add(window)
-        .add(HotbarSlot::default());
+        .add(HotbarSlot::default())
+        .add(PreviousEquipment::default());
 
     game.spawn_entity(builder);
 
@@ -65,3 +77,37 @@ fn broadcast_player_join(game: &mut Game, username: &str) {
     let message = Text::translate_with("multiplayer.player.joined", vec![username.to_owned()]);
     game.broadcast_chat(ChatKind::System, message);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use base::{position, BlockPosition, Position};
+    use protocol::ServerPlayPacket;
+
+    use super::*;
+
+    #[test]
+    fn new_players_appear_at_world_spawn() {
+        let mut game = Game::new();
+        let mut server = Server::for_testing();
+        game.resources
+            .get_mut::<Level>()
+            .unwrap()
+            .set_spawn(BlockPosition::new(10, 70, -5));
+
+        let client = server.connect_test_client("Steve", Ipv4Addr::LOCALHOST.into());
+        accept_new_player(&mut game, &mut server, client.id).unwrap();
+
+        let (_, &position) = game.ecs.query::<&Position>().iter().next().unwrap();
+        assert_eq!(position, position!(10.5, 70.0, -4.5));
+        let spawn_packet = client
+            .sent_packets
+            .try_iter()
+            .find_map(|packet| match packet {
+                ServerPlayPacket::SpawnPosition(packet) => Some(packet.position),
+                _ => None,
+            });
+        assert_eq!(spawn_packet, Some(BlockPosition::new(10, 70, -5)));
+    }
+}
@@ -0,0 +1,65 @@
+//! Utilities for testing `Server` functionality
+//! without binding to a port.
+
+use std::{net::IpAddr, sync::Arc};
+
+use parking_lot::RwLock;
+
+use flume::{Receiver, Sender};
+use protocol::{ClientPlayPacket, ServerPlayPacket};
+use uuid::Uuid;
+
+use crate::{
+    ban_list::BanList,
+    config::{Config, DEFAULT_CONFIG},
+    initial_handler::NewPlayer,
+    player_count::PlayerCount,
+    ClientId, Options, Server,
+};
+
+/// Gets the `Options` for the default config.
+pub fn default_options() -> Options {
+    let config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+    config.to_options()
+}
+
+/// A client connected to a test server.
+pub struct TestClient {
+    pub id: ClientId,
+    /// Packets sent to the client by the server.
+    pub sent_packets: Receiver<ServerPlayPacket>,
+    // Kept alive so that the client isn't considered disconnected.
+    _received_packets: Sender<ClientPlayPacket>,
+}
+
+impl Server {
+    /// Creates a server which doesn't listen for connections.
+    pub fn for_testing() -> Self {
+        let options = Arc::new(default_options());
+        let player_count = PlayerCount::new(options.max_players);
+        let (_, new_players) = flume::bounded(4);
+        let ban_list = Arc::new(RwLock::new(BanList::new()));
+        Self::new(options, player_count, ban_list, new_players)
+    }
+
+    /// Adds a client as if a player had just connected
+    /// from the given IP address.
+    pub fn connect_test_client(&mut self, username: &str, ip: IpAddr) -> TestClient {
+        let (received_packets_tx, received_packets) = flume::unbounded();
+        let (packets_to_send, sent_packets) = flume::unbounded();
+        let uuid = Uuid::from_u128(self.clients.iter().count() as u128 + 1);
+        let id = self.create_client(NewPlayer {
+            uuid,
+            username: username.to_owned(),
+            profile: Vec::new(),
+            ip,
+            received_packets,
+            packets_to_send,
+        });
+        TestClient {
+            id,
+            sent_packets,
+            _received_packets: received_packets_tx,
+        }
+    }
+}
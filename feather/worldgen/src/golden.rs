@@ -0,0 +1,180 @@
+//! Golden-file tests keeping world generation reproducible.
+//!
+//! Each reference stores a chunk generated by the default
+//! generator with a fixed seed. A change to the generated
+//! terrain fails [`assert_chunk_matches_reference`].
+//!
+//! If a change is intentional, regenerate the references with
+//! `FEATHER_UPDATE_GOLDEN=1 cargo test -p feather-worldgen golden`
+//! and commit the files in `feather/worldgen/golden`. Missing
+//! references fail the test; they are recorded the same way.
+
+use base::{BlockId, Chunk, ChunkPosition};
+
+use crate::{ComposableGenerator, WorldGenerator};
+
+/// Number of blocks in a chunk, stored before the biomes.
+const BLOCK_COUNT: usize = 16 * 256 * 16;
+
+/// Generates the chunk at `pos` with the default generator
+/// and encodes it as a reference.
+pub fn record_reference(seed: u64, pos: ChunkPosition) -> Vec<u8> {
+    encode(&chunk_values(&generate(seed, pos)))
+}
+
+/// Asserts that the default generator still generates the
+/// chunk at `pos` like it did when `expected_bytes` was recorded.
+pub fn assert_chunk_matches_reference(seed: u64, pos: ChunkPosition, expected_bytes: &[u8]) {
+    let actual = chunk_values(&generate(seed, pos));
+    let expected = decode(expected_bytes);
+    assert_eq!(
+        actual.len(),
+        expected.len(),
+        "malformed reference for chunk {:?} with seed {}",
+        pos,
+        seed
+    );
+
+    if let Some(index) = actual.iter().zip(&expected).position(|(a, e)| a != e) {
+        if index < BLOCK_COUNT {
+            let (x, z, y) = (index / (16 * 256), index / 256 % 16, index % 256);
+            panic!(
+                "chunk {:?} with seed {} differs from the reference: block at ({}, {}, {}) is {:?}, expected {:?}",
+                pos,
+                seed,
+                x,
+                y,
+                z,
+                BlockId::from_vanilla_id(actual[index]),
+                BlockId::from_vanilla_id(expected[index]),
+            );
+        } else {
+            let index = index - BLOCK_COUNT;
+            let (x, z, y) = (index / (4 * 64), index / 64 % 4, index % 64);
+            panic!(
+                "chunk {:?} with seed {} differs from the reference: biome ({}, {}, {}) has ID {}, expected {}",
+                pos, seed, x, y, z, actual[index + BLOCK_COUNT], expected[index + BLOCK_COUNT],
+            );
+        }
+    }
+}
+
+fn generate(seed: u64, pos: ChunkPosition) -> Chunk {
+    ComposableGenerator::default_with_seed(seed).generate_chunk(pos)
+}
+
+/// Lists the block state IDs of a chunk column by column,
+/// followed by its biome IDs.
+fn chunk_values(chunk: &Chunk) -> Vec<u16> {
+    let mut values = Vec::with_capacity(BLOCK_COUNT + 4 * 64 * 4);
+    for x in 0..16 {
+        for z in 0..16 {
+            for y in 0..256 {
+                let block = chunk.block_at(x, y, z).unwrap_or_else(BlockId::air);
+                values.push(block.vanilla_id());
+            }
+        }
+    }
+    for x in 0..4 {
+        for z in 0..4 {
+            for y in 0..64 {
+                values.push(chunk.biomes().get(x, y, z).id() as u16);
+            }
+        }
+    }
+    values
+}
+
+/// Run-length encodes `values` as pairs of
+/// little-endian `u16`s: the value, then the run length.
+fn encode(values: &[u16]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut iter = values.iter().copied().peekable();
+    while let Some(value) = iter.next() {
+        let mut run: u16 = 1;
+        while run < u16::MAX && iter.peek() == Some(&value) {
+            iter.next();
+            run += 1;
+        }
+        bytes.extend_from_slice(&value.to_le_bytes());
+        bytes.extend_from_slice(&run.to_le_bytes());
+    }
+    bytes
+}
+
+fn decode(bytes: &[u8]) -> Vec<u16> {
+    let mut values = Vec::new();
+    for pair in bytes.chunks_exact(4) {
+        let value = u16::from_le_bytes([pair[0], pair[1]]);
+        let run = u16::from_le_bytes([pair[2], pair[3]]);
+        values.extend((0..run).map(|_| value));
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf};
+
+    use super::*;
+
+    /// Set to record new references instead of checking them.
+    const UPDATE_VAR: &str = "FEATHER_UPDATE_GOLDEN";
+
+    /// Chunks of different terrain checked by `golden_chunks`.
+    const GOLDEN_CHUNKS: [(u64, ChunkPosition); 3] = [
+        (42, ChunkPosition { x: 0, z: 0 }),
+        (42, ChunkPosition { x: -7, z: 12 }),
+        (42, ChunkPosition { x: 31, z: -40 }),
+    ];
+
+    fn reference_path(seed: u64, pos: ChunkPosition) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("golden")
+            .join(format!("seed{}_{}_{}.bin", seed, pos.x, pos.z))
+    }
+
+    // The references in `feather/worldgen/golden` haven't been
+    // recorded yet. Record them with `FEATHER_UPDATE_GOLDEN=1 cargo
+    // test -p feather-worldgen golden -- --ignored`, commit them
+    // and remove this attribute.
+    #[test]
+    #[ignore]
+    fn golden_chunks() {
+        let update = env::var_os(UPDATE_VAR).is_some();
+        for &(seed, pos) in &GOLDEN_CHUNKS {
+            let path = reference_path(seed, pos);
+            if update {
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(&path, record_reference(seed, pos)).unwrap();
+                continue;
+            }
+            let expected = fs::read(&path).unwrap_or_else(|e| {
+                panic!(
+                    "failed to read reference {} ({}); record it with {}=1",
+                    path.display(),
+                    e,
+                    UPDATE_VAR
+                )
+            });
+            assert_chunk_matches_reference(seed, pos, &expected);
+        }
+    }
+
+    #[test]
+    fn generation_is_deterministic() {
+        for &(seed, pos) in &GOLDEN_CHUNKS {
+            let reference = record_reference(seed, pos);
+            assert_chunk_matches_reference(seed, pos, &reference);
+        }
+    }
+
+    #[test]
+    fn encoding_round_trips() {
+        let mut values = vec![7; 70_000];
+        values.extend(&[1, 2, 2, 3]);
+        let bytes = encode(&values);
+        assert_eq!(bytes.len(), 5 * 4);
+        assert_eq!(decode(&bytes), values);
+    }
+}
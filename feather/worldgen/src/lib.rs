@@ -9,6 +9,7 @@ mod biomes;
 mod composition;
 mod density_map;
 mod finishers;
+pub mod golden;
 pub mod noise;
 mod superflat;
 mod util;
@@ -1,82 +1,178 @@
 //! Data sourced from: <https://minecraft.gamepedia.com/Game_rule>
 
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
-/// All game rules.
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GameRules {
-    announce_advancements: bool,
-    command_block_output: bool,
-    disable_elytra_movement_check: bool,
-    disable_raids: bool,
-    do_daylight_cycle: bool,
-    do_entity_drops: bool,
-    do_fire_tick: bool,
-    do_insomnia: bool,
-    do_immediate_respawn: bool,
-    do_limited_crafting: bool,
-    do_mob_loot: bool,
-    do_mob_spawning: bool,
-    do_patrol_spawning: bool,
-    do_tile_drops: bool,
-    do_trader_spawning: bool,
-    do_weather_cycle: bool,
-    drowning_damage: bool,
-    fall_damage: bool,
-    fire_damage: bool,
-    forgive_dead_players: bool,
-    keep_inventory: bool,
-    log_admin_commands: bool,
-    max_command_chain_length: u32,
-    max_entity_cramming: u32,
-    mob_griefing: bool,
-    natural_regeneration: bool,
-    random_tick_speed: u32,
-    reduced_debug_info: bool,
-    send_command_feedback: bool,
-    show_death_messages: bool,
-    spawn_radius: u32,
-    spectators_generate_chunks: bool,
-    universal_anger: bool,
+macro_rules! game_rules {
+    {$($field:ident ($name:literal): $typ:ty = $default:expr),* $(,)?} => {
+        /// All game rules.
+        #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        pub struct GameRules {
+            $(
+                pub $field: $typ,
+            )*
+        }
+
+        impl Default for GameRules {
+            fn default() -> Self {
+                Self {
+                    $(
+                        $field: $default,
+                    )*
+                }
+            }
+        }
+
+        impl GameRules {
+            /// The names of all game rules, e.g. `keepInventory`.
+            pub const NAMES: &'static [&'static str] = &[$($name),*];
+
+            /// Gets the value of the game rule called `name`.
+            pub fn get(&self, name: &str) -> Option<GameRuleValue> {
+                match name {
+                    $(
+                        $name => Some(GameRuleType::to_value(self.$field)),
+                    )*
+                    _ => None,
+                }
+            }
+
+            /// Sets the game rule called `name` from its string form,
+            /// as stored in `level.dat`. Returns the new value.
+            pub fn set(&mut self, name: &str, value: &str) -> Result<GameRuleValue, GameRuleError> {
+                match name {
+                    $(
+                        $name => {
+                            self.$field = GameRuleType::parse(value).ok_or_else(|| {
+                                GameRuleError::InvalidValue {
+                                    rule: name.to_owned(),
+                                    value: value.to_owned(),
+                                    expected: <$typ as GameRuleType>::TYPE_NAME,
+                                }
+                            })?;
+                            Ok(GameRuleType::to_value(self.$field))
+                        }
+                    )*
+                    _ => Err(GameRuleError::UnknownRule(name.to_owned())),
+                }
+            }
+        }
+    };
+}
+
+game_rules! {
+    announce_advancements("announceAdvancements"): bool = true,
+    command_block_output("commandBlockOutput"): bool = true,
+    disable_elytra_movement_check("disableElytraMovementCheck"): bool = false,
+    disable_raids("disableRaids"): bool = false,
+    do_daylight_cycle("doDaylightCycle"): bool = true,
+    do_entity_drops("doEntityDrops"): bool = true,
+    do_fire_tick("doFireTick"): bool = true,
+    do_insomnia("doInsomnia"): bool = true,
+    do_immediate_respawn("doImmediateRespawn"): bool = false,
+    do_limited_crafting("doLimitedCrafting"): bool = false,
+    do_mob_loot("doMobLoot"): bool = true,
+    do_mob_spawning("doMobSpawning"): bool = true,
+    do_patrol_spawning("doPatrolSpawning"): bool = true,
+    do_tile_drops("doTileDrops"): bool = true,
+    do_trader_spawning("doTraderSpawning"): bool = true,
+    do_weather_cycle("doWeatherCycle"): bool = true,
+    drowning_damage("drowningDamage"): bool = true,
+    fall_damage("fallDamage"): bool = true,
+    fire_damage("fireDamage"): bool = true,
+    forgive_dead_players("forgiveDeadPlayers"): bool = true,
+    keep_inventory("keepInventory"): bool = false,
+    log_admin_commands("logAdminCommands"): bool = true,
+    max_command_chain_length("maxCommandChainLength"): u32 = 65536,
+    max_entity_cramming("maxEntityCramming"): u32 = 24,
+    mob_griefing("mobGriefing"): bool = true,
+    natural_regeneration("naturalRegeneration"): bool = true,
+    random_tick_speed("randomTickSpeed"): u32 = 3,
+    reduced_debug_info("reducedDebugInfo"): bool = false,
+    send_command_feedback("sendCommandFeedback"): bool = true,
+    show_death_messages("showDeathMessages"): bool = true,
+    spawn_radius("spawnRadius"): u32 = 10,
+    spectators_generate_chunks("spectatorsGenerateChunks"): bool = true,
+    universal_anger("universalAnger"): bool = false,
+}
+
+/// The value of a single game rule.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GameRuleValue {
+    Bool(bool),
+    Int(u32),
+}
+
+impl fmt::Display for GameRuleValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameRuleValue::Bool(value) => value.fmt(f),
+            GameRuleValue::Int(value) => value.fmt(f),
+        }
+    }
+}
+
+/// An error returned by [`GameRules::set`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameRuleError {
+    UnknownRule(String),
+    InvalidValue {
+        rule: String,
+        value: String,
+        expected: &'static str,
+    },
 }
 
-impl Default for GameRules {
-    fn default() -> Self {
-        Self {
-            announce_advancements: true,
-            command_block_output: true,
-            disable_elytra_movement_check: false,
-            disable_raids: false,
-            do_daylight_cycle: true,
-            do_entity_drops: true,
-            do_fire_tick: true,
-            do_insomnia: true,
-            do_immediate_respawn: false,
-            do_limited_crafting: false,
-            do_mob_loot: true,
-            do_mob_spawning: true,
-            do_patrol_spawning: true,
-            do_tile_drops: true,
-            do_trader_spawning: true,
-            do_weather_cycle: true,
-            drowning_damage: true,
-            fall_damage: true,
-            fire_damage: true,
-            forgive_dead_players: true,
-            keep_inventory: false,
-            log_admin_commands: true,
-            max_command_chain_length: 65536,
-            max_entity_cramming: 24,
-            mob_griefing: true,
-            natural_regeneration: true,
-            random_tick_speed: 3,
-            reduced_debug_info: false,
-            send_command_feedback: true,
-            show_death_messages: true,
-            spawn_radius: 10,
-            spectators_generate_chunks: true,
-            universal_anger: false,
+impl fmt::Display for GameRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameRuleError::UnknownRule(rule) => write!(f, "unknown game rule '{}'", rule),
+            GameRuleError::InvalidValue {
+                rule,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid value '{}' for game rule '{}' (expected {})",
+                value, rule, expected
+            ),
         }
     }
 }
+
+impl std::error::Error for GameRuleError {}
+
+/// A type a game rule can have.
+trait GameRuleType: Sized {
+    const TYPE_NAME: &'static str;
+
+    fn parse(s: &str) -> Option<Self>;
+
+    fn to_value(self) -> GameRuleValue;
+}
+
+impl GameRuleType for bool {
+    const TYPE_NAME: &'static str = "a boolean";
+
+    fn parse(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
+
+    fn to_value(self) -> GameRuleValue {
+        GameRuleValue::Bool(self)
+    }
+}
+
+impl GameRuleType for u32 {
+    const TYPE_NAME: &'static str = "an integer";
+
+    fn parse(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
+
+    fn to_value(self) -> GameRuleValue {
+        GameRuleValue::Int(self)
+    }
+}
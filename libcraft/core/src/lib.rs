@@ -3,6 +3,7 @@
 mod biome;
 pub mod block;
 mod consts;
+mod difficulty;
 mod dimension;
 mod entity;
 mod gamemode;
@@ -13,10 +14,11 @@ mod positions;
 
 pub use biome::Biome;
 pub use consts::*;
+pub use difficulty::Difficulty;
 pub use dimension::Dimension;
 pub use entity::EntityKind;
 pub use gamemode::Gamemode;
-pub use gamerules::GameRules;
+pub use gamerules::{GameRuleError, GameRuleValue, GameRules};
 pub use interaction::InteractionType;
 pub use player::Hand;
 pub use positions::{